@@ -0,0 +1,43 @@
+// Draws textured geometry. The vertex shader applies a uniform matrix transformation to each
+// position, and the fragment shader uses the interpolated texture coordinate to sample from a
+// texture uniform, multiplying the result by a uniform tint color.
+
+struct VertexOutput {
+    [[location(0)]] texture_coord: vec2<f32>;
+    [[builtin(position)]] position: vec4<f32>;
+};
+
+struct Transform {
+    transform: mat4x4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> transform: Transform;
+[[group(0), binding(1)]]
+var background_texture: texture_2d<f32>;
+[[group(0), binding(2)]]
+var texture_sampler: sampler;
+
+struct Tint {
+    color: vec4<f32>;
+};
+
+[[group(0), binding(3)]]
+var<uniform> tint: Tint;
+
+[[stage(vertex)]]
+fn vs_main(
+    // Only the x and y components of the position are used for a 2D interface.
+    [[location(0)]] position: vec4<f32>,
+    [[location(1)]] texture_coord: vec2<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.texture_coord = texture_coord;
+    out.position = transform.transform * vec4<f32>(position.xy, 0.0, 1.0);
+    return out;
+}
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    return textureSample(background_texture, texture_sampler, in.texture_coord) * tint.color;
+}
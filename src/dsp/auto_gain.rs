@@ -0,0 +1,136 @@
+//! Measures the loudness of the input, to automatically choose a gain that brings it to a target
+//! level.
+//!
+//! Loudness is measured as the RMS level of every input channel combined, averaged over an
+//! exponentially weighted window. The chosen gain follows changes in loudness with separate attack
+//! and release times, so that it drops quickly when the input gets louder but recovers slowly
+//! afterwards, rather than "pumping" along with the music.
+
+/// Time over which the input level is averaged, in milliseconds.
+pub const RMS_WINDOW_MS: f32 = 300.;
+/// Time taken for the gain to fall about 63% of the way towards a lower value, in milliseconds.
+const ATTACK_MS: f32 = 50.;
+/// Time taken for the gain to rise about 63% of the way towards a higher value, in milliseconds.
+const RELEASE_MS: f32 = 1000.;
+/// Inputs quieter than this RMS level are treated as silence, for which the gain is left as it is
+/// rather than being raised to amplify noise.
+const SILENCE_RMS: f32 = 0.001;
+
+/// Chooses a gain that brings the RMS level of the input to a target level.
+pub(super) struct AutoGain {
+    /// Length of the averaging window, in milliseconds.
+    window_ms: f32,
+    sample_rate: f32,
+    /// Averaged square of the input samples.
+    mean_square: f32,
+    /// Target RMS level of the output.
+    target_rms: f32,
+    /// Most recently chosen gain.
+    gain: f32,
+    /// Highest gain that can be chosen.
+    max_gain: f32,
+}
+
+impl AutoGain {
+    /// Creates an `AutoGain` that averages the input level over `window_ms` milliseconds, and
+    /// chooses gains up to `max_gain`.
+    pub fn new(window_ms: f32, max_gain: f32) -> Self {
+        Self {
+            window_ms,
+            sample_rate: 44100.,
+            mean_square: 0.,
+            target_rms: 1.,
+            gain: 1.,
+            max_gain,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Sets the target RMS level of the output, in decibels relative to full scale.
+    pub fn set_target_db(&mut self, target_db: f32) {
+        self.target_rms = 10f32.powf(target_db / 20.);
+    }
+
+    /// Starts choosing gains from `gain`, like the manually selected gain when auto-gain is
+    /// enabled.
+    pub fn reset(&mut self, gain: f32) {
+        self.gain = gain.min(self.max_gain);
+    }
+
+    /// Measures the next `len` samples of each of `inputs`, starting at `start`, and returns the
+    /// gain that should be applied to them.
+    pub fn process(&mut self, inputs: &[&[f32]], start: usize, len: usize) -> f32 {
+        if inputs.is_empty() || len == 0 {
+            return self.gain;
+        }
+        let sum_of_squares: f32 = inputs
+            .iter()
+            .flat_map(|input| input[start..start + len].iter())
+            .map(|sample| sample * sample)
+            .sum();
+        let chunk_mean_square = sum_of_squares / (inputs.len() * len) as f32;
+        self.mean_square +=
+            (chunk_mean_square - self.mean_square) * self.coefficient(self.window_ms, len);
+
+        let rms = self.mean_square.sqrt();
+        if rms > SILENCE_RMS {
+            let desired_gain = (self.target_rms / rms).min(self.max_gain);
+            let time_ms = if desired_gain < self.gain {
+                ATTACK_MS
+            } else {
+                RELEASE_MS
+            };
+            self.gain += (desired_gain - self.gain) * self.coefficient(time_ms, len);
+        }
+        self.gain
+    }
+
+    /// Proportion of the distance to a new value to cover over `len` samples, for a one-pole
+    /// filter that covers about 63% of it in `time_ms` milliseconds.
+    fn coefficient(&self, time_ms: f32, len: usize) -> f32 {
+        1. - (-(len as f32) * 1000. / (time_ms * self.sample_rate)).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `seconds` of a sine wave with the given `peak` level through `auto_gain` in 16-sample
+    /// chunks, returning the final gain.
+    fn run_sine(auto_gain: &mut AutoGain, peak: f32, seconds: f32) -> f32 {
+        let sine: Vec<f32> = (0..(seconds * 44100.) as usize)
+            .map(|i| peak * (i as f32 * 0.05).sin())
+            .collect();
+        let mut gain = 0.;
+        for start in (0..sine.len()).step_by(16) {
+            gain = auto_gain.process(&[&sine], start, 16.min(sine.len() - start));
+        }
+        gain
+    }
+
+    #[test]
+    fn gain_reaches_target_level() {
+        let mut auto_gain = AutoGain::new(RMS_WINDOW_MS, 2.);
+        auto_gain.set_target_db(-20.);
+        let peak = 0.1;
+        let gain = run_sine(&mut auto_gain, peak, 10.);
+        let output_rms = peak * gain / 2f32.sqrt();
+        assert!((20. * output_rms.log10() + 20.).abs() < 0.1);
+
+        // Louder input is attenuated much faster than quieter input is boosted.
+        let attenuated = run_sine(&mut auto_gain, 0.2, 0.25);
+        assert!(attenuated < gain * 0.7);
+        let boosted = run_sine(&mut auto_gain, 0.1, 0.25);
+        assert!(boosted < gain * 0.8);
+
+        // Silence leaves the gain alone, once the averaged level has decayed, and gains are
+        // limited.
+        let held = run_sine(&mut auto_gain, 0., 5.);
+        assert_eq!(run_sine(&mut auto_gain, 0., 2.), held);
+        assert!((run_sine(&mut auto_gain, 0.01, 10.) - 2.).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,245 @@
+//! Reads the amplitude from a MIDI control change (CC) message.
+//!
+//! Messages are accepted on all 16 MIDI channels, for a single configurable controller number.
+//!
+//! With pickup enabled, the controller only takes over once it reaches the amplitude's current
+//! value. Until then, moving it has no effect, so that a physical control left in a different
+//! position doesn't make the amplitude jump. It has to be picked up again whenever the amplitude
+//! is changed by anything else, like the editor or host automation.
+//!
+//! Each message carries its position within the next processed block, so values are held here
+//! until the audio processing thread reaches them, rather than all being applied at the start of
+//! the block.
+
+use vst::api::Events;
+use vst::event::{Event, MidiEvent};
+
+/// Status byte (with the channel nibble cleared) of a MIDI control change message.
+const CONTROL_CHANGE: u8 = 0xb0;
+/// Controller number used until told otherwise: channel volume.
+pub const DEFAULT_CONTROLLER: u8 = 7;
+/// Values within half a controller step of the amplitude are close enough to pick it up.
+const PICKUP_TOLERANCE: f32 = 0.5 / 127.;
+/// Most values that can be held for a single block. Once full, later values replace the last
+/// one, so that the final value of the block is never lost.
+const MAX_PENDING: usize = 64;
+
+/// Tracks the value of the MIDI controller mapped to the amplitude.
+pub(super) struct AmplitudeController {
+    /// Controller number, from 0 to 127.
+    number: u8,
+    /// Whether the controller must reach the amplitude's value before taking over.
+    pickup_enabled: bool,
+    /// Whether the controller has taken over since the amplitude was last changed by anything
+    /// else. Always `true` while pickup is disabled.
+    picked_up: bool,
+    /// Most recently received value of the controller, from 0 to 1, whether or not it was applied.
+    last_received: Option<f32>,
+    /// Current value of the amplitude, from 0 to 1.
+    amplitude: f32,
+    /// Values to apply to the amplitude during the next processed block, in order, along with the
+    /// offset in samples into the block at which each applies. Only the first `num_pending`
+    /// entries are used, starting from `next_pending`.
+    pending: [(usize, f32); MAX_PENDING],
+    num_pending: usize,
+    next_pending: usize,
+}
+
+impl AmplitudeController {
+    pub fn new(amplitude: f32) -> Self {
+        Self {
+            number: DEFAULT_CONTROLLER,
+            pickup_enabled: false,
+            picked_up: true,
+            last_received: None,
+            amplitude,
+            pending: [(0, 0.); MAX_PENDING],
+            num_pending: 0,
+            next_pending: 0,
+        }
+    }
+
+    /// Maps a different controller number to the amplitude.
+    pub fn set_number(&mut self, number: u8) {
+        self.number = number & 0x7f;
+    }
+
+    /// Enables or disables pickup. Once enabled, the controller must reach the amplitude's value
+    /// before taking over.
+    pub fn set_pickup(&mut self, enabled: bool) {
+        self.pickup_enabled = enabled;
+        self.picked_up = !enabled;
+    }
+
+    /// Records the amplitude's new value, which has to be picked up again unless it was set by
+    /// the controller itself.
+    pub fn set_amplitude(&mut self, amplitude: f32) {
+        if amplitude != self.amplitude {
+            self.amplitude = amplitude;
+            self.picked_up = !self.pickup_enabled;
+        }
+    }
+
+    /// Reads the controller's values from a block of host events, holding those that should be
+    /// applied to the amplitude until they're reached by `take_due`. Returns the last of them,
+    /// from 0 to 1, or `None` if it wasn't changed or hasn't been picked up yet.
+    pub fn process_events(&mut self, events: &Events) -> Option<f32> {
+        let mut applied = None;
+        for event in events.events() {
+            if let Event::Midi(MidiEvent {
+                data, delta_frames, ..
+            }) = event
+            {
+                applied = self.receive(delta_frames.max(0) as usize, data).or(applied);
+            }
+        }
+        applied
+    }
+
+    /// Handles a single raw MIDI message, `offset` samples into the next block. Returns the new
+    /// value of the amplitude if the message changed it.
+    pub fn receive(&mut self, offset: usize, data: [u8; 3]) -> Option<f32> {
+        let value = self.take_over(self.process_midi(data)?)?;
+        // Hosts should send events in order, but later values must not be applied early if not.
+        let offset = match self.num_pending {
+            0 => offset,
+            num_pending => offset.max(self.pending[num_pending - 1].0),
+        };
+        if self.num_pending == MAX_PENDING {
+            self.pending[MAX_PENDING - 1] = (offset, value);
+        } else {
+            self.pending[self.num_pending] = (offset, value);
+            self.num_pending += 1;
+        }
+        Some(value)
+    }
+
+    /// Returns the latest held value that applies before `offset` samples into the block, or
+    /// `None` if there are none, and forgets every such value. Passing `usize::MAX` takes every
+    /// remaining value.
+    pub fn take_due(&mut self, offset: usize) -> Option<f32> {
+        let mut due = None;
+        while self.next_pending < self.num_pending && self.pending[self.next_pending].0 < offset {
+            due = Some(self.pending[self.next_pending].1);
+            self.next_pending += 1;
+        }
+        if self.next_pending == self.num_pending {
+            self.num_pending = 0;
+            self.next_pending = 0;
+        }
+        due
+    }
+
+    /// Moves every held value `samples` earlier, once the start of the block has been processed
+    /// separately from the rest.
+    pub fn advance(&mut self, samples: usize) {
+        for (offset, _) in &mut self.pending[self.next_pending..self.num_pending] {
+            *offset = offset.saturating_sub(samples);
+        }
+    }
+
+    /// The last value held for the next block, if there are any.
+    pub fn last_pending(&self) -> Option<f32> {
+        if self.next_pending < self.num_pending {
+            Some(self.pending[self.num_pending - 1].1)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `value` if it should be applied to the amplitude: if the controller has already
+    /// been picked up, or if it has reached or passed the amplitude since the previous value.
+    fn take_over(&mut self, value: f32) -> Option<f32> {
+        let previous = self.last_received.replace(value);
+        let crossed = previous
+            .is_some_and(|previous| (previous - self.amplitude) * (value - self.amplitude) <= 0.);
+        if self.picked_up || crossed || (value - self.amplitude).abs() <= PICKUP_TOLERANCE {
+            self.picked_up = true;
+            self.amplitude = value;
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the new value of the controller from a single raw MIDI message, from 0 to 1.
+    /// Messages for other controllers, and anything other than control changes, are ignored.
+    fn process_midi(&self, [status, number, value]: [u8; 3]) -> Option<f32> {
+        if status & 0xf0 == CONTROL_CHANGE && number == self.number {
+            Some((value & 0x7f) as f32 / 127.)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_mapped_controller_is_read() {
+        let mut controller = AmplitudeController::new(0.5);
+        assert_eq!(controller.process_midi([CONTROL_CHANGE, 7, 127]), Some(1.));
+        assert_eq!(
+            controller.process_midi([CONTROL_CHANGE | 9, 7, 0]),
+            Some(0.)
+        );
+        assert_eq!(controller.process_midi([CONTROL_CHANGE, 1, 64]), None);
+        // Note-on for note 7.
+        assert_eq!(controller.process_midi([0x90, 7, 100]), None);
+
+        controller.set_number(1);
+        assert_eq!(controller.process_midi([CONTROL_CHANGE, 7, 64]), None);
+        assert_eq!(controller.process_midi([CONTROL_CHANGE, 1, 127]), Some(1.));
+    }
+
+    #[test]
+    fn pickup_waits_for_controller_to_reach_amplitude() {
+        let mut controller = AmplitudeController::new(0.5);
+        controller.set_pickup(true);
+        assert_eq!(controller.take_over(0.1), None);
+        assert_eq!(controller.take_over(0.3), None);
+        // Passing over the amplitude picks it up, without having to land on it exactly.
+        assert_eq!(controller.take_over(0.6), Some(0.6));
+        assert_eq!(controller.take_over(0.2), Some(0.2));
+
+        // Values set by the controller itself don't need to be picked up again.
+        controller.set_amplitude(0.2);
+        assert_eq!(controller.take_over(0.3), Some(0.3));
+
+        // Other changes do.
+        controller.set_amplitude(0.8);
+        assert_eq!(controller.take_over(0.4), None);
+        assert_eq!(controller.take_over(0.8), Some(0.8));
+
+        // Without pickup, every value is applied straight away.
+        controller.set_pickup(false);
+        controller.set_amplitude(0.);
+        assert_eq!(controller.take_over(1.), Some(1.));
+    }
+
+    #[test]
+    fn values_are_held_until_their_offset() {
+        let mut controller = AmplitudeController::new(0.5);
+        assert_eq!(controller.receive(20, [CONTROL_CHANGE, 7, 0]), Some(0.));
+        assert_eq!(controller.receive(40, [CONTROL_CHANGE, 1, 0]), None);
+        // Out of order, so it's held back until after the previous value.
+        assert_eq!(controller.receive(10, [CONTROL_CHANGE, 7, 127]), Some(1.));
+        assert_eq!(controller.last_pending(), Some(1.));
+
+        assert_eq!(controller.take_due(16), None);
+        assert_eq!(controller.take_due(32), Some(1.));
+        assert_eq!(controller.last_pending(), None);
+        assert_eq!(controller.take_due(usize::MAX), None);
+
+        // The final value is kept even when too many arrive in one block.
+        for offset in 0..MAX_PENDING * 2 {
+            controller.receive(offset, [CONTROL_CHANGE, 7, offset as u8]);
+        }
+        assert_eq!(
+            controller.take_due(usize::MAX),
+            Some((MAX_PENDING * 2 - 1) as f32 / 127.)
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! Removes any DC offset from the output, which would otherwise be amplified along with the rest
+//! of the signal.
+//!
+//! Each channel is passed through a first-order high-pass filter,
+//! `y[n] = x[n] - x[n-1] + r * y[n-1]`, whose pole `r` sits just inside the unit circle. This
+//! blocks 0Hz entirely while leaving audible frequencies essentially untouched.
+
+use super::MAX_CHANNELS;
+
+/// Frequency below which the signal is attenuated, in Hz.
+const CUTOFF_HZ: f32 = 20.;
+/// The filter's tail is considered finished once it has decayed by this factor, or 60dB.
+const TAIL_DECAY: f32 = 1000.;
+
+/// Filter state of a single channel.
+#[derive(Clone, Copy, Default)]
+struct ChannelState {
+    /// Previous input sample.
+    x_prev: f32,
+    /// Previous output sample.
+    y_prev: f32,
+}
+
+/// DC-blocking high-pass filter, applied independently to each channel.
+pub(super) struct DcBlocker {
+    channels: [ChannelState; MAX_CHANNELS],
+    /// Feedback coefficient `r`, determined by the cutoff frequency and sample rate.
+    pole: f32,
+}
+
+impl DcBlocker {
+    pub fn new() -> Self {
+        let mut blocker = Self {
+            channels: [ChannelState::default(); MAX_CHANNELS],
+            pole: 0.,
+        };
+        blocker.set_sample_rate(44100.);
+        blocker
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.pole = (-2. * std::f32::consts::PI * CUTOFF_HZ / sample_rate).exp();
+    }
+
+    /// Forgets previous samples, so that stale state doesn't cause a click when the filter is
+    /// enabled again.
+    pub fn reset(&mut self) {
+        self.channels = [ChannelState::default(); MAX_CHANNELS];
+    }
+
+    /// Number of samples taken for the filter's response to an impulse to die away.
+    pub fn tail_samples(&self) -> usize {
+        (TAIL_DECAY.ln() / -self.pole.ln()).ceil() as usize
+    }
+
+    /// Filters the next `sample` of the given `channel`.
+    pub fn process(&mut self, channel: usize, sample: f32) -> f32 {
+        let state = &mut self.channels[channel];
+        let output = sample - state.x_prev + self.pole * state.y_prev;
+        state.x_prev = sample;
+        state.y_prev = output;
+        output
+    }
+}
@@ -0,0 +1,90 @@
+//! Follows the peak level of the input, for use as a modulation source by other plugins.
+//!
+//! The follower jumps up to new peaks immediately, and falls exponentially afterwards with an
+//! adjustable release time. When built with the `envelope-output` feature, the envelope is written
+//! to an extra output channel after the processed audio.
+
+/// Number of output channels used for the envelope, after those used for audio.
+pub const ENVELOPE_OUTPUTS: usize = if cfg!(feature = "envelope-output") {
+    1
+} else {
+    0
+};
+/// Release time used until told otherwise, in milliseconds.
+pub const DEFAULT_ENVELOPE_RELEASE_MS: f32 = 100.;
+
+/// Peak follower with an instant attack and exponential release.
+pub(super) struct EnvelopeFollower {
+    sample_rate: f32,
+    /// Time taken for the envelope to fall about 63% of the way towards silence, in milliseconds.
+    release_ms: f32,
+    /// Factor by which the envelope is multiplied with each sample, while it's falling.
+    release_factor: f32,
+    /// Current level of the envelope.
+    level: f32,
+}
+
+impl EnvelopeFollower {
+    pub fn new() -> Self {
+        let mut follower = Self {
+            sample_rate: 44100.,
+            release_ms: DEFAULT_ENVELOPE_RELEASE_MS,
+            release_factor: 0.,
+            level: 0.,
+        };
+        follower.update_release_factor();
+        follower
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update_release_factor();
+    }
+
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        self.release_ms = release_ms;
+        self.update_release_factor();
+    }
+
+    fn update_release_factor(&mut self) {
+        let release_samples = self.release_ms / 1000. * self.sample_rate;
+        // A release time of 0 follows the input's peaks exactly.
+        self.release_factor = if release_samples > 0. {
+            (-1. / release_samples).exp()
+        } else {
+            0.
+        };
+    }
+
+    /// Advances the envelope by one sample, whose peak level across every channel is `peak`, and
+    /// returns its new level.
+    pub fn process(&mut self, peak: f32) -> f32 {
+        self.level = peak.max(self.level * self.release_factor);
+        self.level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope_holds_peaks_and_releases() {
+        let mut follower = EnvelopeFollower::new();
+        follower.set_sample_rate(1000.);
+        follower.set_release_ms(10.);
+        assert_eq!(follower.process(0.5), 0.5);
+        assert_eq!(follower.process(1.), 1.);
+
+        // After one release time, the envelope has fallen about 63% of the way.
+        let mut level = 0.;
+        for _ in 0..10 {
+            level = follower.process(0.);
+        }
+        assert!((level - (-1f32).exp()).abs() < 1e-3);
+
+        follower.set_release_ms(0.);
+        assert_eq!(follower.process(0.25), 0.25);
+        assert_eq!(follower.process(0.), 0.);
+    }
+}
@@ -0,0 +1,108 @@
+//! Matches the loudness of the dry signal to the processed signal, so that comparing the two by
+//! turning down the mix isn't biased by a difference in level.
+//!
+//! Both signals are measured as the RMS level of every channel combined, averaged over an
+//! exponentially weighted window. The ratio between them is the average gain applied by
+//! processing, which is then applied to the dry signal.
+
+use super::auto_gain::RMS_WINDOW_MS;
+
+/// Dry signals quieter than this RMS level are treated as silence, during which the gain is left
+/// as it is.
+const SILENCE_RMS: f32 = 0.001;
+/// Highest gain that can be applied to the dry signal, to match the loudest possible output.
+const MAX_GAIN: f32 = 32.;
+
+/// Measures the average gain applied by processing, to apply it to the dry signal as well.
+pub(super) struct LoudnessMatch {
+    sample_rate: f32,
+    /// Averaged square of the dry samples.
+    dry_mean_square: f32,
+    /// Averaged square of the processed samples.
+    wet_mean_square: f32,
+    /// Most recently measured gain.
+    gain: f32,
+}
+
+impl LoudnessMatch {
+    pub fn new() -> Self {
+        Self {
+            sample_rate: 44100.,
+            dry_mean_square: 0.,
+            wet_mean_square: 0.,
+            gain: 1.,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Forgets any previous measurements, starting again from unity gain.
+    pub fn reset(&mut self) {
+        self.dry_mean_square = 0.;
+        self.wet_mean_square = 0.;
+        self.gain = 1.;
+    }
+
+    /// Measures the next `len` samples of each of `dry` and `wet`, starting at `start`, and
+    /// returns the gain that should be applied to the dry samples to match the wet samples.
+    pub fn process(&mut self, dry: &[&[f32]], wet: &[&mut [f32]], start: usize, len: usize) -> f32 {
+        if dry.is_empty() || len == 0 {
+            return self.gain;
+        }
+        let coefficient = 1. - (-(len as f32) * 1000. / (RMS_WINDOW_MS * self.sample_rate)).exp();
+        let num_samples = (dry.len() * len) as f32;
+        let dry_sum: f32 = dry
+            .iter()
+            .flat_map(|input| input[start..start + len].iter())
+            .map(|sample| sample * sample)
+            .sum();
+        let wet_sum: f32 = wet
+            .iter()
+            .flat_map(|output| output[start..start + len].iter())
+            .map(|sample| sample * sample)
+            .sum();
+        self.dry_mean_square += (dry_sum / num_samples - self.dry_mean_square) * coefficient;
+        self.wet_mean_square += (wet_sum / num_samples - self.wet_mean_square) * coefficient;
+
+        if self.dry_mean_square.sqrt() > SILENCE_RMS {
+            self.gain = (self.wet_mean_square / self.dry_mean_square)
+                .sqrt()
+                .min(MAX_GAIN);
+        }
+        self.gain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gain_matches_processed_level() {
+        let mut loudness_match = LoudnessMatch::new();
+        let dry: Vec<f32> = (0..44100).map(|i| 0.1 * (i as f32 * 0.05).sin()).collect();
+        let mut wet: Vec<f32> = dry.iter().map(|sample| sample * 0.5).collect();
+        let mut gain = 1.;
+        for start in (0..dry.len()).step_by(16) {
+            let len = 16.min(dry.len() - start);
+            gain = loudness_match.process(&[&dry], &[&mut wet], start, len);
+        }
+        assert!((gain - 0.5).abs() < 1e-3);
+
+        // Silence leaves the gain alone.
+        let silence = vec![0.; 16];
+        let mut silent_wet = vec![0.; 16];
+        for _ in 0..10000 {
+            gain = loudness_match.process(&[&silence], &[&mut silent_wet], 0, 16);
+        }
+        assert!((gain - 0.5).abs() < 1e-3);
+
+        loudness_match.reset();
+        assert_eq!(
+            loudness_match.process(&[&silence], &[&mut silent_wet], 0, 16),
+            1.
+        );
+    }
+}
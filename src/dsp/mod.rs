@@ -6,41 +6,627 @@
 //! struct to ensure that parameters are consistently and efficiently interpolated while minimizing
 //! the number of messages passed.
 
-use crate::plugin_state::StateUpdate;
-use std::sync::mpsc::Receiver;
+use crate::parameters::AMPLITUDE_DEFAULT;
+use crate::plugin_state::{EditorNotifier, LatestValue, StateUpdate};
+use std::ops::Range;
+use std::sync::mpsc::{Receiver, SyncSender};
+
+mod auto_gain;
+use auto_gain::{AutoGain, RMS_WINDOW_MS};
+
+mod controller;
+use controller::AmplitudeController;
+pub use controller::DEFAULT_CONTROLLER;
+
+mod dc_block;
+use dc_block::DcBlocker;
+
+mod envelope;
+use envelope::EnvelopeFollower;
+pub use envelope::{DEFAULT_ENVELOPE_RELEASE_MS, ENVELOPE_OUTPUTS};
+
+mod loudness_match;
+use loudness_match::LoudnessMatch;
+
+mod note_gate;
+use note_gate::HeldNotes;
+
+mod oversample;
+use oversample::Oversampler;
+pub use oversample::{oversampling_latency, MAX_OVERSAMPLING};
+
+mod scope;
+use scope::ScopeCapture;
+pub use scope::SCOPE_BATCH_LEN;
 
 mod smoothed;
 use smoothed::SmoothedRange;
+pub use smoothed::DEFAULT_SMOOTHING_TIME_MS;
+
+use vst::{api::Events, buffer::AudioBuffer};
+
+/// Maximum number of channels processed by `PluginDsp::process`.
+const MAX_CHANNELS: usize = 8;
+/// Length of the scratch buffers used by `PluginDsp::process_f64` until the host reports the
+/// largest block it will process.
+const DEFAULT_SCRATCH_LEN: usize = 1024;
+
+/// Smoothing must have stopped for this long before the editor is told that it has settled, so
+/// that brief pauses in a knob drag don't make the readout flicker.
+const SMOOTHING_SETTLE_TIME_MS: f32 = 100.;
+
+/// Time taken for the mute to fade the output about 63% of the way in or out, in milliseconds.
+/// This is just long enough to avoid an audible click.
+const MUTE_FADE_MS: f32 = 5.;
 
-use vst::buffer::AudioBuffer;
+/// Time taken to fade the output in when processing starts or resumes, in milliseconds. Like the
+/// mute's fade, this is just long enough to avoid an audible click.
+const STARTUP_RAMP_MS: f32 = 5.;
+
+/// Gain applied to the sum of the left and right channels while summing to mono. This is -6dB, so
+/// that identical channels keep their level.
+const MONO_SUM_GAIN: f32 = 0.5;
+
+/// Gain applied at the top of the amplitude parameter's range.
+const MAX_AMPLITUDE: f32 = 2.;
+
+/// Make-up gain applied at the top of the make-up gain parameter's range, in decibels.
+pub const MAX_MAKEUP_GAIN_DB: f32 = 24.;
+
+/// Largest boost or cut that the output trim can apply, in decibels.
+pub const MAX_OUTPUT_TRIM_DB: f32 = 12.;
 
 /// Handles all audio processing algorithms for the plugin.
 pub(super) struct PluginDsp {
     amplitude_range: SmoothedRange,
     amplitude: f32,
+    /// Value of the amplitude knob, from 0 to 1. This is the target of `amplitude_range`, except
+    /// while auto-gain is enabled.
+    manual_amplitude: f32,
+
+    /// Chooses the amplitude while enabled, to bring the input to a target loudness.
+    auto_gain: AutoGain,
+    auto_gain_enabled: bool,
+
+    /// Gain of each individual channel, from 0 to 2, applied along with the amplitude. One range
+    /// is kept for each of `MAX_CHANNELS`, so that no allocation is needed while processing.
+    channel_gain_ranges: Vec<SmoothedRange>,
+    channel_gains: [f32; MAX_CHANNELS],
+    /// Bit mask of the channels whose polarity is inverted along with their gain, with the lowest
+    /// bit for the first channel.
+    phase_invert_mask: u8,
+
+    /// Additional gain applied after the amplitude, as a fraction of `MAX_MAKEUP_GAIN_DB`.
+    makeup_gain_range: SmoothedRange,
+    makeup_gain_db: f32,
+
+    /// Stereo width, applied using mid/side processing. A width of 0 collapses the signal to mono,
+    /// 1 leaves it unchanged, and 2 doubles the level of the side signal.
+    width_range: SmoothedRange,
+    width: f32,
+
+    /// Stereo position, from 0 (hard left) to 1 (hard right), applied with a constant-power law.
+    pan_range: SmoothedRange,
+    pan: f32,
+
+    /// Proportion of the processed ("wet") signal in the output, with the remainder taken from the
+    /// unprocessed ("dry") input.
+    mix_range: SmoothedRange,
+    mix: f32,
+
+    /// Proportion of both output channels replaced with their sum, crossfading between 0 (stereo)
+    /// and 1 (mono) over a single chunk whenever summing is toggled.
+    mono_sum_range: SmoothedRange,
+    mono_sum: f32,
+
+    /// Linear gain applied to the output after every other stage, for calibrating its level.
+    output_trim: f32,
+
+    /// Level applied by the MIDI note gate, fading between 0 (closed) and 1 (open) to avoid
+    /// clicks.
+    gate_range: SmoothedRange,
+    gate: f32,
+    held_notes: HeldNotes,
+
+    /// Level applied by the mute, fading between 0 (muted) and 1 (unmuted) independently of the
+    /// amplitude.
+    mute_range: SmoothedRange,
+    mute: f32,
+
+    /// Level applied while fading the output in after processing starts or resumes, rising
+    /// linearly from 0 to 1 over `STARTUP_RAMP_MS`.
+    startup_range: SmoothedRange,
+    startup: f32,
+    /// Whether the next processed block is the first since processing started or resumed, so that
+    /// the startup ramp should begin.
+    needs_startup_ramp: bool,
+
+    /// Samples processed in chunks shorter than 16 since smoothed parameters were last advanced
+    /// by one of them.
+    partial_chunk_samples: usize,
+
+    /// MIDI controller that sets the amplitude.
+    amplitude_controller: AmplitudeController,
+
+    /// Follows the peak level of the input, for the envelope output.
+    envelope: EnvelopeFollower,
+
+    /// Time taken by parameter smoothing, in milliseconds.
+    smoothing_time_ms: f32,
+    /// Time over which loading a preset crossfades to its parameters, in milliseconds.
+    preset_crossfade_ms: f32,
+    sample_rate: f32,
+
+    /// Removes any DC offset from the output while enabled.
+    dc_blocker: DcBlocker,
+    dc_block_enabled: bool,
+
+    /// Matches the loudness of the dry signal to the processed signal while enabled.
+    loudness_match: LoudnessMatch,
+    loudness_match_enabled: bool,
+    /// Gain applied to the dry signal at the end of the last chunk, from which the next chunk's
+    /// gain is interpolated.
+    dry_gain: f32,
+
+    /// Nonlinear stage applied to output samples to keep them from exceeding full scale.
+    saturation: SaturationMode,
+    /// Runs the saturation stage at a higher sample rate, to reduce aliasing.
+    oversampler: Oversampler,
+
+    /// Collects output samples for the editor's scope.
+    scope: ScopeCapture,
+    /// Single-precision copies of the input and output channels of blocks passed to
+    /// `process_f64`, which processes them in pieces of up to the length of these buffers.
+    scratch_inputs: Vec<Vec<f32>>,
+    scratch_outputs: Vec<Vec<f32>>,
+    /// Number of samples processed since a parameter was last being smoothed.
+    samples_since_smoothing: usize,
 
     messages_from_params: Receiver<StateUpdate>,
+    /// Time-critical updates, which are applied before any from `messages_from_params`.
+    priority_from_params: Receiver<StateUpdate>,
+    /// Most recent amplitude knob value, which is passed separately from other messages so that
+    /// rapid changes can be coalesced.
+    knob_from_params: LatestValue,
+    /// Used to report the output level and other measurements to the editor.
+    to_editor: EditorNotifier,
+    /// Hands the emptied vectors of `Batch` and `Crossfade` updates back to be freed off the
+    /// audio processing thread, since freeing memory can block.
+    retired_batches: SyncSender<Vec<StateUpdate>>,
 }
 
 impl PluginDsp {
-    pub fn new(incoming_messages: Receiver<StateUpdate>) -> Self {
-        Self {
-            amplitude_range: SmoothedRange::new(0.5),
-            amplitude: 1.,
+    pub fn new(
+        incoming_messages: Receiver<StateUpdate>,
+        incoming_priority_messages: Receiver<StateUpdate>,
+        incoming_knob: LatestValue,
+        to_editor: EditorNotifier,
+        retired_batches: SyncSender<Vec<StateUpdate>>,
+    ) -> Self {
+        let mut dsp = Self {
+            amplitude_range: SmoothedRange::new(AMPLITUDE_DEFAULT),
+            amplitude: AMPLITUDE_DEFAULT * MAX_AMPLITUDE,
+            manual_amplitude: AMPLITUDE_DEFAULT,
+
+            auto_gain: AutoGain::new(RMS_WINDOW_MS, MAX_AMPLITUDE),
+            auto_gain_enabled: false,
+
+            channel_gain_ranges: vec![SmoothedRange::new(0.5); MAX_CHANNELS],
+            channel_gains: [1.; MAX_CHANNELS],
+            phase_invert_mask: 0,
+
+            makeup_gain_range: SmoothedRange::new(0.),
+            makeup_gain_db: 0.,
+
+            width_range: SmoothedRange::new(0.5),
+            width: 1.,
+
+            pan_range: SmoothedRange::new(0.5),
+            pan: 0.5,
+
+            mix_range: SmoothedRange::new(1.),
+            mix: 1.,
+
+            mono_sum_range: SmoothedRange::new(0.),
+            mono_sum: 0.,
+
+            output_trim: 1.,
+
+            gate_range: SmoothedRange::new(1.),
+            gate: 1.,
+            held_notes: HeldNotes::new(),
+
+            mute_range: SmoothedRange::new(1.),
+            mute: 1.,
+
+            startup_range: SmoothedRange::new(0.),
+            startup: 0.,
+            needs_startup_ramp: true,
+
+            partial_chunk_samples: 0,
+
+            amplitude_controller: AmplitudeController::new(AMPLITUDE_DEFAULT),
+
+            envelope: EnvelopeFollower::new(),
+
+            smoothing_time_ms: DEFAULT_SMOOTHING_TIME_MS,
+            preset_crossfade_ms: 0.,
+            sample_rate: 44100.,
+
+            dc_blocker: DcBlocker::new(),
+            dc_block_enabled: false,
+
+            loudness_match: LoudnessMatch::new(),
+            loudness_match_enabled: false,
+            dry_gain: 1.,
+
+            saturation: SaturationMode::None,
+            oversampler: Oversampler::new(),
+
+            scope: ScopeCapture::new(),
+            scratch_inputs: vec![vec![0.; DEFAULT_SCRATCH_LEN]; MAX_CHANNELS],
+            scratch_outputs: vec![vec![0.; DEFAULT_SCRATCH_LEN]; MAX_CHANNELS + ENVELOPE_OUTPUTS],
+            samples_since_smoothing: usize::MAX,
 
             messages_from_params: incoming_messages,
+            priority_from_params: incoming_priority_messages,
+            knob_from_params: incoming_knob,
+            to_editor,
+            retired_batches,
+        };
+        dsp.update_smoothing_time();
+        dsp
+    }
+
+    /// Creates a `PluginDsp` without an editor, which receives updates from the returned `Sender`.
+    /// Nothing is sent through its priority channel.
+    #[cfg(test)]
+    pub fn headless() -> (Self, std::sync::mpsc::Sender<StateUpdate>) {
+        let (to_dsp, dsp_recv) = std::sync::mpsc::channel();
+        let dsp = Self::new(
+            dsp_recv,
+            std::sync::mpsc::channel().1,
+            LatestValue::new(),
+            EditorNotifier::detached(),
+            std::sync::mpsc::sync_channel(0).0,
+        );
+        (dsp, to_dsp)
+    }
+
+    /// Adapts time-based processing, like parameter smoothing, to a new sample rate.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.auto_gain.set_sample_rate(sample_rate);
+        self.dc_blocker.set_sample_rate(sample_rate);
+        self.loudness_match.set_sample_rate(sample_rate);
+        self.envelope.set_sample_rate(sample_rate);
+        self.update_smoothing_time();
+    }
+
+    /// Prepares for blocks of up to `max_block_size` samples. Allocating memory while processing
+    /// can cause dropouts, so any stage that needs scratch space should allocate it here, before
+    /// processing starts. Only `process_f64` needs any, to convert whole blocks at once.
+    pub fn set_block_size(&mut self, max_block_size: usize) {
+        for scratch in self
+            .scratch_inputs
+            .iter_mut()
+            .chain(&mut self.scratch_outputs)
+        {
+            if scratch.len() < max_block_size {
+                scratch.resize(max_block_size, 0.);
+            }
+        }
+    }
+
+    /// Prepares to fade the output in from silence when processing resumes, since the host may have
+    /// stopped it partway through a signal. The fade begins with the next processed block.
+    pub fn resume(&mut self) {
+        self.needs_startup_ramp = true;
+    }
+
+    /// Starts fading the output in from silence, if processing just started or resumed.
+    fn start_ramp_if_needed(&mut self) {
+        if !self.needs_startup_ramp {
+            return;
+        }
+        self.needs_startup_ramp = false;
+        // The ramp is linear rather than exponential, so that it reaches full level in a fixed
+        // number of chunks.
+        let num_updates = (STARTUP_RAMP_MS / 1000. * self.sample_rate / 16.).round() as usize;
+        self.startup_range = SmoothedRange::new(0.);
+        self.startup = 0.;
+        self.startup_range.set(1.);
+        self.startup_range.glide_linearly(num_updates.max(1));
+    }
+
+    /// Applies the current smoothing time and sample rate to every smoothed parameter.
+    fn update_smoothing_time(&mut self) {
+        // Smoothing is processed once per 16-sample chunk.
+        let update_rate = self.sample_rate / 16.;
+        for range in self.channel_gain_ranges.iter_mut().chain([
+            &mut self.amplitude_range,
+            &mut self.makeup_gain_range,
+            &mut self.width_range,
+            &mut self.pan_range,
+            &mut self.mix_range,
+            &mut self.gate_range,
+        ]) {
+            range.set_smoothing_time(self.smoothing_time_ms, update_rate);
         }
+        // The mute fades at a fixed speed, regardless of the glide time.
+        self.mute_range
+            .set_smoothing_time(MUTE_FADE_MS, update_rate);
+    }
+
+    /// Number of samples by which the processed output lags behind its input. Any stage that
+    /// buffers audio should be accounted for here.
+    pub fn latency_samples(&self) -> usize {
+        self.oversampler.latency_samples()
+    }
+
+    /// Number of samples for which the output can continue after the input falls silent. Gain
+    /// changes and saturation have no tail of their own, but stages with memory, like filters and
+    /// delays, should be accounted for here.
+    pub fn tail_samples(&self) -> usize {
+        let dc_block_tail = if self.dc_block_enabled {
+            self.dc_blocker.tail_samples()
+        } else {
+            0
+        };
+        self.oversampler.tail_samples().max(dc_block_tail)
+    }
+
+    /// Hands the emptied vector of a `Batch` or `Crossfade` update back to the thread that sends
+    /// them, to be freed there. If too many are already waiting, it's freed here after all.
+    fn retire_batch(&self, updates: Vec<StateUpdate>) {
+        let _ = self.retired_batches.try_send(updates);
+    }
+
+    /// Applies a single state update to the audio processing algorithm.
+    fn apply_update(&mut self, message: StateUpdate) {
+        match message {
+            StateUpdate::SetKnob(v) => {
+                self.set_manual_amplitude(v);
+                self.amplitude_controller.set_amplitude(v);
+            }
+            StateUpdate::SetAutoGain(enabled) => {
+                if enabled && !self.auto_gain_enabled {
+                    self.auto_gain.reset(self.amplitude);
+                } else if !enabled {
+                    // The amplitude is smoothed back to the knob's value.
+                    self.amplitude_range.set(self.manual_amplitude);
+                }
+                self.auto_gain_enabled = enabled;
+            }
+            StateUpdate::SetAutoGainTarget(db) => self.auto_gain.set_target_db(db),
+            StateUpdate::SetAmplitudeController(number) => {
+                self.amplitude_controller.set_number(number)
+            }
+            StateUpdate::SetControllerPickup(enabled) => {
+                self.amplitude_controller.set_pickup(enabled)
+            }
+            StateUpdate::SetMakeupGain(v) => self.makeup_gain_range.set(v),
+            StateUpdate::SetChannelGain(channel, v) => {
+                if let Some(range) = self.channel_gain_ranges.get_mut(channel) {
+                    range.set(v);
+                }
+            }
+            StateUpdate::SetPhaseInvert(mask) => self.phase_invert_mask = mask,
+            StateUpdate::SetWidth(v) => self.width_range.set(v),
+            StateUpdate::SetPan(v) => self.pan_range.set(v),
+            StateUpdate::SetMix(v) => self.mix_range.set(v),
+            StateUpdate::SetMonoSum(enabled) => {
+                self.mono_sum_range.set(if enabled { 1. } else { 0. });
+                self.mono_sum_range.glide_linearly(1);
+            }
+            StateUpdate::SetEnvelopeRelease(ms) => self.envelope.set_release_ms(ms),
+            StateUpdate::SetPresetCrossfade(ms) => self.preset_crossfade_ms = ms,
+            StateUpdate::SetOutputTrim(db) => self.output_trim = 10f32.powf(db / 20.),
+            StateUpdate::SetDcBlock(enabled) => {
+                if enabled && !self.dc_block_enabled {
+                    self.dc_blocker.reset();
+                }
+                self.dc_block_enabled = enabled;
+            }
+            StateUpdate::SetLoudnessMatch(enabled) => {
+                if enabled && !self.loudness_match_enabled {
+                    self.loudness_match.reset();
+                }
+                self.loudness_match_enabled = enabled;
+            }
+            StateUpdate::SetSaturation(mode) => self.saturation = mode,
+            StateUpdate::SetOversampling(factor) => self.oversampler.set_factor(factor),
+            StateUpdate::SetGlideTime(ms) => {
+                self.smoothing_time_ms = ms;
+                self.update_smoothing_time();
+            }
+            StateUpdate::SetGate(open) => self.gate_range.set(if open { 1. } else { 0. }),
+            StateUpdate::SetMute(muted) => self.mute_range.set(if muted { 0. } else { 1. }),
+            StateUpdate::Batch(mut updates) => {
+                for update in updates.drain(..) {
+                    self.apply_update(update);
+                }
+                self.retire_batch(updates);
+            }
+            StateUpdate::Crossfade(mut updates) => {
+                // The crossfade time is the one in effect before the preset is loaded, since the
+                // preset includes its own.
+                let num_chunks =
+                    (self.preset_crossfade_ms / 1000. * self.sample_rate / 16.).round();
+                for update in updates.drain(..) {
+                    self.apply_update(update);
+                }
+                self.retire_batch(updates);
+                for range in self.channel_gain_ranges.iter_mut().chain([
+                    &mut self.amplitude_range,
+                    &mut self.makeup_gain_range,
+                    &mut self.width_range,
+                    &mut self.pan_range,
+                    &mut self.mix_range,
+                    &mut self.mono_sum_range,
+                ]) {
+                    range.glide_linearly(num_chunks as usize);
+                }
+            }
+            StateUpdate::MeterLevels(_)
+            | StateUpdate::SmoothingActive(_)
+            | StateUpdate::InputClipping(_)
+            | StateUpdate::ScopeSamples(_) => (),
+        }
+    }
+
+    /// Sets the amplitude knob's value, from 0 to 1, which is followed unless auto-gain is enabled.
+    fn set_manual_amplitude(&mut self, value: f32) {
+        self.manual_amplitude = value;
+        if !self.auto_gain_enabled {
+            self.amplitude_range.set(value);
+        }
+    }
+
+    /// Opens or closes the note gate in response to incoming MIDI notes. Audio passes while any
+    /// note is held on any channel, and fades to silence once all notes are released.
+    ///
+    /// Returns the most recent value of the amplitude's MIDI controller, if it was changed, so that
+    /// it can be passed to the rest of the plugin like any other parameter change. Each of the
+    /// controller's values is applied to the audio at its own position within the next block.
+    pub fn process_events(&mut self, events: &Events) -> Option<f32> {
+        let was_open = self.held_notes.gate_is_open();
+        self.held_notes.process_events(events);
+        let is_open = self.held_notes.gate_is_open();
+        if is_open != was_open {
+            self.apply_update(StateUpdate::SetGate(is_open));
+        }
+        self.amplitude_controller.process_events(events)
     }
 
     /// Applies any incoming state update events to the audio generation algorithm, and then writes
     /// processed audio into the output buffer.
     pub fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
-        // First, get any new changes to parameter ranges.
+        // Gather the channels into fixed-size arrays, to avoid allocating on the audio processing
+        // thread. Any channels beyond `MAX_CHANNELS` are left untouched.
+        let (inputs, mut outputs) = buffer.split();
+        let mut input_slices: [&[f32]; MAX_CHANNELS] = Default::default();
+        let mut output_slices: [&mut [f32]; MAX_CHANNELS] = Default::default();
+        let mut num_inputs = 0;
+        for (slot, input) in input_slices.iter_mut().zip(inputs) {
+            *slot = input;
+            num_inputs += 1;
+        }
+        let mut num_outputs = 0;
+        for (slot, output) in output_slices.iter_mut().zip(&mut outputs) {
+            *slot = output;
+            num_outputs += 1;
+        }
+        self.process_slices(
+            &input_slices[..num_inputs],
+            &mut output_slices[..num_outputs],
+        );
+    }
+
+    /// Like `process`, but for hosts that process audio in double precision.
+    pub fn process_f64(&mut self, buffer: &mut AudioBuffer<f64>) {
+        let (inputs, mut outputs) = buffer.split();
+        let mut input_slices: [&[f64]; MAX_CHANNELS] = Default::default();
+        let mut output_slices: [&mut [f64]; MAX_CHANNELS + ENVELOPE_OUTPUTS] = Default::default();
+        let mut num_inputs = 0;
+        for (slot, input) in input_slices.iter_mut().zip(inputs) {
+            *slot = input;
+            num_inputs += 1;
+        }
+        let mut num_outputs = 0;
+        for (slot, output) in output_slices.iter_mut().zip(&mut outputs) {
+            *slot = output;
+            num_outputs += 1;
+        }
+        self.process_slices_f64(
+            &input_slices[..num_inputs],
+            &mut output_slices[..num_outputs],
+        );
+    }
+
+    /// Like `process_slices`, but for samples in double precision. Samples are converted to single
+    /// precision for processing, through scratch buffers allocated ahead of time, so that the same
+    /// processing is used for both.
+    pub fn process_slices_f64(&mut self, inputs: &[&[f64]], outputs: &mut [&mut [f64]]) {
+        let num_inputs = inputs.len().min(self.scratch_inputs.len());
+        let num_outputs = outputs.len().min(self.scratch_outputs.len());
+        let num_samples = inputs[..num_inputs]
+            .iter()
+            .map(|input| input.len())
+            .chain(outputs[..num_outputs].iter().map(|output| output.len()))
+            .min()
+            .unwrap_or(0);
+        // The scratch buffers are moved out while in use, so that `process_slices` can borrow
+        // `self` mutably. Neither moving them nor putting them back allocates.
+        let mut scratch_inputs = std::mem::take(&mut self.scratch_inputs);
+        let mut scratch_outputs = std::mem::take(&mut self.scratch_outputs);
+        let scratch_len = scratch_inputs[0].len();
+
+        for start in (0..num_samples).step_by(scratch_len) {
+            let piece = start..num_samples.min(start + scratch_len);
+            let len = piece.len();
+            for (scratch, input) in scratch_inputs.iter_mut().zip(&inputs[..num_inputs]) {
+                for (converted, &sample) in scratch.iter_mut().zip(&input[piece.clone()]) {
+                    *converted = sample as f32;
+                }
+            }
+
+            let mut input_slices: [&[f32]; MAX_CHANNELS] = Default::default();
+            for (slot, scratch) in input_slices.iter_mut().zip(&scratch_inputs) {
+                *slot = &scratch[..len];
+            }
+            let mut output_slices: [&mut [f32]; MAX_CHANNELS + ENVELOPE_OUTPUTS] =
+                Default::default();
+            for (slot, scratch) in output_slices.iter_mut().zip(&mut scratch_outputs) {
+                *slot = &mut scratch[..len];
+            }
+            self.process_piece(
+                &input_slices[..num_inputs],
+                &mut output_slices[..num_outputs],
+                piece.end == num_samples,
+            );
+            // MIDI controller values are held relative to the start of the block, so the
+            // remaining ones have to be moved up to the start of the next piece.
+            self.amplitude_controller.advance(len);
+
+            for (output, scratch) in outputs[..num_outputs].iter_mut().zip(&scratch_outputs) {
+                for (sample, &processed) in output[piece.clone()].iter_mut().zip(scratch) {
+                    *sample = processed as f64;
+                }
+            }
+        }
+
+        self.scratch_inputs = scratch_inputs;
+        self.scratch_outputs = scratch_outputs;
+    }
+
+    /// Like `process`, but operates on plain slices of samples for each channel. Only as many
+    /// channels and samples as are present in both `inputs` and `outputs` are processed, up to
+    /// `MAX_CHANNELS` channels.
+    pub fn process_slices(&mut self, inputs: &[&[f32]], outputs: &mut [&mut [f32]]) {
+        self.process_piece(inputs, outputs, true);
+    }
+
+    /// Like `process_slices`, but for one piece of a block that's processed in several. Unless
+    /// the piece `ends_block`, values from the MIDI controller beyond its end are left for the
+    /// next piece.
+    fn process_piece(&mut self, inputs: &[&[f32]], outputs: &mut [&mut [f32]], ends_block: bool) {
+        // First, get any new changes to parameter ranges. Time-critical updates come first, so that
+        // they aren't held up behind a flood of others.
+        while let Ok(message) = self.priority_from_params.try_recv() {
+            self.apply_update(message);
+        }
         while let Ok(message) = self.messages_from_params.try_recv() {
-            match message {
-                StateUpdate::SetKnob(v) => self.amplitude_range.set(v),
+            self.apply_update(message);
+        }
+        if let Some(value) = self.knob_from_params.take() {
+            // The MIDI controller's last value in this block is passed back through the knob slot
+            // too, but shouldn't take effect until its place in the block.
+            if self.amplitude_controller.last_pending() != Some(value) {
+                self.apply_update(StateUpdate::SetKnob(value));
             }
         }
+        self.start_ramp_if_needed();
 
         // To take advantage of SIMD auto-vectorization, and for consistent parameter smoothing,
         // audio is processed in "chunks" of 16 samples at a time. The number of samples requested
@@ -49,47 +635,1261 @@ impl PluginDsp {
         //
         // This approach is overly complex for such a simple use-case, but can be particularly
         // useful for reducing unnecessary re-computation with many parameters.
-        let num_samples = buffer.samples();
-        let num_chunks = num_samples / 16;
-        let extra_samples = num_samples % 16;
-        let num_channels = buffer.input_count();
+        let num_channels = inputs.len().min(outputs.len()).min(MAX_CHANNELS);
+        let num_samples = inputs[..num_channels]
+            .iter()
+            .map(|input| input.len())
+            .chain(outputs[..num_channels].iter().map(|output| output.len()))
+            .min()
+            .unwrap_or(0);
 
-        let (inputs, mut outputs) = buffer.split();
-        for chunk_start in (0..num_chunks).map(|i| i * 16) {
+        self.report_input_clipping(&inputs[..num_channels], num_samples);
+
+        // While no parameter is changing, the gain stage can be applied to the whole block at once,
+        // rather than interpolating every parameter in each chunk. The output is identical either
+        // way.
+        let is_static =
+            self.amplitude_controller.last_pending().is_none() && self.is_static(num_channels);
+        if is_static {
+            self.apply_static_gain(inputs, outputs, num_channels, num_samples);
+        }
+
+        for chunk_start in (0..num_samples).step_by(16) {
+            let chunk_len = (num_samples - chunk_start).min(16);
+            let chunk = chunk_start..chunk_start + chunk_len;
+            // Values from the MIDI controller are applied at the start of the chunk they fall in.
+            if let Some(value) = self.amplitude_controller.take_due(chunk.end) {
+                self.set_manual_amplitude(value);
+            }
+            let parameters = if is_static {
+                self.static_parameters()
+            } else {
+                self.apply_chunk_gain(inputs, outputs, num_channels, chunk.clone())
+            };
+            self.apply_output_stages(inputs, outputs, num_channels, chunk, &parameters);
+        }
+
+        // The envelope is written to the first output after the processed channels, if there's
+        // one without a corresponding input.
+        if cfg!(feature = "envelope-output") && inputs.len() == num_channels {
+            if let Some(envelope_output) = outputs.get_mut(num_channels) {
+                self.write_envelope(&inputs[..num_channels], envelope_output, num_samples);
+            }
+        }
+
+        // Anything beyond the end of a truncated block still applies from the next one.
+        if ends_block {
+            if let Some(value) = self.amplitude_controller.take_due(usize::MAX) {
+                self.set_manual_amplitude(value);
+            }
+        }
+
+        // The output trim is applied last of all, so that the meters show the calibrated level.
+        if self.output_trim != 1. {
+            for output in outputs[..num_channels].iter_mut() {
+                for sample in &mut output[..num_samples] {
+                    *sample *= self.output_trim;
+                }
+            }
+        }
+
+        // Report the peak output levels to the editor's meters.
+        if self.to_editor.is_open() {
+            self.to_editor
+                .report_meter_levels(stereo_peaks(&outputs[..num_channels], num_samples));
+
+            // The scope only shows the first channel.
+            if num_channels > 0 {
+                let to_editor = &self.to_editor;
+                self.scope.capture(&outputs[0][..num_samples], |batch| {
+                    to_editor.report_scope_samples(batch)
+                });
+            }
+        }
+
+        self.report_smoothing(num_samples);
+    }
+
+    /// Writes the peak envelope of every channel of `inputs` combined into the first
+    /// `num_samples` samples of `output`.
+    fn write_envelope(&mut self, inputs: &[&[f32]], output: &mut [f32], num_samples: usize) {
+        for (i, sample) in output.iter_mut().take(num_samples).enumerate() {
+            let peak = inputs
+                .iter()
+                .fold(0., |peak: f32, input| peak.max(input[i].abs()));
+            *sample = self.envelope.process(peak);
+        }
+    }
+
+    /// Whether none of the parameters used by `num_channels` channels are changing, so that the
+    /// same values can be used for every sample of the next block.
+    fn is_static(&self, num_channels: usize) -> bool {
+        // Auto-gain changes the amplitude with every chunk.
+        !self.auto_gain_enabled
+            && !self.channel_gain_ranges[..num_channels]
+                .iter()
+                .chain([
+                    &self.amplitude_range,
+                    &self.makeup_gain_range,
+                    &self.width_range,
+                    &self.pan_range,
+                    &self.mix_range,
+                    &self.mono_sum_range,
+                    &self.gate_range,
+                    &self.mute_range,
+                    &self.startup_range,
+                ])
+                .any(SmoothedRange::is_smoothing)
+    }
+
+    /// Multiplies the first `num_samples` samples of each input by a single gain for its channel,
+    /// for blocks in which no parameter is changing.
+    fn apply_static_gain(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        num_channels: usize,
+        num_samples: usize,
+    ) {
+        let gain = self.amplitude * 10f32.powf(self.makeup_gain_db / 20.);
+        for channel in 0..num_channels {
+            let channel_gain = self.channel_gains[channel] * self.polarity(channel);
+            for (output, input) in outputs[channel][..num_samples]
+                .iter_mut()
+                .zip(inputs[channel][..num_samples].iter())
+            {
+                *output = input * gain * channel_gain;
+            }
+        }
+    }
+
+    /// Parameters applied after the gain stage, for a chunk in which none of them are changing.
+    fn static_parameters(&self) -> ChunkParameters {
+        ChunkParameters {
+            widths: [self.width; 16],
+            pans: [self.pan; 16],
+            mixes: [self.mix; 16],
+            mono_sums: [self.mono_sum; 16],
+            gates: [self.gate; 16],
+            mutes: [self.mute; 16],
+            startups: [self.startup; 16],
+        }
+    }
+
+    /// Applies the gain stage to the samples of each channel in `chunk`, smoothing every parameter
+    /// towards its target. Returns the parameters to apply to the rest of the chunk.
+    fn apply_chunk_gain(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        num_channels: usize,
+        chunk: Range<usize>,
+    ) -> ChunkParameters {
+        let (chunk_start, chunk_len) = (chunk.start, chunk.len());
+
+        // While auto-gain is enabled, it takes over the amplitude from the knob. The chosen
+        // gain is still smoothed like any other amplitude change.
+        if self.auto_gain_enabled {
+            let gain = self
+                .auto_gain
+                .process(&inputs[..num_channels], chunk_start, chunk_len);
+            self.amplitude_range.set(gain / MAX_AMPLITUDE);
+        }
+
+        // Prepare the chunk's base parameter values by placing them into 16-element arrays,
+        // then linearly interpolate them towards the next value if the parameter has recently
+        // been changed.
+        //
+        // We could precompute extra interpolated values for a final chunk of <16 samples into
+        // a rollover buffer, but it's simpler to approximate by just reusing the last known
+        // values. Their samples still count towards the next step, though, so that parameters
+        // keep moving even if the host only ever sends blocks shorter than a chunk.
+        let advance = chunk_len == 16 || {
+            self.partial_chunk_samples += chunk_len;
+            if self.partial_chunk_samples >= 16 {
+                self.partial_chunk_samples -= 16;
+                true
+            } else {
+                false
+            }
+        };
+        let (chunk_amplitudes, chunk_makeup_gains_db, parameters) = if advance {
             self.amplitude_range.process();
+            self.makeup_gain_range.process();
+            self.width_range.process();
+            self.pan_range.process();
+            self.mix_range.process();
+            self.mono_sum_range.process();
+            self.gate_range.process();
+            self.mute_range.process();
+            self.startup_range.process();
+            (
+                interpolate_chunk(
+                    &mut self.amplitude_range,
+                    &mut self.amplitude,
+                    MAX_AMPLITUDE,
+                ),
+                interpolate_chunk(
+                    &mut self.makeup_gain_range,
+                    &mut self.makeup_gain_db,
+                    MAX_MAKEUP_GAIN_DB,
+                ),
+                ChunkParameters {
+                    widths: interpolate_chunk(&mut self.width_range, &mut self.width, 2.),
+                    pans: interpolate_chunk(&mut self.pan_range, &mut self.pan, 1.),
+                    mixes: interpolate_chunk(&mut self.mix_range, &mut self.mix, 1.),
+                    mono_sums: interpolate_chunk(&mut self.mono_sum_range, &mut self.mono_sum, 1.),
+                    gates: interpolate_chunk(&mut self.gate_range, &mut self.gate, 1.),
+                    mutes: interpolate_chunk(&mut self.mute_range, &mut self.mute, 1.),
+                    startups: interpolate_chunk(&mut self.startup_range, &mut self.startup, 1.),
+                },
+            )
+        } else {
+            (
+                [self.amplitude; 16],
+                [self.makeup_gain_db; 16],
+                self.static_parameters(),
+            )
+        };
+
+        // Then, calculate each output sample by multiplying each input sample by its
+        // corresponding amplitude value, make-up gain, and the gain of its channel.
+        let mut chunk_gains = chunk_amplitudes;
+        for (gain, makeup_gain_db) in chunk_gains.iter_mut().zip(chunk_makeup_gains_db.iter()) {
+            *gain *= 10f32.powf(makeup_gain_db / 20.);
+        }
+        for channel in 0..num_channels {
+            let polarity = self.polarity(channel);
+            let chunk_channel_gains = if advance {
+                self.channel_gain_ranges[channel].process();
+                interpolate_chunk(
+                    &mut self.channel_gain_ranges[channel],
+                    &mut self.channel_gains[channel],
+                    2.,
+                )
+            } else {
+                [self.channel_gains[channel]; 16]
+            };
+            for (i, (gain, channel_gain)) in chunk_gains[..chunk_len]
+                .iter()
+                .zip(chunk_channel_gains.iter())
+                .enumerate()
+            {
+                outputs[channel][chunk_start + i] =
+                    inputs[channel][chunk_start + i] * gain * channel_gain * polarity;
+            }
+        }
+        parameters
+    }
+
+    /// Returns -1 if the polarity of `channel` is inverted, or 1 otherwise.
+    fn polarity(&self, channel: usize) -> f32 {
+        if self.phase_invert_mask & (1 << channel) != 0 {
+            -1.
+        } else {
+            1.
+        }
+    }
+
+    /// Applies every processing stage after the gain stage to the samples of each channel in
+    /// `chunk`.
+    fn apply_output_stages(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        num_channels: usize,
+        chunk: Range<usize>,
+        parameters: &ChunkParameters,
+    ) {
+        let (chunk_start, chunk_len) = (chunk.start, chunk.len());
+
+        // Stereo width only makes sense with exactly two channels.
+        if num_channels == 2 {
+            for (i, width) in parameters.widths[..chunk_len].iter().enumerate() {
+                let (left, right) = apply_width(
+                    outputs[0][chunk_start + i],
+                    outputs[1][chunk_start + i],
+                    *width,
+                );
+                outputs[0][chunk_start + i] = left;
+                outputs[1][chunk_start + i] = right;
+            }
+
+            // Panning also only makes sense with exactly two channels.
+            for (i, pan) in parameters.pans[..chunk_len].iter().enumerate() {
+                let (left_gain, right_gain) = pan_gains(*pan);
+                outputs[0][chunk_start + i] *= left_gain;
+                outputs[1][chunk_start + i] *= right_gain;
+            }
+        }
+
+        // Finally, blend the processed signal with the original input. While loudness matching
+        // is enabled, the input is first brought to the level of the processed signal, so that
+        // turning down the mix compares the two at the same loudness.
+        let new_dry_gain = if self.loudness_match_enabled {
+            self.loudness_match.process(
+                &inputs[..num_channels],
+                &outputs[..num_channels],
+                chunk_start,
+                chunk_len,
+            )
+        } else {
+            1.
+        };
+        // Like the other gains, the dry gain is interpolated across the chunk, so that it doesn't
+        // step at chunk boundaries.
+        let mut dry_gains = [self.dry_gain; 16];
+        let per_sample_difference = (new_dry_gain - self.dry_gain) / chunk_len as f32;
+        dry_gains
+            .iter_mut()
+            .zip((1..=16).map(|i| i as f32 * per_sample_difference))
+            .for_each(|(gain, difference)| *gain += difference);
+        self.dry_gain = new_dry_gain;
+        for channel in 0..num_channels {
+            for (i, (mix, dry_gain)) in parameters.mixes[..chunk_len]
+                .iter()
+                .zip(dry_gains.iter())
+                .enumerate()
+            {
+                let dry = inputs[channel][chunk_start + i] * dry_gain;
+                let wet = outputs[channel][chunk_start + i];
+                outputs[channel][chunk_start + i] = dry + (wet - dry) * mix;
+            }
+        }
+
+        // Optionally, remove any DC offset. This happens before saturation, so that the signal
+        // is saturated symmetrically and still kept within full scale.
+        if self.dc_block_enabled {
+            for (channel, output) in outputs[..num_channels].iter_mut().enumerate() {
+                for sample in &mut output[chunk_start..chunk_start + chunk_len] {
+                    *sample = self.dc_blocker.process(channel, *sample);
+                }
+            }
+        }
 
-            // Prepare the chunk's base amplitude value by placing it into a 16-element array, then
-            // linearly interpolate them towards the next value if the amplitude has recently been
-            // changed.
-            let mut chunk_amplitudes = [self.amplitude; 16];
-            if let Some(amplitude_range) = self.amplitude_range.get_new_value() {
-                let new_amplitude = amplitude_range * 2.;
-                let per_sample_difference = (new_amplitude - self.amplitude) / 16.;
-                chunk_amplitudes
-                    .iter_mut()
-                    .zip((0..16).map(|i| i as f32 * per_sample_difference))
-                    .for_each(|(amplitude, difference)| *amplitude += difference);
-                self.amplitude = new_amplitude;
-            }
-
-            // Then, calculate each output sample by multiplying each input sample by its
-            // corresponding amplitude value.
-            for channel in 0..num_channels {
-                for (i, amplitude) in chunk_amplitudes.iter().enumerate() {
-                    outputs[channel][chunk_start + i] =
-                        inputs[channel][chunk_start + i] * amplitude;
+        // Optionally, saturate the output to prevent clipping further down the signal chain.
+        // This happens after amplitude smoothing, so that saturation responds to the actual
+        // smoothed level of the signal. While oversampling is enabled, the signal is always
+        // passed through the oversampler, so that its latency stays the same.
+        if self.saturation != SaturationMode::None || self.oversampler.is_active() {
+            let saturation = self.saturation;
+            for (channel, output) in outputs[..num_channels].iter_mut().enumerate() {
+                for sample in &mut output[chunk_start..chunk_start + chunk_len] {
+                    *sample = self
+                        .oversampler
+                        .process(channel, *sample, |sample| saturation.apply(sample));
                 }
             }
         }
 
-        // Finally, process the final <16 samples, if any.
-        for i in 0..extra_samples {
-            for channel in 0..num_channels {
-                // We could precompute extra interpolated amplitude values into a rollover buffer,
-                // but it's simpler to approximate by just reusing the last known amplitude value.
-                outputs[channel][num_chunks * 16 + i] =
-                    inputs[channel][num_chunks * 16 + i] * self.amplitude;
+        // The note gate silences everything, including the dry signal, when closed, and so
+        // does the mute. The startup ramp fades everything in the same way.
+        for output in outputs[..num_channels].iter_mut() {
+            for (i, ((gate, mute), startup)) in parameters.gates[..chunk_len]
+                .iter()
+                .zip(parameters.mutes.iter())
+                .zip(parameters.startups.iter())
+                .enumerate()
+            {
+                output[chunk_start + i] *= gate * mute * startup;
+            }
+        }
+
+        // Summing to mono comes last, so that it takes precedence over the stereo width and
+        // pan. Like them, it only makes sense with exactly two channels.
+        if num_channels == 2
+            && parameters.mono_sums[..chunk_len]
+                .iter()
+                .any(|&sum| sum > 0.)
+        {
+            let chunk = chunk_start..chunk_start + chunk_len;
+            let (left, right) = outputs.split_at_mut(1);
+            for ((left, right), mono_sum) in left[0][chunk.clone()]
+                .iter_mut()
+                .zip(right[0][chunk].iter_mut())
+                .zip(parameters.mono_sums.iter())
+            {
+                let sum = (*left + *right) * MONO_SUM_GAIN;
+                *left = *left * (1. - mono_sum) + sum * mono_sum;
+                *right = *right * (1. - mono_sum) + sum * mono_sum;
             }
         }
     }
+
+    /// Tells the editor whether any of the first `num_samples` samples of `inputs` has reached full
+    /// scale. Clipping that happened upstream can't be undone by any amount of gain.
+    fn report_input_clipping(&mut self, inputs: &[&[f32]], num_samples: usize) {
+        if !self.to_editor.is_open() {
+            return;
+        }
+        let clipping = inputs
+            .iter()
+            .flat_map(|input| input[..num_samples].iter())
+            .any(|sample| sample.abs() >= 1.);
+        self.to_editor.report_input_clipping(clipping);
+    }
+
+    /// Tells the editor whether any parameter is being smoothed. Smoothing is only reported as
+    /// finished once it has stopped for `SMOOTHING_SETTLE_TIME_MS`.
+    fn report_smoothing(&mut self, num_samples: usize) {
+        let smoothing = self
+            .channel_gain_ranges
+            .iter()
+            .chain([
+                &self.amplitude_range,
+                &self.makeup_gain_range,
+                &self.width_range,
+                &self.pan_range,
+                &self.mix_range,
+            ])
+            .any(SmoothedRange::is_smoothing);
+        self.samples_since_smoothing = if smoothing {
+            0
+        } else {
+            self.samples_since_smoothing.saturating_add(num_samples)
+        };
+
+        if !self.to_editor.is_open() {
+            return;
+        }
+        let settle_samples = (SMOOTHING_SETTLE_TIME_MS / 1000. * self.sample_rate) as usize;
+        let active = self.samples_since_smoothing < settle_samples;
+        self.to_editor.report_smoothing(active);
+    }
+}
+
+/// Values of the parameters applied after the gain stage, for each sample of a chunk.
+struct ChunkParameters {
+    widths: [f32; 16],
+    pans: [f32; 16],
+    mixes: [f32; 16],
+    mono_sums: [f32; 16],
+    gates: [f32; 16],
+    mutes: [f32; 16],
+    startups: [f32; 16],
+}
+
+/// Ways of bounding the output signal to full scale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SaturationMode {
+    /// The output is left unbounded.
+    None,
+    /// Samples are clamped to the range [-1, 1], acting as a hard limiter.
+    HardClip,
+    /// Samples are smoothly compressed into the range (-1, 1) with a `tanh` curve.
+    SoftClip,
+}
+
+impl SaturationMode {
+    /// Applies this saturation curve to a single sample.
+    fn apply(self, sample: f32) -> f32 {
+        match self {
+            Self::None => sample,
+            Self::HardClip => sample.clamp(-1., 1.),
+            Self::SoftClip => sample.tanh(),
+        }
+    }
+}
+
+/// Returns the peak levels of the first `num_samples` samples of the left and right channels of
+/// `outputs`. A single channel is reported as both, and any channels beyond the first two are
+/// counted alternately as left and right.
+fn stereo_peaks(outputs: &[&mut [f32]], num_samples: usize) -> [f32; 2] {
+    let mut peaks = [0.; 2];
+    for (channel, output) in outputs.iter().enumerate() {
+        let peak = &mut peaks[channel % 2];
+        *peak = output[..num_samples]
+            .iter()
+            .fold(*peak, |peak: f32, sample| peak.max(sample.abs()));
+    }
+    if outputs.len() == 1 {
+        peaks[1] = peaks[0];
+    }
+    peaks
+}
+
+/// Returns the left and right channel gains for a stereo position from 0 (hard left) to 1 (hard
+/// right). The gains follow a constant-power law, so their squares always sum to 1, and a centered
+/// signal is attenuated by 3dB in each channel.
+fn pan_gains(pan: f32) -> (f32, f32) {
+    let angle = pan * std::f32::consts::FRAC_PI_2;
+    (angle.cos(), angle.sin())
+}
+
+/// Returns a 16-element array filled with the last known value of a parameter, linearly
+/// interpolated towards its next value if its `SmoothedRange` has recently changed. `scale` maps
+/// the 0 to 1 range onto the actual parameter value, and `current` is updated to the new value.
+fn interpolate_chunk(range: &mut SmoothedRange, current: &mut f32, scale: f32) -> [f32; 16] {
+    let mut chunk_values = [*current; 16];
+    if let Some(new_range) = range.get_new_value() {
+        let new_value = new_range * scale;
+        let per_sample_difference = (new_value - *current) / 16.;
+        chunk_values
+            .iter_mut()
+            .zip((0..16).map(|i| i as f32 * per_sample_difference))
+            .for_each(|(value, difference)| *value += difference);
+        *current = new_value;
+    }
+    chunk_values
+}
+
+/// Applies mid/side stereo width processing to a single pair of left and right samples.
+fn apply_width(left: f32, right: f32, width: f32) -> (f32, f32) {
+    let mid = (left + right) / 2.;
+    let side = (left - right) / 2. * width;
+    (mid + side, mid - side)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::Sender;
+
+    /// Runs `input` through `dsp` as a single stereo buffer, returning the processed output.
+    fn process_stereo(dsp: &mut PluginDsp, input: &[Vec<f32>; 2]) -> [Vec<f32>; 2] {
+        let mut output = [vec![0.; input[0].len()], vec![0.; input[1].len()]];
+        {
+            let [left, right] = &mut output;
+            dsp.process_slices(&[&input[0], &input[1]], &mut [left, right]);
+        }
+        output
+    }
+
+    /// A full-scale stereo sine wave, loud enough to clip when amplified.
+    fn loud_sine() -> [Vec<f32>; 2] {
+        let sine: Vec<f32> = (0..4800).map(|i| (i as f32 * 0.05).sin()).collect();
+        [sine.clone(), sine]
+    }
+
+    /// Creates a `PluginDsp` with maximum amplitude, after allowing smoothing to settle.
+    fn max_amplitude_dsp() -> (PluginDsp, Sender<StateUpdate>) {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        to_dsp.send(StateUpdate::SetKnob(1.)).unwrap();
+        process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+        (dsp, to_dsp)
+    }
+
+    /// Creates a `PluginDsp` whose output has already faded in after starting.
+    fn started_dsp() -> (PluginDsp, Sender<StateUpdate>) {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        process_stereo(&mut dsp, &[vec![0.; 1024], vec![0.; 1024]]);
+        (dsp, to_dsp)
+    }
+
+    fn peak(output: &[Vec<f32>; 2]) -> f32 {
+        output
+            .iter()
+            .flatten()
+            .fold(0., |peak: f32, sample| peak.max(sample.abs()))
+    }
+
+    #[test]
+    fn limiter_bounds_output() {
+        let (mut dsp, to_dsp) = max_amplitude_dsp();
+        to_dsp
+            .send(StateUpdate::SetSaturation(SaturationMode::HardClip))
+            .unwrap();
+        let output = process_stereo(&mut dsp, &loud_sine());
+        assert!(peak(&output) <= 1.);
+    }
+
+    #[test]
+    fn disabled_limiter_allows_clipping() {
+        let (mut dsp, _to_dsp) = max_amplitude_dsp();
+        let output = process_stereo(&mut dsp, &loud_sine());
+        assert!(peak(&output) > 1.);
+    }
+
+    #[test]
+    fn soft_clip_is_bounded_and_monotonic() {
+        let (mut dsp, to_dsp) = max_amplitude_dsp();
+        to_dsp
+            .send(StateUpdate::SetSaturation(SaturationMode::SoftClip))
+            .unwrap();
+        // Centered panning attenuates the signal by 3dB, so the ramp must be loud enough to still
+        // saturate afterwards.
+        let ramp: Vec<f32> = (0..4800).map(|i| i as f32 / 1200. - 2.).collect();
+        let output = process_stereo(&mut dsp, &[ramp.clone(), ramp]);
+
+        assert!(peak(&output) <= 1.);
+        assert!(peak(&output) > 0.95);
+        for channel in output.iter() {
+            assert!(channel.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+    }
+
+    /// Energy of the frequency content of `signal` below `max_frequency`, excluding DC.
+    fn energy_below(signal: &[f32], sample_rate: f32, max_frequency: f32) -> f32 {
+        let len = signal.len() as f32;
+        let max_bin = (max_frequency * len / sample_rate) as usize;
+        (1..max_bin)
+            .map(|bin| {
+                let (re, im) = signal
+                    .iter()
+                    .enumerate()
+                    .fold((0., 0.), |(re, im), (i, x)| {
+                        let phase = std::f32::consts::TAU * bin as f32 * i as f32 / len;
+                        (re + x * phase.cos(), im - x * phase.sin())
+                    });
+                re * re + im * im
+            })
+            .sum()
+    }
+
+    #[test]
+    fn oversampling_reduces_aliasing() {
+        const SAMPLE_RATE: f32 = 44100.;
+        const FREQUENCY: f32 = 10000.;
+
+        let clipped_energy = |oversampling| {
+            let (mut dsp, to_dsp) = max_amplitude_dsp();
+            dsp.set_sample_rate(SAMPLE_RATE);
+            to_dsp
+                .send(StateUpdate::SetSaturation(SaturationMode::HardClip))
+                .unwrap();
+            to_dsp
+                .send(StateUpdate::SetOversampling(oversampling))
+                .unwrap();
+            let sine: Vec<f32> = (0..8820)
+                .map(|i| 4. * (std::f32::consts::TAU * FREQUENCY * i as f32 / SAMPLE_RATE).sin())
+                .collect();
+            let output = process_stereo(&mut dsp, &[sine.clone(), sine]);
+            // Every harmonic of the clipped sine is above 9kHz, so anything below it is aliasing.
+            energy_below(&output[0][4410..], SAMPLE_RATE, 9000.)
+        };
+
+        let aliased = clipped_energy(1);
+        let oversampled = clipped_energy(4);
+        assert!(oversampled * 10. < aliased);
+    }
+
+    #[test]
+    fn channel_gains_are_independent() {
+        let (mut dsp, to_dsp) = started_dsp();
+        let reference = process_stereo(&mut dsp, &loud_sine());
+        to_dsp.send(StateUpdate::SetChannelGain(1, 0.)).unwrap();
+        // Gains of channels that aren't processed are ignored.
+        to_dsp
+            .send(StateUpdate::SetChannelGain(MAX_CHANNELS, 0.))
+            .unwrap();
+        process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+        let output = process_stereo(&mut dsp, &loud_sine());
+
+        assert_eq!(output[0], reference[0]);
+        assert_eq!(peak(&[output[1].clone(), vec![]]), 0.);
+    }
+
+    #[test]
+    fn phase_invert_flips_selected_channels() {
+        let (mut dsp, to_dsp) = started_dsp();
+        let reference = process_stereo(&mut dsp, &loud_sine());
+        to_dsp.send(StateUpdate::SetPhaseInvert(0b10)).unwrap();
+        let output = process_stereo(&mut dsp, &loud_sine());
+
+        // Stereo width is applied to the inverted channel, so the results are only nearly exact.
+        for (output, reference) in output[0].iter().zip(reference[0].iter()) {
+            assert!((output - reference).abs() < 1e-6);
+        }
+        for (output, reference) in output[1].iter().zip(reference[1].iter()) {
+            assert!((output + reference).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn batches_are_handed_back_to_be_freed() {
+        let (to_dsp, dsp_recv) = std::sync::mpsc::channel();
+        let (retired_sender, retired_batches) = std::sync::mpsc::sync_channel(1);
+        let mut dsp = PluginDsp::new(
+            dsp_recv,
+            std::sync::mpsc::channel().1,
+            LatestValue::new(),
+            EditorNotifier::detached(),
+            retired_sender,
+        );
+
+        for batch in [
+            StateUpdate::Batch(vec![StateUpdate::SetWidth(0.2)]),
+            StateUpdate::Crossfade(vec![StateUpdate::SetPan(0.3)]),
+        ] {
+            to_dsp.send(batch).unwrap();
+            process_stereo(&mut dsp, &[vec![0.; 64], vec![0.; 64]]);
+            let retired = retired_batches.try_recv().unwrap();
+            assert!(retired.is_empty());
+            assert!(retired.capacity() > 0);
+        }
+    }
+
+    #[test]
+    fn smoothing_activity_is_reported_once() {
+        let (to_dsp, dsp_recv) = std::sync::mpsc::channel();
+        let (to_editor, mut editor_reports) = EditorNotifier::open();
+        let mut dsp = PluginDsp::new(
+            dsp_recv,
+            std::sync::mpsc::channel().1,
+            LatestValue::new(),
+            to_editor,
+            std::sync::mpsc::sync_channel(0).0,
+        );
+        let mut smoothing_reports = || {
+            let mut reports = Vec::new();
+            editor_reports.poll(|update| {
+                if let StateUpdate::SmoothingActive(active) = update {
+                    reports.push(active);
+                }
+            });
+            reports
+        };
+
+        process_stereo(&mut dsp, &[vec![0.; 512], vec![0.; 512]]);
+        assert_eq!(smoothing_reports(), vec![]);
+
+        to_dsp.send(StateUpdate::SetKnob(1.)).unwrap();
+        for _ in 0..200 {
+            process_stereo(&mut dsp, &[vec![0.; 64], vec![0.; 64]]);
+        }
+        assert_eq!(smoothing_reports(), vec![true]);
+
+        process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+        assert_eq!(smoothing_reports(), vec![false]);
+    }
+
+    #[test]
+    fn input_clipping_is_reported_once() {
+        let (_to_dsp, dsp_recv) = std::sync::mpsc::channel();
+        let (to_editor, mut editor_reports) = EditorNotifier::open();
+        let mut dsp = PluginDsp::new(
+            dsp_recv,
+            std::sync::mpsc::channel().1,
+            LatestValue::new(),
+            to_editor,
+            std::sync::mpsc::sync_channel(0).0,
+        );
+        let mut clipping_reports = || {
+            let mut reports = Vec::new();
+            editor_reports.poll(|update| {
+                if let StateUpdate::InputClipping(clipping) = update {
+                    reports.push(clipping);
+                }
+            });
+            reports
+        };
+
+        // Input that only clips once amplified isn't reported.
+        process_stereo(&mut dsp, &[vec![0.99; 64], vec![0.; 64]]);
+        assert_eq!(clipping_reports(), vec![]);
+
+        for _ in 0..2 {
+            process_stereo(&mut dsp, &[vec![0.; 64], vec![-1.; 64]]);
+        }
+        process_stereo(&mut dsp, &[vec![0.; 64], vec![0.; 64]]);
+        assert_eq!(clipping_reports(), vec![true, false]);
+    }
+
+    #[test]
+    fn parameters_move_with_blocks_shorter_than_a_chunk() {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        to_dsp.send(StateUpdate::SetMonoSum(true)).unwrap();
+        let process_short_blocks = |dsp: &mut PluginDsp| {
+            let mut output = [vec![], vec![]];
+            for _ in 0..20000 {
+                output = process_stereo(dsp, &[vec![1.; 8], vec![0.; 8]]);
+            }
+            output
+        };
+
+        // The startup ramp finishes, and summing to mono takes effect.
+        let output = process_short_blocks(&mut dsp);
+        assert!(peak(&output) > 0.1);
+        assert_eq!(output[0], output[1]);
+
+        to_dsp.send(StateUpdate::SetMute(true)).unwrap();
+        assert_eq!(peak(&process_short_blocks(&mut dsp)), 0.);
+    }
+
+    #[test]
+    fn mute_fades_out_and_back_in() {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        let input = loud_sine();
+        let unmuted = peak(&process_stereo(&mut dsp, &input));
+
+        to_dsp.send(StateUpdate::SetMute(true)).unwrap();
+        let fading = process_stereo(&mut dsp, &input);
+        // The fade starts from the unmuted level, rather than cutting off abruptly.
+        assert!(fading[0][..16].iter().any(|sample| sample.abs() > 0.1));
+        assert_eq!(peak(&process_stereo(&mut dsp, &input)), 0.);
+
+        to_dsp.send(StateUpdate::SetMute(false)).unwrap();
+        process_stereo(&mut dsp, &input);
+        assert!((peak(&process_stereo(&mut dsp, &input)) - unmuted).abs() < 1e-3);
+    }
+
+    #[test]
+    fn output_fades_in_on_start_and_resume() {
+        let (mut dsp, _to_dsp) = PluginDsp::headless();
+        let ones = [vec![1.; 1024], vec![1.; 1024]];
+        let ramp_samples = (STARTUP_RAMP_MS / 1000. * 44100.) as usize;
+        for _ in 0..2 {
+            let output = process_stereo(&mut dsp, &ones);
+            // The centered pan attenuates each channel by 3dB.
+            let full = 2f32.sqrt() / 2.;
+            assert_eq!(output[0][0], 0.);
+            assert!(output[0].windows(2).all(|pair| pair[1] >= pair[0]));
+            assert!(output[0][ramp_samples / 2] < full * 0.75);
+            assert!((output[0][ramp_samples + 16] - full).abs() < 1e-6);
+
+            // Once faded in, the output stays at full level until processing resumes.
+            let output = process_stereo(&mut dsp, &ones);
+            assert!(output[0].iter().all(|sample| (sample - full).abs() < 1e-6));
+            dsp.resume();
+        }
+    }
+
+    #[test]
+    fn priority_updates_are_applied_first() {
+        let (to_dsp, dsp_recv) = std::sync::mpsc::channel();
+        let (priority_to_dsp, priority_dsp_recv) = std::sync::mpsc::channel();
+        let mut dsp = PluginDsp::new(
+            dsp_recv,
+            priority_dsp_recv,
+            LatestValue::new(),
+            EditorNotifier::detached(),
+            std::sync::mpsc::sync_channel(0).0,
+        );
+        // Mutes are normally only sent through the priority channel, but sending conflicting ones
+        // through each shows which is applied first.
+        to_dsp.send(StateUpdate::SetMute(true)).unwrap();
+        priority_to_dsp.send(StateUpdate::SetMute(false)).unwrap();
+        process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+        assert_eq!(peak(&process_stereo(&mut dsp, &loud_sine())), 0.);
+    }
+
+    #[test]
+    fn controller_changes_apply_within_block() {
+        let (mut dsp, _to_dsp) = PluginDsp::headless();
+        let input = [vec![0.5; 48000], vec![0.5; 48000]];
+        let before = process_stereo(&mut dsp, &input)[0][47999];
+
+        // Control change to 0 on the default controller, partway through the next block.
+        dsp.amplitude_controller
+            .receive(512, [0xb0, DEFAULT_CONTROLLER, 0]);
+        let output = process_stereo(&mut dsp, &input);
+        assert!(output[0][..512].iter().all(|&sample| sample == before));
+        assert!(output[0][530] < before);
+        assert!(output[0][47999].abs() < 1e-3);
+    }
+
+    #[test]
+    fn controller_changes_apply_within_double_precision_pieces() {
+        let (mut dsp, _to_dsp) = PluginDsp::headless();
+        let input = [vec![0.5; 48000], vec![0.5; 48000]];
+        let mut output = [vec![0.; 48000], vec![0.; 48000]];
+        {
+            let [left, right] = &mut output;
+            dsp.process_slices_f64(&[&input[0], &input[1]], &mut [left, right]);
+        }
+        let before = output[0][47999];
+
+        // Partway through the third piece of the next block.
+        let offset = DEFAULT_SCRATCH_LEN * 2 + 512;
+        dsp.amplitude_controller
+            .receive(offset, [0xb0, DEFAULT_CONTROLLER, 0]);
+        {
+            let [left, right] = &mut output;
+            dsp.process_slices_f64(&[&input[0], &input[1]], &mut [left, right]);
+        }
+        assert!(output[0][..offset].iter().all(|&sample| sample == before));
+        assert!(output[0][offset + 18] < before);
+        assert!(output[0][47999].abs() < 1e-3);
+    }
+
+    #[test]
+    fn envelope_follows_input_peaks() {
+        let (mut dsp, _to_dsp) = PluginDsp::headless();
+        let left: Vec<f32> = (0..64).map(|i| if i == 10 { 0.5 } else { 0. }).collect();
+        let right: Vec<f32> = (0..64).map(|i| if i == 20 { -0.8 } else { 0. }).collect();
+        let mut outputs = [vec![0.; 64], vec![0.; 64], vec![-1.; 64]];
+        {
+            let [out_left, out_right, envelope] = &mut outputs;
+            dsp.process_slices(&[&left, &right], &mut [out_left, out_right, envelope]);
+        }
+        let envelope = &outputs[2];
+        if cfg!(feature = "envelope-output") {
+            assert_eq!(envelope[..10], [0.; 10]);
+            assert_eq!(envelope[10], 0.5);
+            assert!(envelope[19] < 0.5 && envelope[19] > 0.4);
+            assert_eq!(envelope[20], 0.8);
+        } else {
+            // Without the feature, extra outputs are left alone.
+            assert_eq!(envelope, &vec![-1.; 64]);
+        }
+    }
+
+    #[test]
+    fn double_precision_matches_single_precision() {
+        let mut dsps = [PluginDsp::headless(), PluginDsp::headless()];
+        for (_, to_dsp) in &dsps {
+            to_dsp.send(StateUpdate::SetKnob(0.8)).unwrap();
+            to_dsp
+                .send(StateUpdate::SetSaturation(SaturationMode::SoftClip))
+                .unwrap();
+            to_dsp.send(StateUpdate::SetOversampling(2)).unwrap();
+        }
+        // Longer than the scratch buffers, so that it's processed in several pieces.
+        let input = loud_sine();
+        let single = process_stereo(&mut dsps[0].0, &input);
+
+        let input: Vec<Vec<f64>> = input
+            .iter()
+            .map(|channel| channel.iter().map(|&sample| sample as f64).collect())
+            .collect();
+        let mut double = [vec![0.; input[0].len()], vec![0.; input[1].len()]];
+        {
+            let [left, right] = &mut double;
+            dsps[1]
+                .0
+                .process_slices_f64(&[&input[0], &input[1]], &mut [left, right]);
+        }
+        for (single, double) in single.iter().flatten().zip(double.iter().flatten()) {
+            assert!((*single as f64 - double).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn output_trim_scales_final_output() {
+        let (mut dsp, to_dsp) = started_dsp();
+        to_dsp
+            .send(StateUpdate::SetSaturation(SaturationMode::HardClip))
+            .unwrap();
+        let input = loud_sine();
+        let untrimmed = process_stereo(&mut dsp, &input);
+
+        to_dsp.send(StateUpdate::SetOutputTrim(-6.)).unwrap();
+        let trimmed = process_stereo(&mut dsp, &input);
+        // The trim comes after clipping, so it scales the clipped output exactly.
+        let expected = 10f32.powf(-6. / 20.);
+        for (trimmed, untrimmed) in trimmed.iter().flatten().zip(untrimmed.iter().flatten()) {
+            assert!((trimmed - untrimmed * expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn meters_are_per_channel() {
+        let (mut left, mut right) = (vec![0.5, -0.25], vec![0.1, -0.75]);
+        assert_eq!(stereo_peaks(&[&mut left, &mut right], 2), [0.5, 0.75]);
+        assert_eq!(stereo_peaks(&[&mut left, &mut right], 1), [0.5, 0.1]);
+        assert_eq!(stereo_peaks(&[&mut right], 2), [0.75, 0.75]);
+        assert_eq!(stereo_peaks(&[], 2), [0., 0.]);
+    }
+
+    #[test]
+    fn centered_pan_has_unity_power() {
+        let (left, right) = pan_gains(0.5);
+        assert!((left - right).abs() < 1e-6);
+        assert!((left.powi(2) + right.powi(2) - 1.).abs() < 1e-6);
+
+        let (hard_left, silent_right) = pan_gains(0.);
+        assert_eq!(hard_left, 1.);
+        assert_eq!(silent_right, 0.);
+    }
+
+    #[test]
+    fn amplitude_scales_output() {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        to_dsp.send(StateUpdate::SetKnob(0.25)).unwrap();
+        process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+
+        let output = process_stereo(&mut dsp, &[vec![1.; 64], vec![1.; 64]]);
+        // Half gain, attenuated by 3dB in each channel by centered panning.
+        let expected = 0.5 * std::f32::consts::FRAC_1_SQRT_2;
+        for sample in output.iter().flatten() {
+            assert!((sample - expected).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn auto_gain_returns_to_manual_amplitude() {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        to_dsp.send(StateUpdate::SetKnob(0.25)).unwrap();
+        to_dsp.send(StateUpdate::SetAutoGain(true)).unwrap();
+        to_dsp.send(StateUpdate::SetAutoGainTarget(-20.)).unwrap();
+        let quiet_sine: Vec<f32> = (0..88200).map(|i| 0.1 * (i as f32 * 0.05).sin()).collect();
+        process_stereo(&mut dsp, &[quiet_sine.clone(), quiet_sine]);
+        // The input is 3dB below the target, but is panned down by another 3dB.
+        assert!((dsp.amplitude - 2f32.sqrt()).abs() < 0.05);
+
+        to_dsp.send(StateUpdate::SetAutoGain(false)).unwrap();
+        process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+        assert!((dsp.amplitude - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn first_block_uses_default_amplitude() {
+        let (mut dsp, _to_dsp) = PluginDsp::headless();
+        let mut output = vec![0.; 1024];
+        dsp.process_slices(&[&[1.; 1024]], &mut [&mut output]);
+        // The default amplitude is unity gain from the very first chunk, so once the output has
+        // faded in, it matches the input exactly.
+        let ramp_samples = (STARTUP_RAMP_MS / 1000. * 44100.) as usize;
+        assert!(output[ramp_samples + 16..]
+            .iter()
+            .all(|&sample| sample == 1.));
+        assert_eq!(dsp.amplitude, 1.);
+        assert!(!dsp.amplitude_range.is_smoothing());
+    }
+
+    #[test]
+    fn mono_is_not_panned() {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        to_dsp.send(StateUpdate::SetKnob(0.25)).unwrap();
+        let mut settle = vec![0.; 48000];
+        dsp.process_slices(&[&vec![0.; 48000]], &mut [&mut settle]);
+
+        let mut output = vec![0.; 64];
+        dsp.process_slices(&[&[1.; 64]], &mut [&mut output]);
+        for sample in output {
+            assert!((sample - 0.5).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn mono_sum_overrides_width_and_pan() {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        to_dsp.send(StateUpdate::SetMonoSum(true)).unwrap();
+        to_dsp.send(StateUpdate::SetWidth(1.)).unwrap();
+        to_dsp.send(StateUpdate::SetPan(0.)).unwrap();
+        process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+
+        let [left, right] = loud_sine();
+        let output = process_stereo(&mut dsp, &[left.clone(), right]);
+        assert_eq!(output[0], output[1]);
+        assert!(peak(&output) > 0.1);
+
+        // Once centered, channels that are out of phase cancel out entirely.
+        to_dsp.send(StateUpdate::SetPan(0.5)).unwrap();
+        process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+        let inverted = left.iter().map(|sample| -sample).collect();
+        assert!(peak(&process_stereo(&mut dsp, &[left, inverted])) < 1e-6);
+    }
+
+    #[test]
+    fn mono_sum_crossfades_over_one_chunk() {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+
+        to_dsp.send(StateUpdate::SetMonoSum(true)).unwrap();
+        let output = process_stereo(&mut dsp, &[vec![0.5; 64], vec![0.; 64]]);
+        assert_eq!(output[1][0], 0.);
+        assert!(output[1][8] > 0. && output[1][8] < output[1][16]);
+        assert_eq!(output[0][16..], output[1][16..]);
+    }
+
+    #[test]
+    fn dc_block_removes_offset() {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        let offset_sine: Vec<f32> = (0..48000)
+            .map(|i| 0.25 + 0.5 * (i as f32 * 0.05).sin())
+            .collect();
+        let input = [offset_sine.clone(), offset_sine];
+        let mean =
+            |output: &[Vec<f32>; 2]| output[0][..4800].iter().sum::<f32>() / 4800. * 2f32.sqrt();
+
+        // Panning attenuates each channel by 3dB, which `mean` accounts for.
+        let unfiltered = process_stereo(&mut dsp, &input);
+        assert!((mean(&unfiltered) - 0.25).abs() < 0.01);
+        assert_eq!(dsp.tail_samples(), 0);
+
+        to_dsp.send(StateUpdate::SetDcBlock(true)).unwrap();
+        process_stereo(&mut dsp, &input);
+        let filtered = process_stereo(&mut dsp, &input);
+        assert!(mean(&filtered).abs() < 0.01);
+        assert!(dsp.tail_samples() > 0);
+    }
+
+    #[test]
+    fn static_blocks_match_smoothed_blocks() {
+        let input = loud_sine();
+        let settled_dsp = || {
+            let (mut dsp, to_dsp) = PluginDsp::headless();
+            to_dsp.send(StateUpdate::SetKnob(0.7)).unwrap();
+            to_dsp.send(StateUpdate::SetMakeupGain(0.1)).unwrap();
+            to_dsp.send(StateUpdate::SetWidth(0.3)).unwrap();
+            process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+            (dsp, to_dsp)
+        };
+        let (mut static_dsp, _to_static_dsp) = settled_dsp();
+        assert!(static_dsp.is_static(2));
+        let (mut smoothed_dsp, to_smoothed_dsp) = settled_dsp();
+        // Setting a parameter to its current value makes it smooth, without changing anything.
+        to_smoothed_dsp.send(StateUpdate::SetMix(1.)).unwrap();
+        smoothed_dsp.process_slices(&[], &mut []);
+        assert!(!smoothed_dsp.is_static(2));
+
+        assert_eq!(
+            process_stereo(&mut static_dsp, &input),
+            process_stereo(&mut smoothed_dsp, &input)
+        );
+    }
+
+    /// Compares the speed of blocks processed with and without the static fast path. Run with
+    /// `cargo test --release -- --ignored --nocapture static_block_benchmark`.
+    #[test]
+    #[ignore]
+    fn static_block_benchmark() {
+        let input = [vec![0.5; 1 << 20], vec![0.5; 1 << 20]];
+        let time = |force_smoothing: bool| {
+            let (mut dsp, to_dsp) = PluginDsp::headless();
+            let start = std::time::Instant::now();
+            for _ in 0..16 {
+                if force_smoothing {
+                    to_dsp.send(StateUpdate::SetMix(1.)).unwrap();
+                }
+                process_stereo(&mut dsp, &input);
+            }
+            start.elapsed()
+        };
+        println!("static: {:?}, smoothed: {:?}", time(false), time(true));
+    }
+
+    /// Counts the allocations made by each thread, so that tests running in parallel don't
+    /// interfere with each other.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            std::alloc::System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            std::alloc::System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn processing_does_not_allocate() {
+        // With an open editor, measurements are also reported to it while processing.
+        let (open_editor, mut editor_reports) = EditorNotifier::open();
+        for to_editor in [EditorNotifier::detached(), open_editor] {
+            let (to_dsp, dsp_recv) = std::sync::mpsc::channel();
+            let mut dsp = PluginDsp::new(
+                dsp_recv,
+                std::sync::mpsc::channel().1,
+                LatestValue::new(),
+                to_editor,
+                std::sync::mpsc::sync_channel(0).0,
+            );
+            dsp.set_sample_rate(48000.);
+            dsp.set_block_size(512);
+            // Exercise every optional stage.
+            for update in [
+                StateUpdate::SetKnob(0.8),
+                StateUpdate::SetAutoGain(true),
+                StateUpdate::SetSaturation(SaturationMode::SoftClip),
+                StateUpdate::SetOversampling(4),
+                StateUpdate::SetDcBlock(true),
+                StateUpdate::SetMonoSum(true),
+                StateUpdate::SetMute(true),
+            ] {
+                to_dsp.send(update).unwrap();
+            }
+            let [left, right] = loud_sine();
+            let mut output = [vec![0.; 512], vec![0.; 512]];
+
+            let allocations_before = ALLOCATIONS.with(|count| count.get());
+            for _ in 0..8 {
+                let [out_left, out_right] = &mut output;
+                dsp.process_slices(&[&left[..512], &right[..512]], &mut [out_left, out_right]);
+            }
+            assert_eq!(ALLOCATIONS.with(|count| count.get()), allocations_before);
+        }
+
+        let mut meter_reports = 0;
+        let mut scope_reports = 0;
+        editor_reports.poll(|update| match update {
+            StateUpdate::MeterLevels(_) => meter_reports += 1,
+            StateUpdate::ScopeSamples(_) => scope_reports += 1,
+            _ => (),
+        });
+        assert_eq!(meter_reports, 1);
+        assert!(scope_reports > 0);
+    }
+
+    #[test]
+    fn preset_crossfade_is_linear() {
+        let (mut dsp, to_dsp) = max_amplitude_dsp();
+        to_dsp.send(StateUpdate::SetPresetCrossfade(1000.)).unwrap();
+        to_dsp
+            .send(StateUpdate::Crossfade(vec![
+                StateUpdate::SetKnob(0.),
+                StateUpdate::SetPresetCrossfade(0.),
+            ]))
+            .unwrap();
+
+        // Halfway through the crossfade, the amplitude is halfway between 2 and 0, attenuated by
+        // 3dB by the centered pan.
+        let input = [vec![0.1; 22050], vec![0.1; 22050]];
+        let halfway = process_stereo(&mut dsp, &input);
+        assert!((halfway[0][22049] - 0.1 * 0.5f32.sqrt()).abs() < 1e-3);
+        let end = process_stereo(&mut dsp, &input);
+        assert!(end[0][22049].abs() < 1e-6);
+    }
+
+    #[test]
+    fn zero_mix_passes_input_through() {
+        let (mut dsp, to_dsp) = PluginDsp::headless();
+        to_dsp.send(StateUpdate::SetKnob(1.)).unwrap();
+        to_dsp.send(StateUpdate::SetWidth(0.)).unwrap();
+        to_dsp.send(StateUpdate::SetMix(0.)).unwrap();
+
+        // Allow plenty of time for smoothing to settle.
+        let silence = [vec![0.; 48000], vec![0.; 48000]];
+        process_stereo(&mut dsp, &silence);
+
+        let input = [
+            (0..1000).map(|i| (i as f32 * 0.1).sin()).collect(),
+            (0..1000).map(|i| (i as f32 * 0.3).cos()).collect(),
+        ];
+        let output = process_stereo(&mut dsp, &input);
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn loudness_match_brings_dry_signal_to_processed_level() {
+        let (mut dsp, to_dsp) = max_amplitude_dsp();
+        to_dsp.send(StateUpdate::SetMix(0.)).unwrap();
+        to_dsp.send(StateUpdate::SetLoudnessMatch(true)).unwrap();
+        let sine: Vec<f32> = (0..48000).map(|i| 0.1 * (i as f32 * 0.05).sin()).collect();
+        let input = [sine.clone(), sine];
+
+        // The processed signal is doubled, then attenuated by 3dB by the centered pan. Once
+        // measured, the dry signal is matched to it, despite the mix being turned all the way down.
+        process_stereo(&mut dsp, &input);
+        let matched = process_stereo(&mut dsp, &input);
+        assert!((peak(&matched) - 0.2 * 0.5f32.sqrt()).abs() < 0.005);
+
+        to_dsp.send(StateUpdate::SetLoudnessMatch(false)).unwrap();
+        assert!((peak(&process_stereo(&mut dsp, &input)) - 0.1).abs() < 1e-4);
+    }
+
+    #[test]
+    fn loudness_match_gain_is_interpolated_within_chunks() {
+        let (mut dsp, to_dsp) = max_amplitude_dsp();
+        to_dsp.send(StateUpdate::SetMix(0.)).unwrap();
+        process_stereo(&mut dsp, &[vec![0.; 48000], vec![0.; 48000]]);
+
+        // The processed signal is louder than the dry signal, so the dry gain rises from unity
+        // over the first chunk, rather than jumping at its start.
+        to_dsp.send(StateUpdate::SetLoudnessMatch(true)).unwrap();
+        let output = process_stereo(&mut dsp, &[vec![0.1; 64], vec![0.1; 64]]);
+        assert!(output[0][0] < 0.105);
+        assert!(output[0][..16].windows(2).all(|pair| pair[1] > pair[0]));
+        assert!((output[0][15] - 0.2 * 0.5f32.sqrt()).abs() < 1e-4);
+    }
 }
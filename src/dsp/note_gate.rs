@@ -0,0 +1,92 @@
+//! Tracks which MIDI notes are held, so that audio can be gated by incoming notes.
+//!
+//! Notes are accepted on all 16 MIDI channels. The same note may be held on several channels at
+//! once, and the gate only closes once every held note has been released.
+
+use vst::api::Events;
+use vst::event::{Event, MidiEvent};
+
+/// Status byte (with the channel nibble cleared) of a MIDI note-off message.
+const NOTE_OFF: u8 = 0x80;
+/// Status byte (with the channel nibble cleared) of a MIDI note-on message.
+const NOTE_ON: u8 = 0x90;
+
+/// Set of MIDI notes currently held, on any channel.
+pub(super) struct HeldNotes {
+    /// For each note number, a bitmask of the channels on which it is held.
+    channels_by_note: [u16; 128],
+    /// Whether any note has been received yet. Until then, the gate is left open so that the
+    /// plugin still passes audio when it isn't being used with MIDI.
+    received_notes: bool,
+}
+
+impl HeldNotes {
+    pub fn new() -> Self {
+        Self {
+            channels_by_note: [0; 128],
+            received_notes: false,
+        }
+    }
+
+    /// Updates the held notes from a block of host events.
+    pub fn process_events(&mut self, events: &Events) {
+        for event in events.events() {
+            if let Event::Midi(MidiEvent { data, .. }) = event {
+                self.process_midi(data);
+            }
+        }
+    }
+
+    /// Updates the held notes from a single raw MIDI message. Messages other than note-on and
+    /// note-off are ignored.
+    fn process_midi(&mut self, [status, note, velocity]: [u8; 3]) {
+        let channel_bit = 1 << (status & 0x0f);
+        let note = (note & 0x7f) as usize;
+        match status & 0xf0 {
+            // A note-on with zero velocity is equivalent to a note-off.
+            NOTE_ON if velocity > 0 => self.channels_by_note[note] |= channel_bit,
+            NOTE_ON | NOTE_OFF => self.channels_by_note[note] &= !channel_bit,
+            _ => return,
+        }
+        self.received_notes = true;
+    }
+
+    /// Whether audio should currently pass through the gate.
+    pub fn gate_is_open(&self) -> bool {
+        !self.received_notes || self.channels_by_note.iter().any(|&channels| channels != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gate_is_open_before_any_notes() {
+        assert!(HeldNotes::new().gate_is_open());
+    }
+
+    #[test]
+    fn overlapping_notes_keep_gate_open() {
+        let mut notes = HeldNotes::new();
+        notes.process_midi([NOTE_ON, 60, 100]);
+        notes.process_midi([NOTE_ON | 3, 64, 100]);
+        notes.process_midi([NOTE_OFF, 60, 0]);
+        assert!(notes.gate_is_open());
+
+        // Zero-velocity note-on releases the last held note.
+        notes.process_midi([NOTE_ON | 3, 64, 0]);
+        assert!(!notes.gate_is_open());
+    }
+
+    #[test]
+    fn same_note_on_different_channels() {
+        let mut notes = HeldNotes::new();
+        notes.process_midi([NOTE_ON, 60, 100]);
+        notes.process_midi([NOTE_ON | 1, 60, 100]);
+        notes.process_midi([NOTE_OFF | 1, 60, 0]);
+        assert!(notes.gate_is_open());
+        notes.process_midi([NOTE_OFF, 60, 0]);
+        assert!(!notes.gate_is_open());
+    }
+}
@@ -0,0 +1,217 @@
+//! Runs nonlinear processing at a multiple of the sample rate, to reduce aliasing of the harmonics
+//! it generates.
+
+use super::MAX_CHANNELS;
+
+/// Delay of each halfband filter, in samples at the higher of its two sample rates.
+const FILTER_DELAY: usize = 16;
+/// Number of taps of each halfband filter.
+const FILTER_TAPS: usize = FILTER_DELAY * 2 + 1;
+/// Highest supported oversampling factor. Each doubling of the sample rate uses another
+/// `HalfbandStage`.
+pub const MAX_OVERSAMPLING: usize = 4;
+const MAX_STAGES: usize = 2;
+
+/// Number of samples by which the output is delayed at the base sample rate while oversampling by
+/// `factor`, which is rounded down like by `Oversampler::set_factor`.
+pub fn oversampling_latency(factor: usize) -> usize {
+    stages_latency(num_stages(factor))
+}
+
+/// Number of stages needed for an oversampling factor, rounded down to a supported one.
+fn num_stages(factor: usize) -> usize {
+    match factor {
+        0..=1 => 0,
+        2..=3 => 1,
+        _ => 2,
+    }
+}
+
+/// Number of samples by which `num_stages` stages delay the output at the base sample rate.
+fn stages_latency(num_stages: usize) -> usize {
+    // Each stage delays by `FILTER_DELAY` samples at its higher rate when upsampling, and again
+    // when downsampling.
+    (0..num_stages)
+        .map(|stage| (2 * FILTER_DELAY) >> (stage + 1))
+        .sum()
+}
+
+/// Upsamples and downsamples each channel by a power of two, using cascaded stages that each
+/// double the sample rate. Each stage is a polyphase implementation of a linear-phase halfband
+/// lowpass filter, so the output is delayed by a whole number of samples.
+pub(super) struct Oversampler {
+    /// Coefficients of the halfband filter shared by every stage.
+    taps: [f32; FILTER_TAPS],
+    /// Number of stages in use.
+    num_stages: usize,
+    channels: [[HalfbandStage; MAX_STAGES]; MAX_CHANNELS],
+}
+
+impl Oversampler {
+    pub fn new() -> Self {
+        Self {
+            taps: halfband_taps(),
+            num_stages: 0,
+            channels: Default::default(),
+        }
+    }
+
+    /// Selects an oversampling factor of 1, 2, or 4. Other factors are rounded down to the nearest
+    /// supported one. Changing the factor clears the filter history.
+    pub fn set_factor(&mut self, factor: usize) {
+        let num_stages = num_stages(factor);
+        if num_stages != self.num_stages {
+            self.num_stages = num_stages;
+            self.channels = Default::default();
+        }
+    }
+
+    /// Whether any oversampling is applied.
+    pub fn is_active(&self) -> bool {
+        self.num_stages > 0
+    }
+
+    /// Number of samples by which the output is delayed at the base sample rate.
+    pub fn latency_samples(&self) -> usize {
+        stages_latency(self.num_stages)
+    }
+
+    /// Number of samples at the base sample rate for which the output can continue after the input
+    /// falls silent. The filters are symmetric, so their response extends as far past its peak as
+    /// the latency before it.
+    pub fn tail_samples(&self) -> usize {
+        self.latency_samples() * 2
+    }
+
+    /// Passes the next `sample` of `channel` through `process` at the oversampled rate, returning
+    /// the next output sample at the base rate.
+    pub fn process(&mut self, channel: usize, sample: f32, process: impl Fn(f32) -> f32) -> f32 {
+        process_stages(
+            &mut self.channels[channel][..self.num_stages],
+            &self.taps,
+            sample,
+            &process,
+        )
+    }
+}
+
+/// Runs `sample` up through each of `stages` in turn, applies `process` at the highest rate, and
+/// then returns back down through each stage.
+fn process_stages(
+    stages: &mut [HalfbandStage],
+    taps: &[f32; FILTER_TAPS],
+    sample: f32,
+    process: &impl Fn(f32) -> f32,
+) -> f32 {
+    match stages.split_first_mut() {
+        None => process(sample),
+        Some((stage, higher_stages)) => {
+            let [first, second] = stage.upsample(taps, sample);
+            let first = process_stages(higher_stages, taps, first, process);
+            let second = process_stages(higher_stages, taps, second, process);
+            stage.downsample(taps, [first, second])
+        }
+    }
+}
+
+/// Filter history for doubling and then halving the sample rate of a single channel.
+#[derive(Clone, Copy)]
+struct HalfbandStage {
+    /// Recent input samples at the lower rate, most recent first.
+    up_history: [f32; FILTER_TAPS / 2 + 1],
+    /// Recent samples at the higher rate, most recent first.
+    down_history: [f32; FILTER_TAPS],
+}
+
+impl Default for HalfbandStage {
+    fn default() -> Self {
+        Self {
+            up_history: [0.; FILTER_TAPS / 2 + 1],
+            down_history: [0.; FILTER_TAPS],
+        }
+    }
+}
+
+impl HalfbandStage {
+    /// Returns the two samples at double the rate that follow from the next input `sample`.
+    fn upsample(&mut self, taps: &[f32; FILTER_TAPS], sample: f32) -> [f32; 2] {
+        self.up_history.rotate_right(1);
+        self.up_history[0] = sample;
+        // Upsampling inserts a zero after every sample, so each output only depends on every other
+        // tap. The gain of 2 makes up for the energy of the inserted zeros.
+        let phase = |offset: usize| {
+            taps.iter()
+                .skip(offset)
+                .step_by(2)
+                .zip(self.up_history.iter())
+                .map(|(tap, sample)| tap * sample)
+                .sum::<f32>()
+                * 2.
+        };
+        [phase(0), phase(1)]
+    }
+
+    /// Returns the next output sample at half the rate, from the two given `samples`.
+    fn downsample(&mut self, taps: &[f32; FILTER_TAPS], samples: [f32; 2]) -> f32 {
+        // Only the filter output aligned with the first of each pair of samples is kept, so that
+        // the delays of upsampling and downsampling add up to a whole number of output samples.
+        self.down_history.rotate_right(1);
+        self.down_history[0] = samples[0];
+        let output = taps
+            .iter()
+            .zip(self.down_history.iter())
+            .map(|(tap, sample)| tap * sample)
+            .sum();
+        self.down_history.rotate_right(1);
+        self.down_history[0] = samples[1];
+        output
+    }
+}
+
+/// Designs a lowpass filter with a cutoff at a quarter of the sample rate, using a
+/// Blackman-windowed sinc function normalized to unity gain.
+fn halfband_taps() -> [f32; FILTER_TAPS] {
+    let mut taps = [0.; FILTER_TAPS];
+    for (n, tap) in taps.iter_mut().enumerate() {
+        let t = n as f64 - FILTER_DELAY as f64;
+        let sinc = if t == 0. {
+            1.
+        } else {
+            let x = std::f64::consts::PI * t / 2.;
+            x.sin() / x
+        };
+        let phase = 2. * std::f64::consts::PI * n as f64 / (FILTER_TAPS - 1) as f64;
+        let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2. * phase).cos();
+        *tap = (sinc * window) as f32;
+    }
+    let sum: f32 = taps.iter().sum();
+    taps.iter_mut().for_each(|tap| *tap /= sum);
+    taps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impulse_is_delayed_by_latency() {
+        for &factor in &[2, 4] {
+            let mut oversampler = Oversampler::new();
+            oversampler.set_factor(factor);
+            let output: Vec<f32> = (0..64)
+                .map(|i| oversampler.process(0, if i == 0 { 1. } else { 0. }, |x| x))
+                .collect();
+            let peak = output
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap())
+                .unwrap()
+                .0;
+            assert_eq!(peak, oversampler.latency_samples());
+            assert!((output.iter().sum::<f32>() - 1.).abs() < 1e-3);
+            assert!(output[oversampler.tail_samples() + 1..]
+                .iter()
+                .all(|sample| sample.abs() < 1e-6));
+        }
+    }
+}
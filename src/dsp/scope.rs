@@ -0,0 +1,66 @@
+//! Captures a low-resolution copy of the output signal for display by the editor's scope.
+
+/// Number of captured samples reported to the editor in each batch.
+pub const SCOPE_BATCH_LEN: usize = 32;
+/// Only one of every this many output samples is captured.
+const SCOPE_DOWNSAMPLE: usize = 32;
+
+/// Collects every `SCOPE_DOWNSAMPLE`th output sample into fixed-size batches, so that the editor
+/// receives a handful of small batches per second rather than one per processed block. Capturing
+/// never allocates. Completed batches should be passed on through an `EditorNotifier`, whose ring
+/// of batches is allocated up front, rather than through a channel.
+pub(super) struct ScopeCapture {
+    batch: [f32; SCOPE_BATCH_LEN],
+    batch_len: usize,
+    /// Number of samples to skip before the next one is captured.
+    skip: usize,
+}
+
+impl ScopeCapture {
+    pub fn new() -> Self {
+        Self {
+            batch: [0.; SCOPE_BATCH_LEN],
+            batch_len: 0,
+            skip: 0,
+        }
+    }
+
+    /// Captures the next part of the output signal from `samples`, calling `send` with each batch
+    /// that is completed.
+    pub fn capture(&mut self, samples: &[f32], mut send: impl FnMut([f32; SCOPE_BATCH_LEN])) {
+        let mut position = self.skip;
+        while position < samples.len() {
+            self.batch[self.batch_len] = samples[position];
+            self.batch_len += 1;
+            if self.batch_len == SCOPE_BATCH_LEN {
+                send(self.batch);
+                self.batch_len = 0;
+            }
+            position += SCOPE_DOWNSAMPLE;
+        }
+        self.skip = position - samples.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_evenly_across_blocks() {
+        let signal: Vec<f32> = (0..SCOPE_BATCH_LEN * SCOPE_DOWNSAMPLE * 2)
+            .map(|i| i as f32)
+            .collect();
+        let mut capture = ScopeCapture::new();
+        let mut batches = Vec::new();
+        // Use a block size that doesn't divide evenly into the downsampling factor.
+        for block in signal.chunks(100) {
+            capture.capture(block, |batch| batches.push(batch));
+        }
+
+        assert_eq!(batches.len(), 2);
+        let captured: Vec<f32> = batches.iter().flatten().copied().collect();
+        let expected: Vec<f32> = signal.iter().step_by(SCOPE_DOWNSAMPLE).copied().collect();
+        assert_eq!(captured, expected);
+    }
+}
@@ -1,17 +1,74 @@
-/// A `SmoothedRange` will get closer to its target value by this proportion of the difference
-/// between the current and target value on every `process` call.
-const FILTER_FACTOR: f32 = 0.005;
+/// Time taken for a `SmoothedRange` to cover about 63% of the distance to a new target value, in
+/// milliseconds, until told otherwise.
+pub const DEFAULT_SMOOTHING_TIME_MS: f32 = 72.;
+/// Number of times per second that a `SmoothedRange` is processed until told otherwise,
+/// corresponding to a 44.1kHz sample rate processed in chunks of 16 samples.
+const DEFAULT_UPDATE_RATE: f32 = 44100. / 16.;
 /// If a `SmoothedRange`'s value is at least this close to its target, it will "snap" to the
 /// target and stop smoothing.
 const SMOOTH_EPSILON: f32 = 0.001;
 
+/// Taper applied to the value of a `SmoothedRange` when it is returned. Smoothing always happens
+/// in the linear domain.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) enum SmoothCurve {
+    /// Values are returned unchanged.
+    #[default]
+    Linear,
+    /// Values are mapped onto an exponential curve through 0 and 1, which rises more slowly at the
+    /// low end of the range for larger values of `base`. Bases that aren't finite and positive,
+    /// or that are too close to 1 to form a curve, are treated as `Linear`.
+    // Not currently used by any parameter, but useful for perceptually even gain controls.
+    #[allow(dead_code)]
+    Exponential { base: f32 },
+}
+
+/// Exponential curves with a base closer to 1 than this are nearly straight, and are treated as
+/// linear rather than dividing by almost nothing.
+const MIN_CURVE_BASE_DISTANCE: f32 = 1e-3;
+
+impl SmoothCurve {
+    /// Replaces an exponential curve that can't be applied with `Linear`.
+    fn validated(self) -> Self {
+        match self {
+            Self::Exponential { base }
+                if !base.is_finite()
+                    || base <= 0.
+                    || (base - 1.).abs() < MIN_CURVE_BASE_DISTANCE =>
+            {
+                Self::Linear
+            }
+            curve => curve,
+        }
+    }
+
+    /// Maps a value from 0 to 1 onto this curve.
+    fn apply(self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Exponential { base } => (base.powf(value) - 1.) / (base - 1.),
+        }
+    }
+}
+
 /// Represents a value between 0. and 1. that exponentially interpolates towards a settable target
 /// value whenever it is processed. Allows efficient calculation of derived values by only
 /// returning values when it has been updated or smoothed.
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub(super) struct SmoothedRange {
     value: f32,
     target: f32,
+    curve: SmoothCurve,
+    /// Proportion of the difference between the current and target value covered on every
+    /// `process` call while rising towards a higher target.
+    attack_factor: f32,
+    /// Like `attack_factor`, but while falling towards a lower target.
+    release_factor: f32,
+    /// Change in value on every `process` call during a linear glide.
+    linear_step: f32,
+    /// Number of `process` calls left in a linear glide, or 0 if the value is moving
+    /// exponentially.
+    linear_updates: usize,
 
     needs_smooth: bool,
     did_change: bool,
@@ -19,19 +76,68 @@ pub(super) struct SmoothedRange {
 
 impl SmoothedRange {
     pub fn new(starting_value: f32) -> Self {
+        Self::with_curve(starting_value, SmoothCurve::Linear)
+    }
+
+    /// Creates a `SmoothedRange` whose returned values are mapped onto the given `curve`.
+    pub fn with_curve(starting_value: f32, curve: SmoothCurve) -> Self {
         Self {
             value: starting_value,
             target: starting_value,
+            curve: curve.validated(),
+            attack_factor: filter_factor(DEFAULT_SMOOTHING_TIME_MS, DEFAULT_UPDATE_RATE),
+            release_factor: filter_factor(DEFAULT_SMOOTHING_TIME_MS, DEFAULT_UPDATE_RATE),
+            linear_step: 0.,
+            linear_updates: 0,
             needs_smooth: false,
             did_change: true,
         }
     }
 
+    /// Adjusts the smoothing speed so that it takes `smoothing_time_ms` milliseconds to cover about
+    /// 63% of the distance to a new target, when `process` is called `update_rate` times per
+    /// second. Rising and falling take the same time.
+    pub fn set_smoothing_time(&mut self, smoothing_time_ms: f32, update_rate: f32) {
+        self.set_attack_release_times(smoothing_time_ms, smoothing_time_ms, update_rate);
+    }
+
+    /// Like `set_smoothing_time`, but rising towards a higher target takes `attack_ms` and falling
+    /// towards a lower target takes `release_ms`, for envelope-like behavior.
+    // No parameter uses different times yet, but they're useful for gain changes that should duck
+    // quickly and recover slowly, or vice versa.
+    pub fn set_attack_release_times(&mut self, attack_ms: f32, release_ms: f32, update_rate: f32) {
+        self.attack_factor = filter_factor(attack_ms, update_rate);
+        self.release_factor = filter_factor(release_ms, update_rate);
+    }
+
+    /// Moves towards the current target in equal steps over the next `num_updates` calls to
+    /// `process`, rather than exponentially. Setting a new target cancels the glide.
+    pub fn glide_linearly(&mut self, num_updates: usize) {
+        if self.needs_smooth && num_updates > 0 {
+            self.linear_step = (self.target - self.value) / num_updates as f32;
+            self.linear_updates = num_updates;
+        }
+    }
+
     /// Smoothes this parameter towards its target value if necessary.
     pub fn process(&mut self) {
-        if self.needs_smooth {
+        if self.needs_smooth && self.linear_updates > 0 {
+            self.did_change = true;
+            self.linear_updates -= 1;
+            if self.linear_updates == 0 {
+                self.value = self.target;
+                self.needs_smooth = false;
+            } else {
+                self.value += self.linear_step;
+            }
+        } else if self.needs_smooth {
             self.did_change = true;
-            self.value += (self.target - self.value) * FILTER_FACTOR;
+            let filter_factor = if self.target > self.value {
+                self.attack_factor
+            } else {
+                self.release_factor
+            };
+            self.value += (self.target - self.value) * filter_factor;
             if (self.value - self.target).abs() < SMOOTH_EPSILON {
                 self.value = self.target;
                 self.needs_smooth = false;
@@ -41,20 +147,150 @@ impl SmoothedRange {
         }
     }
 
-    /// Provides a new target to smooth towards.
+    /// Provides a new target to smooth towards. Values outside of the range are clamped to it, and
+    /// non-finite values are ignored, keeping the previous target.
     pub fn set(&mut self, value: f32) {
-        self.target = value;
+        if !value.is_finite() {
+            return;
+        }
+        self.target = value.clamp(0., 1.);
+        self.linear_updates = 0;
         self.needs_smooth = true;
         self.did_change = true;
     }
 
-    /// Return this parameter's value if it is different from its previous value because of
-    /// smoothing or updating.
+    /// Whether the value is still moving towards its target.
+    pub fn is_smoothing(&self) -> bool {
+        self.needs_smooth
+    }
+
+    /// Return this parameter's value, mapped onto its curve, if it is different from its previous
+    /// value because of smoothing or updating.
     pub fn get_new_value(&mut self) -> Option<f32> {
         if self.did_change {
-            Some(self.value)
+            Some(self.curve.apply(self.value))
         } else {
             None
         }
     }
 }
+
+/// Returns the proportion of the remaining distance to cover on each `process` call, for the given
+/// smoothing time and `update_rate`. A smoothing time of 0 jumps straight to the target.
+fn filter_factor(smoothing_time_ms: f32, update_rate: f32) -> f32 {
+    if smoothing_time_ms <= 0. {
+        return 1.;
+    }
+    1. - (-1000. / (smoothing_time_ms * update_rate)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value_at(position: f32, curve: SmoothCurve) -> f32 {
+        SmoothedRange::with_curve(position, curve)
+            .get_new_value()
+            .unwrap()
+    }
+
+    #[test]
+    fn linear_curve() {
+        assert_eq!(value_at(0., SmoothCurve::Linear), 0.);
+        assert_eq!(value_at(0.5, SmoothCurve::Linear), 0.5);
+        assert_eq!(value_at(1., SmoothCurve::Linear), 1.);
+    }
+
+    #[test]
+    fn exponential_curve() {
+        let curve = SmoothCurve::Exponential { base: 10. };
+        assert_eq!(value_at(0., curve), 0.);
+        assert!((value_at(0.5, curve) - (10f32.sqrt() - 1.) / 9.).abs() < 1e-6);
+        assert!((value_at(1., curve) - 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn degenerate_exponential_curve_is_linear() {
+        for base in [1., 1.0001, 0., -2., f32::NAN, f32::INFINITY] {
+            let curve = SmoothCurve::Exponential { base };
+            assert_eq!(value_at(0., curve), 0.);
+            assert_eq!(value_at(0.5, curve), 0.5);
+            assert_eq!(value_at(1., curve), 1.);
+        }
+    }
+
+    /// Counts the `process` calls needed for `range` to settle on `target`.
+    fn calls_to_reach(range: &mut SmoothedRange, target: f32) -> usize {
+        range.set(target);
+        let mut calls = 0;
+        while range.needs_smooth {
+            range.process();
+            calls += 1;
+        }
+        calls
+    }
+
+    /// Counts the `process` calls needed for a range to settle on a new target.
+    fn calls_to_converge(smoothing_time_ms: f32) -> usize {
+        let mut range = SmoothedRange::new(0.);
+        range.set_smoothing_time(smoothing_time_ms, DEFAULT_UPDATE_RATE);
+        calls_to_reach(&mut range, 1.)
+    }
+
+    #[test]
+    fn invalid_targets_stay_in_range() {
+        let mut range = SmoothedRange::new(0.5);
+        for &target in &[f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -1., 5.] {
+            range.set(target);
+            for _ in 0..1000 {
+                range.process();
+                let value = range.get_new_value().unwrap_or(range.value);
+                assert!(value.is_finite());
+                assert!((0. ..=1.).contains(&value));
+            }
+        }
+        range.set(f32::NAN);
+        assert_eq!(range.target, 1.);
+    }
+
+    #[test]
+    fn convergence_time_scales_with_smoothing_time() {
+        let short = calls_to_converge(10.) as f32;
+        let long = calls_to_converge(40.) as f32;
+        assert!((long / short - 4.).abs() < 0.2);
+    }
+
+    #[test]
+    fn attack_and_release_are_independent() {
+        let mut symmetric = SmoothedRange::new(0.25);
+        let up = calls_to_reach(&mut symmetric, 0.75);
+        assert_eq!(calls_to_reach(&mut symmetric, 0.25), up);
+
+        let mut asymmetric = SmoothedRange::new(0.25);
+        asymmetric.set_attack_release_times(10., 40., DEFAULT_UPDATE_RATE);
+        let attack = calls_to_reach(&mut asymmetric, 0.75) as f32;
+        let release = calls_to_reach(&mut asymmetric, 0.25) as f32;
+        assert!((release / attack - 4.).abs() < 0.2);
+    }
+
+    #[test]
+    fn linear_glide_takes_exact_number_of_calls() {
+        let mut range = SmoothedRange::new(0.);
+        range.set(1.);
+        range.glide_linearly(4);
+        range.process();
+        assert!((range.value - 0.25).abs() < 1e-6);
+        range.process();
+        assert!((range.value - 0.5).abs() < 1e-6);
+        range.process();
+        range.process();
+        assert_eq!(range.value, 1.);
+        assert!(!range.is_smoothing());
+
+        // A new target returns to exponential smoothing.
+        range.glide_linearly(4);
+        range.set(0.);
+        range.process();
+        assert!(range.value > 0.75);
+    }
+}
@@ -0,0 +1,78 @@
+//! Text copied from a knob's value, which can be pasted into any knob.
+//!
+//! With the `system-clipboard` feature, text is copied to the system clipboard, so values can be
+//! pasted into other applications too. Otherwise, or if the system clipboard can't be opened, the
+//! copied text is shared by every instance of the plugin within the same process, so exact
+//! settings can still be transferred between instances loaded by the same host.
+
+use std::sync::{Mutex, PoisonError};
+
+/// Somewhere that text can be copied to and pasted from.
+pub(super) trait Clipboard {
+    /// Replaces the copied text with `text`.
+    fn copy(&mut self, text: String);
+    /// Returns the most recently copied text, or `None` if nothing has been copied yet.
+    fn paste(&mut self) -> Option<String>;
+}
+
+/// Opens the clipboard that the editor should use.
+pub(super) fn open_clipboard() -> Box<dyn Clipboard> {
+    #[cfg(feature = "system-clipboard")]
+    match arboard::Clipboard::new() {
+        Ok(clipboard) => return Box::new(SystemClipboard(clipboard)),
+        Err(e) => log::warn!("couldn't open the system clipboard: {}", e),
+    }
+    Box::new(SharedClipboard)
+}
+
+/// Most recently copied text in any plugin instance, if any.
+static SHARED_TEXT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Clipboard shared by every instance of the plugin within the same process.
+pub(super) struct SharedClipboard;
+
+impl Clipboard for SharedClipboard {
+    fn copy(&mut self, text: String) {
+        *SHARED_TEXT.lock().unwrap_or_else(PoisonError::into_inner) = Some(text);
+    }
+
+    fn paste(&mut self) -> Option<String> {
+        SHARED_TEXT
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// The system clipboard, shared with other applications.
+#[cfg(feature = "system-clipboard")]
+struct SystemClipboard(arboard::Clipboard);
+
+#[cfg(feature = "system-clipboard")]
+impl Clipboard for SystemClipboard {
+    fn copy(&mut self, text: String) {
+        if let Err(e) = self.0.set_text(text) {
+            log::warn!("couldn't copy to the system clipboard: {}", e);
+        }
+    }
+
+    fn paste(&mut self) -> Option<String> {
+        self.0.get_text().ok()
+    }
+}
+
+/// Clipboard private to a single owner, so that tests don't interfere with each other.
+#[cfg(test)]
+#[derive(Default)]
+pub(super) struct LocalClipboard(Option<String>);
+
+#[cfg(test)]
+impl Clipboard for LocalClipboard {
+    fn copy(&mut self, text: String) {
+        self.0 = Some(text);
+    }
+
+    fn paste(&mut self) -> Option<String> {
+        self.0.clone()
+    }
+}
@@ -4,24 +4,122 @@
 //! very flexible, but requires a lot of setup!
 
 use cgmath::{prelude::SquareMatrix, Matrix4, Vector3};
-use once_cell::sync::Lazy;
 use wgpu::util::DeviceExt;
 use wgpu_glyph::{GlyphBrush, GlyphBrushBuilder};
 use zerocopy::AsBytes;
 
 use super::{
-    image_consts::{ORIG_BG_SIZE_X, ORIG_BG_SIZE_Y, ORIG_KNOB_RADIUS, ORIG_KNOB_X, ORIG_KNOB_Y},
-    SCALE, SIZE_X, SIZE_Y,
+    image_consts::{
+        KNOB_END_ANGLE, KNOB_START_ANGLE, ORIG_ABOUT_BUTTON_BOTTOM, ORIG_ABOUT_BUTTON_LEFT,
+        ORIG_ABOUT_BUTTON_RIGHT, ORIG_ABOUT_BUTTON_TOP, ORIG_BG_SIZE_X, ORIG_BG_SIZE_Y,
+        ORIG_CLIP_INDICATOR_BOTTOM, ORIG_CLIP_INDICATOR_TOP, ORIG_METER_BOTTOM, ORIG_METER_LEFT,
+        ORIG_METER_RIGHT, ORIG_METER_TOP, ORIG_MUTE_BUTTON_BOTTOM, ORIG_MUTE_BUTTON_LEFT,
+        ORIG_MUTE_BUTTON_RIGHT, ORIG_MUTE_BUTTON_TOP, ORIG_SNAPSHOT_BUTTON_BOTTOM,
+        ORIG_SNAPSHOT_BUTTON_TOP, ORIG_SNAPSHOT_STORE_SPANS, ORIG_SNAPSHOT_TOGGLE_SPAN,
+        ORIG_THEME_TOGGLE_BOTTOM, ORIG_THEME_TOGGLE_LEFT, ORIG_THEME_TOGGLE_RIGHT,
+        ORIG_THEME_TOGGLE_TOP, ORIG_TRIM_CONTROL_BOTTOM, ORIG_TRIM_CONTROL_LEFT,
+        ORIG_TRIM_CONTROL_RIGHT, ORIG_TRIM_CONTROL_TOP,
+    },
+    menu::{MENU_ITEMS, ORIG_MENU_WIDTH},
+    state::{METER_MIN_DB, SCOPE_LEN},
+    theme::{Image, ThemeAssets, THEMES, WHITE_TINT},
+    Knob, Layout, AMPLITUDE_KNOB, KNOBS,
 };
+use crate::parameters::format_amplitude;
+use crate::plugin_state::NUM_SNAPSHOTS;
 
-const MSAA_SAMPLES: u32 = 4;
+/// Number of samples taken per pixel for anti-aliasing, unless another count is requested.
+const DEFAULT_SAMPLE_COUNT: u32 = 4;
+/// Sample counts that every adapter supports for the render format. `wgpu` doesn't yet offer a way
+/// to query any others a particular adapter might support, so requests are limited to these.
+const SUPPORTED_SAMPLE_COUNTS: [u32; 2] = [1, 4];
+
+/// Setting this environment variable to any value makes the editor prefer a high-performance GPU,
+/// as with the `high-performance-gpu` feature.
+const HIGH_PERFORMANCE_GPU_VAR: &str = "AMPLIFE_HIGH_PERFORMANCE_GPU";
+
+/// Setting this environment variable to `dx12`, `vulkan`, `metal`, or `gl` limits the editor to
+/// that graphics backend, for when the default one fails on a particular machine.
+const BACKEND_VAR: &str = "AMPLIFE_WGPU_BACKEND";
+
+/// Parses the value of the `AMPLIFE_WGPU_BACKEND` environment variable, falling back to the
+/// primary backends if it's unset or invalid.
+fn backends(value: Option<&str>) -> wgpu::Backends {
+    let value = match value {
+        Some(value) => value.trim().to_ascii_lowercase(),
+        None => return wgpu::Backends::PRIMARY,
+    };
+    match value.as_str() {
+        "dx12" => wgpu::Backends::DX12,
+        "vulkan" => wgpu::Backends::VULKAN,
+        "metal" => wgpu::Backends::METAL,
+        "gl" => wgpu::Backends::GL,
+        _ => {
+            log::warn!(
+                "Unknown graphics backend {:?} in {}, using the default",
+                value,
+                BACKEND_VAR
+            );
+            wgpu::Backends::PRIMARY
+        }
+    }
+}
+
+/// Power preferences to request a GPU adapter with, in order of preference.
+///
+/// The interface is simple enough to draw on any GPU, so a low-power adapter is preferred by
+/// default to avoid waking up a discrete GPU on laptops.
+fn power_preferences() -> [wgpu::PowerPreference; 2] {
+    if cfg!(feature = "high-performance-gpu")
+        || std::env::var_os(HIGH_PERFORMANCE_GPU_VAR).is_some()
+    {
+        [
+            wgpu::PowerPreference::HighPerformance,
+            wgpu::PowerPreference::LowPower,
+        ]
+    } else {
+        [
+            wgpu::PowerPreference::LowPower,
+            wgpu::PowerPreference::HighPerformance,
+        ]
+    }
+}
+
+/// Reasons that the GPU could not be prepared for rendering.
+#[derive(Debug)]
+pub(in crate::editor) enum RendererError {
+    /// No GPU adapter is compatible with the editor window.
+    NoAdapter,
+    /// A GPU adapter was found, but a device could not be opened on it.
+    RequestDevice(wgpu::RequestDeviceError),
+}
+
+impl std::fmt::Display for RendererError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoAdapter => write!(f, "no compatible GPU adapter was found"),
+            Self::RequestDevice(e) => write!(f, "could not open a GPU device: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RendererError {}
 
 /// Contains all handles to GPU resources required for rendering the editor interface.
 pub(super) struct Renderer {
     device: wgpu::Device,
     queue: wgpu::Queue,
-    multisampled_framebuffer: wgpu::TextureView,
-    surface: wgpu::Surface,
+    /// Framebuffer that is drawn to and then resolved onto each frame, or `None` if anti-aliasing
+    /// is disabled and frames are drawn to directly.
+    multisampled_framebuffer: Option<wgpu::TextureView>,
+    /// Number of samples taken per pixel, as supported by the device.
+    sample_count: u32,
+    /// Surface of the window that frames are drawn on, or `None` for a renderer that only draws
+    /// off-screen.
+    surface: Option<wgpu::Surface>,
+    /// Describes the frames drawn, even if there's no surface to configure with it.
+    surface_config: wgpu::SurfaceConfiguration,
+    layout: Layout,
 
     text_renderer: GlyphBrush<()>,
     /// Required by `wgpu_glyph`
@@ -34,9 +132,81 @@ pub(super) struct Renderer {
     rectangle_vertex_buffer: wgpu::Buffer,
 
     background_bind_group: wgpu::BindGroup,
+    /// Holds the tint of the background, which is set by the theme.
+    background_tint_buffer: wgpu::Buffer,
+    /// Holds the tint shared by every knob's pointer, which is set by the theme.
+    pointer_tint_buffer: wgpu::Buffer,
+
+    /// Resources for drawing each knob in `KNOBS`, in the same order.
+    knobs: Vec<KnobGraphics>,
+
+    /// Resources for drawing each of the `NUM_METERS` output level meters, from left to right.
+    meters: Vec<MeterGraphics>,
+
+    /// Holds triangles of the value arcs around each knob, regenerated every frame.
+    arc_vertex_buffer: wgpu::Buffer,
+    arc_bind_group: wgpu::BindGroup,
+    /// Draws the arcs of knobs that are boosting the signal level.
+    boost_arc_bind_group: wgpu::BindGroup,
+
+    /// Holds triangles of the highlights around knobs recently changed from outside of the
+    /// editor, regenerated every frame.
+    highlight_vertex_buffer: wgpu::Buffer,
+    highlight_bind_group: wgpu::BindGroup,
+
+    /// Holds triangles of the tick marks around each knob, which never change.
+    tick_vertex_buffer: wgpu::Buffer,
+    tick_vertex_count: u32,
+    tick_bind_group: wgpu::BindGroup,
 
+    /// Draws lines connecting each point of the scope, using `scope_vertex_buffer`.
+    line_pipeline: wgpu::RenderPipeline,
+    /// Holds the points of the scope's waveform, regenerated every frame.
+    scope_vertex_buffer: wgpu::Buffer,
+    scope_track_bind_group: wgpu::BindGroup,
+    scope_bind_group: wgpu::BindGroup,
+
+    /// Draws the theme toggle in the font color of each theme in `THEMES`, in the same order.
+    theme_toggle_bind_groups: Vec<wgpu::BindGroup>,
+
+    /// Draws over the finished frame without anti-aliasing, so that the context menu and the about
+    /// overlay can cover text as well as images.
+    overlay_pipeline: wgpu::RenderPipeline,
+    /// Draws the context menu's background, moved into place whenever it is open.
+    menu_bind_group: wgpu::BindGroup,
+    menu_transform_buffer: wgpu::Buffer,
+    /// Draws the background of the context menu entry under the cursor.
+    menu_hover_bind_group: wgpu::BindGroup,
+    menu_hover_transform_buffer: wgpu::Buffer,
+    /// Draws the semi-transparent panel behind the about overlay, which never moves.
+    about_bind_group: wgpu::BindGroup,
+    /// Lines of text shown on the about overlay, from top to bottom.
+    about_lines: Vec<String>,
+}
+
+/// GPU resources used to draw a single knob.
+struct KnobGraphics {
+    /// Draws the knob's body, if it isn't already part of the background image.
+    body_bind_group: Option<wgpu::BindGroup>,
     pointer_bind_group: wgpu::BindGroup,
+    /// Holds the transformation of the pointer, which is rotated according to the knob's value.
     pointer_transform_buffer: wgpu::Buffer,
+    /// Scales and moves the knob images into place, before any rotation.
+    base_transform: Matrix4<f32>,
+}
+
+/// GPU resources used to draw a single output level meter.
+struct MeterGraphics {
+    track_bind_group: wgpu::BindGroup,
+    bar_bind_group: wgpu::BindGroup,
+    /// Holds the transformation of the bar, which is resized according to the output level.
+    bar_transform_buffer: wgpu::Buffer,
+    peak_hold_bind_group: wgpu::BindGroup,
+    /// Holds the transformation of the peak hold line, which is moved to the held level.
+    peak_hold_transform_buffer: wgpu::Buffer,
+    clip_hold_bind_group: wgpu::BindGroup,
+    /// Left and right edges of the meter, in pixels of the original background image.
+    span: (f64, f64),
 }
 
 /// Low-level representation of a point in 3D space. This representation is designed to be shared
@@ -67,50 +237,211 @@ struct TransformUniform {
     transform: [[f32; 4]; 4],
 }
 
-const BACKGROUND_IMAGE: &[u8] = include_bytes!("../../../assets/images/bg.png");
-const POINTER_IMAGE: &[u8] = include_bytes!("../../../assets/images/pointer.png");
-const FONT: &[u8] = include_bytes!("../../../assets/fonts/iosevka-Iosevka-medium.ttf");
-const FONT_COLOR: [f32; 4] = [1.0, 0.51, 0.0, 1.0];
+/// Color multiplied with every sampled texture color of a bind group. This representation is
+/// designed to be shared directly with GPU memory for use in shaders.
+#[repr(C)]
+#[derive(Clone, Copy, AsBytes)]
+struct TintUniform {
+    color: [f32; 4],
+}
 
-const TEXT_RIGHT_ANCHOR: f32 = 460. * SCALE as f32;
-const TEXT_CENTER_Y_ANCHOR: f32 = 500. * SCALE as f32;
+/// Right edge of the value readout, in pixels of the original background image.
+const ORIG_TEXT_RIGHT_ANCHOR: f64 = 460.;
+/// Vertical center of the value readout, in pixels of the original background image.
+const ORIG_TEXT_CENTER_Y_ANCHOR: f64 = 500.;
+/// Height of the value readout text, in pixels of the original background image.
+const ORIG_TEXT_SCALE: f64 = 100.;
+/// Opacity of the value readout text, relative to normal, while the parameter is being smoothed.
+const SMOOTHING_TEXT_ALPHA: f32 = 0.5;
+/// Height of the button labels, in pixels of the original background image.
+const ORIG_BUTTON_TEXT_SCALE: f64 = 36.;
+/// Names of the A/B snapshots shown on the snapshot buttons, in slot order.
+const SNAPSHOT_NAMES: [&str; NUM_SNAPSHOTS] = ["A", "B"];
+/// Color of the mute button label while the output is muted.
+const MUTED_TEXT_COLOR: [f32; 4] = [0.9, 0.12, 0.12, 1.];
+/// Label shown above the meters while the input is clipping.
+const INPUT_CLIP_TEXT: &str = "IN CLIP";
+/// Color of the input clipping indicator.
+const INPUT_CLIP_TEXT_COLOR: [f32; 4] = [0.9, 0.12, 0.12, 1.];
+/// Vertical center of the input clipping indicator, in pixels of the original background image.
+/// It's centered horizontally over the meters.
+const ORIG_INPUT_CLIP_CENTER_Y: f64 = 260.;
 
-/// Scales and moves the original knob image from ([-1,1],[-1,1]) to its correct position on the
+/// Color of the context menu's background.
+const MENU_COLOR: [u8; 4] = [35, 35, 35, 240];
+/// Color behind the context menu entry under the cursor.
+const MENU_HOVER_COLOR: [u8; 4] = [75, 75, 75, 255];
+/// Distance between the left edge of the context menu and its labels, in pixels of the original
 /// background image.
-static SCALE_MOVE_KNOB_TRANSFORM: Lazy<Matrix4<f32>> = Lazy::new(|| {
+const ORIG_MENU_TEXT_PADDING: f64 = 16.;
+
+/// Label of the button that opens the about overlay.
+const ABOUT_BUTTON_TEXT: &str = "About";
+/// Color of the about overlay's panel, which lets the interface show through faintly.
+const ABOUT_PANEL_COLOR: [u8; 4] = [20, 20, 20, 225];
+/// Bounds of the about overlay's panel, in pixels of the original background image.
+const ORIG_ABOUT_PANEL_LEFT: f64 = 300.;
+const ORIG_ABOUT_PANEL_TOP: f64 = 250.;
+const ORIG_ABOUT_PANEL_RIGHT: f64 = 900.;
+const ORIG_ABOUT_PANEL_BOTTOM: f64 = 550.;
+/// Vertical distance between the centers of consecutive lines on the about overlay, in pixels of
+/// the original background image.
+const ORIG_ABOUT_LINE_SPACING: f64 = 60.;
+
+/// Color of the empty part of the output level meter.
+const METER_TRACK_COLOR: [u8; 4] = [27, 27, 27, 255];
+/// Color of the filled part of the output level meter.
+const METER_COLOR: [u8; 4] = [255, 130, 0, 255];
+/// Color of the line marking the peak level held by each meter.
+const PEAK_HOLD_COLOR: [u8; 4] = [255, 220, 150, 255];
+/// Thickness of the peak hold line, in pixels of the original background image.
+const ORIG_PEAK_HOLD_THICKNESS: f64 = 3.;
+/// Color of the indicator above each meter once it has reached full scale.
+const CLIP_HOLD_COLOR: [u8; 4] = [230, 30, 30, 255];
+/// Horizontal space between the left and right meters, in pixels of the original background
+/// image.
+const ORIG_METER_GAP: f64 = 4.;
+/// Number of output level meters: a single centered meter for mono builds, or separate left and
+/// right meters otherwise.
+const NUM_METERS: usize = if crate::NUM_CHANNELS == 1 { 1 } else { 2 };
+/// Color of the arcs indicating the value of each knob.
+const ARC_COLOR: [u8; 4] = [255, 130, 0, 255];
+/// Color of the arcs of knobs that are boosting the signal level.
+const BOOST_ARC_COLOR: [u8; 4] = [230, 30, 30, 255];
+/// Inner and outer radius of the value arcs, as proportions of the radius of their knob.
+const ARC_INNER_RADIUS: f64 = 1.04;
+const ARC_OUTER_RADIUS: f64 = 1.1;
+/// Number of straight segments used to draw an arc covering a knob's full range.
+const ARC_SEGMENTS: usize = 64;
+/// Translucent color of the highlight around knobs changed from outside of the editor.
+const HIGHLIGHT_COLOR: [u8; 4] = [255, 220, 150, 110];
+/// Half of the width of the highlight around a knob at full strength, as a proportion of the
+/// radius of the knob. The highlight is centered on the value arc, and narrows as it fades.
+const HIGHLIGHT_HALF_WIDTH: f64 = 0.08;
+/// Number of straight segments used to draw the highlight around a knob.
+const HIGHLIGHT_SEGMENTS: usize = 64;
+/// Number of evenly spaced tick marks drawn around each knob, including its minimum and maximum
+/// positions.
+const NUM_TICKS: usize = 11;
+/// Color of the tick marks around each knob.
+const TICK_COLOR: [u8; 4] = [150, 150, 150, 255];
+/// Inner and outer radius of the tick marks, as proportions of the radius of their knob.
+const TICK_INNER_RADIUS: f64 = 1.12;
+const TICK_OUTER_RADIUS: f64 = 1.2;
+/// Angular width of each tick mark, in degrees.
+const TICK_WIDTH_DEGREES: f32 = 1.5;
+
+/// Color of the scope's waveform.
+const SCOPE_COLOR: [u8; 4] = [255, 130, 0, 255];
+/// Bounds of the scope, in pixels of the original background image.
+const ORIG_SCOPE_LEFT: f64 = 60.;
+const ORIG_SCOPE_RIGHT: f64 = 500.;
+const ORIG_SCOPE_TOP: f64 = 80.;
+const ORIG_SCOPE_BOTTOM: f64 = 320.;
+
+/// Output level displayed at the top of the meter. The bottom is at `METER_MIN_DB`.
+const METER_MAX_DB: f32 = 6.;
+
+/// Scales and moves the original knob images from ([-1,1],[-1,1]) to the correct position of
+/// `knob` on the background image.
+fn scale_move_knob_transform(knob: &Knob) -> Matrix4<f32> {
+    let (x, y) = (knob.center.0 as f32, knob.center.1 as f32);
     Matrix4::from_translation(Vector3::new(
-        2. * (ORIG_KNOB_X - ORIG_BG_SIZE_X / 2) as f32 / ORIG_BG_SIZE_X as f32,
-        2. * -((ORIG_KNOB_Y - ORIG_BG_SIZE_Y / 2) as f32) / ORIG_BG_SIZE_Y as f32,
+        2. * (x - ORIG_BG_SIZE_X as f32 / 2.) / ORIG_BG_SIZE_X as f32,
+        2. * -(y - ORIG_BG_SIZE_Y as f32 / 2.) / ORIG_BG_SIZE_Y as f32,
         0.,
     )) * Matrix4::from_nonuniform_scale(
-        (ORIG_KNOB_RADIUS * 2) as f32 / ORIG_BG_SIZE_X as f32,
-        (ORIG_KNOB_RADIUS * 2) as f32 / ORIG_BG_SIZE_Y as f32,
+        (knob.radius * 2) as f32 / ORIG_BG_SIZE_X as f32,
+        (knob.radius * 2) as f32 / ORIG_BG_SIZE_Y as f32,
         1.,
     )
-});
+}
 
 impl Renderer {
-    /// Creates a new `Renderer` by initializing the GPU to prepare it for rendering.
-    pub fn new<W: raw_window_handle::HasRawWindowHandle>(handle: W) -> Self {
-        let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
+    /// Creates a new `Renderer` by initializing the GPU to prepare it for rendering with the given
+    /// `layout`, using the default anti-aliasing and any replacement assets specified by
+    /// environment variables.
+    pub fn new<W: raw_window_handle::HasRawWindowHandle>(
+        handle: W,
+        layout: Layout,
+    ) -> Result<Self, RendererError> {
+        Self::with_theme(
+            handle,
+            layout,
+            ThemeAssets::from_environment(),
+            DEFAULT_SAMPLE_COUNT,
+        )
+    }
+
+    /// Creates a new `Renderer` like `new`, drawing the interface with any replacement assets
+    /// specified by `theme`.
+    ///
+    /// Each pixel is anti-aliased by taking `sample_count` samples. If that count isn't supported,
+    /// the nearest supported count is used instead; a count of 1 disables anti-aliasing, as does
+    /// a software adapter.
+    ///
+    /// Fails if the GPU can't be used to render to the window, in which case the editor can't be
+    /// shown.
+    pub fn with_theme<W: raw_window_handle::HasRawWindowHandle>(
+        handle: W,
+        layout: Layout,
+        theme: ThemeAssets,
+        sample_count: u32,
+    ) -> Result<Self, RendererError> {
+        let instance = wgpu::Instance::new(backends(std::env::var(BACKEND_VAR).ok().as_deref()));
 
         // Acquire the window as a surface to be rendered on.
         // This is the only unsafe code in the plugin; it is only required to satisfy the
         // `raw_window_handle` API. Safety is upheld by taking ownership of `handle` in the
         // function signature, ensuring it is only ever used to create a single surface.
         let surface = unsafe { instance.create_surface(&handle) };
+        Self::with_surface(&instance, Some(surface), layout, theme, sample_count)
+    }
+
+    /// Creates a new `Renderer` like `with_theme`, drawing off-screen rather than onto a window.
+    /// Frames can only be drawn with `render_to_image`.
+    #[cfg(test)]
+    pub fn offscreen(
+        layout: Layout,
+        theme: ThemeAssets,
+        sample_count: u32,
+    ) -> Result<Self, RendererError> {
+        let instance = wgpu::Instance::new(backends(std::env::var(BACKEND_VAR).ok().as_deref()));
+        Self::with_surface(&instance, None, layout, theme, sample_count)
+    }
 
+    /// Creates a new `Renderer` like `with_theme`, drawing onto `surface` if there is one.
+    fn with_surface(
+        instance: &wgpu::Instance,
+        surface: Option<wgpu::Surface>,
+        layout: Layout,
+        theme: ThemeAssets,
+        sample_count: u32,
+    ) -> Result<Self, RendererError> {
         // Get a handle to the GPU and a queue of commands to be uploaded to it while rendering.
-        let (device, queue) = futures::executor::block_on(async {
-            let adapter = instance
-                .request_adapter(&wgpu::RequestAdapterOptions {
-                    power_preference: wgpu::PowerPreference::HighPerformance,
-                    force_fallback_adapter: false,
-                    compatible_surface: Some(&surface),
-                })
-                .await
-                .unwrap();
+        let (device, queue, device_type) = futures::executor::block_on(async {
+            let mut adapter = None;
+            for power_preference in power_preferences() {
+                adapter = instance
+                    .request_adapter(&wgpu::RequestAdapterOptions {
+                        power_preference,
+                        force_fallback_adapter: false,
+                        compatible_surface: surface.as_ref(),
+                    })
+                    .await;
+                if adapter.is_some() {
+                    break;
+                }
+            }
 
+            let adapter = adapter.ok_or(RendererError::NoAdapter)?;
+            let info = adapter.get_info();
+            log::info!(
+                "drawing the editor with {} ({:?}, {:?})",
+                info.name,
+                info.device_type,
+                info.backend
+            );
             adapter
                 .request_device(
                     &wgpu::DeviceDescriptor {
@@ -121,18 +452,15 @@ impl Renderer {
                     None,
                 )
                 .await
-                .unwrap()
-        });
+                .map(|(device, queue)| (device, queue, info.device_type))
+                .map_err(RendererError::RequestDevice)
+        })?;
 
-        // Shaders are written in GLSL and compiled to SPIR-V from `build.rs`. They describe how
-        // to layout points in space (vertex shaders), or how to render triangular fragments to
-        // the screen (fragment shaders). The resulting SPIR-V is loaded to the GPU at runtime.
-        let vs_module = device.create_shader_module(&wgpu::include_spirv!(
-            "../../../assets/generated/spirv/shader.vert.spv"
-        ));
-        let fs_module = device.create_shader_module(&wgpu::include_spirv!(
-            "../../../assets/generated/spirv/shader.frag.spv"
-        ));
+        // Shaders are written in WGSL, and compiled for the GPU at runtime. They describe how to
+        // layout points in space (vertex shaders), or how to render triangular fragments to the
+        // screen (fragment shaders).
+        let shader_module = device
+            .create_shader_module(&wgpu::include_wgsl!("../../../assets/shaders/shader.wgsl"));
 
         // Bind group layouts describe data available to the GPU in different shader stages.
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -170,6 +498,18 @@ impl Renderer {
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None,
                 },
+                // Binding 3 is a uniform buffer holding a color that the fragment shader
+                // multiplies with each sampled texture color, so that images can be recolored.
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -177,15 +517,22 @@ impl Renderer {
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: render_format,
-            width: SIZE_X as u32,
-            height: SIZE_Y as u32,
+            width: layout.window_size.0,
+            height: layout.window_size.1,
             present_mode: wgpu::PresentMode::Mailbox,
         };
-        surface.configure(&device, &config);
+        if let Some(surface) = &surface {
+            surface.configure(&device, &config);
+        }
 
-        // A multisampled framebuffer is used for anti-aliasing.
+        // A multisampled framebuffer is used for anti-aliasing, unless there's no GPU to make it
+        // affordable. Without one, frames are drawn directly onto the surface.
+        let sample_count = adapter_sample_count(sample_count, device_type);
+        if sample_count != DEFAULT_SAMPLE_COUNT {
+            log::info!("Using {} samples per pixel", sample_count);
+        }
         let multisampled_framebuffer =
-            create_multisampled_framebuffer(&device, &config, MSAA_SAMPLES);
+            create_multisampled_framebuffer(&device, &config, sample_count);
 
         // The graphics pipeline specifies what behavior to use when rendering to the screen.
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -193,52 +540,46 @@ impl Renderer {
             bind_group_layouts: &[&bind_group_layout],
             push_constant_ranges: &[],
         });
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &vs_module,
-                entry_point: "main",
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x2],
-                }],
+        let pipeline = create_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader_module,
+            config.format,
+            sample_count,
+            wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
             },
-            fragment: Some(wgpu::FragmentState {
-                module: &fs_module,
-                entry_point: "main",
-                targets: &[wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent {
-                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                        alpha: wgpu::BlendComponent {
-                            src_factor: wgpu::BlendFactor::One,
-                            dst_factor: wgpu::BlendFactor::One,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                    }),
-                    write_mask: wgpu::ColorWrites::ALL,
-                }],
-            }),
-            primitive: wgpu::PrimitiveState {
+        );
+        // The context menu and the about overlay are drawn after the text, directly onto the
+        // frame, which has only one sample per pixel.
+        let overlay_pipeline = create_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader_module,
+            config.format,
+            1,
+            wgpu::PrimitiveState {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 front_face: wgpu::FrontFace::Ccw,
                 cull_mode: Some(wgpu::Face::Back),
                 ..Default::default()
             },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: MSAA_SAMPLES,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
+        );
+        // The scope is drawn as a single connected line, using a separate pipeline.
+        let line_pipeline = create_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader_module,
+            config.format,
+            sample_count,
+            wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineStrip,
+                ..Default::default()
             },
-            multiview: None,
-        });
+        );
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -256,7 +597,7 @@ impl Renderer {
         let rectangle_vertex_buffer =
             device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: None,
-                contents: &[
+                contents: [
                     Vertex::new(1., 1., 1., 0.),
                     Vertex::new(-1., 1., 0., 0.),
                     Vertex::new(-1., -1., 0., 1.),
@@ -267,41 +608,290 @@ impl Renderer {
             });
         let rectangle_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
-            contents: &[0u32, 1, 2, 2, 3, 0].as_bytes(),
+            contents: [0u32, 1, 2, 2, 3, 0].as_bytes(),
             usage: wgpu::BufferUsages::INDEX,
         });
 
         // Different bind groups for the background and pointer allow them to be rendered with a
         // different appearance. We also save the uniform buffer used to transform the pointer, so
         // that we can give it a different rotation later on. The background doesn't move, so we
-        // never need to update its uniform buffer.
+        // never need to update its uniform buffer. Their tints are held in separate buffers, which
+        // are updated to match the theme whenever a frame is drawn.
+        let background_tint_buffer = make_tint_buffer(&device, WHITE_TINT);
+        let pointer_tint_buffer = make_tint_buffer(&device, WHITE_TINT);
+        let knob_body_tint_buffer = make_tint_buffer(&device, WHITE_TINT);
         let (background_bind_group, _) = make_bind_group(
             &device,
             &queue,
             &bind_group_layout,
             &sampler,
-            BACKGROUND_IMAGE,
+            &theme.background(),
+            &background_tint_buffer,
+            Matrix4::identity(),
+        );
+        let knob_image = theme.knob();
+        let pointer_image = theme.pointer();
+        let knobs = KNOBS
+            .iter()
+            .map(|knob| {
+                let base_transform = scale_move_knob_transform(knob);
+                let body_bind_group = if knob.draws_body {
+                    let (body_bind_group, _) = make_bind_group(
+                        &device,
+                        &queue,
+                        &bind_group_layout,
+                        &sampler,
+                        &knob_image,
+                        &knob_body_tint_buffer,
+                        base_transform,
+                    );
+                    Some(body_bind_group)
+                } else {
+                    None
+                };
+                let (pointer_bind_group, pointer_transform_buffer) = make_bind_group(
+                    &device,
+                    &queue,
+                    &bind_group_layout,
+                    &sampler,
+                    &pointer_image,
+                    &pointer_tint_buffer,
+                    base_transform,
+                );
+                KnobGraphics {
+                    body_bind_group,
+                    pointer_bind_group,
+                    pointer_transform_buffer,
+                    base_transform,
+                }
+            })
+            .collect();
+
+        // Each meter is drawn as a solid-colored track, partially covered by a solid-colored bar
+        // that is resized according to the current output level, and a line that is moved to the
+        // held peak level. A solid-colored indicator above it shows whether it has clipped.
+        let meters = meter_spans()
+            .map(|span| {
+                let meter_rect = image_rect_transform(
+                    span.0,
+                    ORIG_METER_TOP as f64,
+                    span.1,
+                    ORIG_METER_BOTTOM as f64,
+                );
+                let (track_bind_group, _) = make_solid_bind_group(
+                    &device,
+                    &queue,
+                    &bind_group_layout,
+                    &sampler,
+                    METER_TRACK_COLOR,
+                    meter_rect,
+                );
+                let (bar_bind_group, bar_transform_buffer) = make_solid_bind_group(
+                    &device,
+                    &queue,
+                    &bind_group_layout,
+                    &sampler,
+                    METER_COLOR,
+                    meter_rect,
+                );
+                let (peak_hold_bind_group, peak_hold_transform_buffer) = make_solid_bind_group(
+                    &device,
+                    &queue,
+                    &bind_group_layout,
+                    &sampler,
+                    PEAK_HOLD_COLOR,
+                    meter_rect,
+                );
+                let (clip_hold_bind_group, _) = make_solid_bind_group(
+                    &device,
+                    &queue,
+                    &bind_group_layout,
+                    &sampler,
+                    CLIP_HOLD_COLOR,
+                    image_rect_transform(
+                        span.0,
+                        ORIG_CLIP_INDICATOR_TOP as f64,
+                        span.1,
+                        ORIG_CLIP_INDICATOR_BOTTOM as f64,
+                    ),
+                );
+                MeterGraphics {
+                    track_bind_group,
+                    bar_bind_group,
+                    bar_transform_buffer,
+                    peak_hold_bind_group,
+                    peak_hold_transform_buffer,
+                    clip_hold_bind_group,
+                    span,
+                }
+            })
+            .collect();
+
+        // Each knob's value arc is drawn as a solid-colored set of triangles, generated directly in
+        // normalized device coordinates so that no transformation is needed.
+        let arc_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (KNOBS.len() * ARC_SEGMENTS * 6 * std::mem::size_of::<Vertex>())
+                as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let (arc_bind_group, _) = make_solid_bind_group(
+            &device,
+            &queue,
+            &bind_group_layout,
+            &sampler,
+            ARC_COLOR,
             Matrix4::identity(),
         );
-        let (pointer_bind_group, pointer_transform_buffer) = make_bind_group(
+        let (boost_arc_bind_group, _) = make_solid_bind_group(
             &device,
             &queue,
             &bind_group_layout,
             &sampler,
-            POINTER_IMAGE,
-            *SCALE_MOVE_KNOB_TRANSFORM,
+            BOOST_ARC_COLOR,
+            Matrix4::identity(),
         );
 
+        // Highlights are generated the same way as the arcs.
+        let highlight_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (KNOBS.len() * HIGHLIGHT_SEGMENTS * 6 * std::mem::size_of::<Vertex>())
+                as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let (highlight_bind_group, _) = make_solid_bind_group(
+            &device,
+            &queue,
+            &bind_group_layout,
+            &sampler,
+            HIGHLIGHT_COLOR,
+            Matrix4::identity(),
+        );
+
+        // The scope is drawn over a solid-colored track, with its points generated directly in
+        // normalized device coordinates like the arcs.
+        let (scope_track_bind_group, _) = make_solid_bind_group(
+            &device,
+            &queue,
+            &bind_group_layout,
+            &sampler,
+            METER_TRACK_COLOR,
+            image_rect_transform(
+                ORIG_SCOPE_LEFT,
+                ORIG_SCOPE_TOP,
+                ORIG_SCOPE_RIGHT,
+                ORIG_SCOPE_BOTTOM,
+            ),
+        );
+        let scope_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (SCOPE_LEN * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let (scope_bind_group, _) = make_solid_bind_group(
+            &device,
+            &queue,
+            &bind_group_layout,
+            &sampler,
+            SCOPE_COLOR,
+            Matrix4::identity(),
+        );
+
+        // Tick marks are generated the same way, but only once.
+        let tick_vertices: Vec<Vertex> = KNOBS.iter().flat_map(tick_vertices).collect();
+        let tick_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: tick_vertices.as_bytes(),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let (tick_bind_group, _) = make_solid_bind_group(
+            &device,
+            &queue,
+            &bind_group_layout,
+            &sampler,
+            TICK_COLOR,
+            Matrix4::identity(),
+        );
+
+        let theme_toggle_rect = image_rect_transform(
+            ORIG_THEME_TOGGLE_LEFT as f64,
+            ORIG_THEME_TOGGLE_TOP as f64,
+            ORIG_THEME_TOGGLE_RIGHT as f64,
+            ORIG_THEME_TOGGLE_BOTTOM as f64,
+        );
+        let theme_toggle_bind_groups = THEMES
+            .iter()
+            .map(|theme| {
+                let color = theme.font_color.map(|channel| (channel * 255.) as u8);
+                let (bind_group, _) = make_solid_bind_group(
+                    &device,
+                    &queue,
+                    &bind_group_layout,
+                    &sampler,
+                    color,
+                    theme_toggle_rect,
+                );
+                bind_group
+            })
+            .collect();
+
+        // The context menu is drawn as solid-colored rectangles, which are moved into place
+        // whenever it's open.
+        let (menu_bind_group, menu_transform_buffer) = make_solid_bind_group(
+            &device,
+            &queue,
+            &bind_group_layout,
+            &sampler,
+            MENU_COLOR,
+            Matrix4::identity(),
+        );
+        let (menu_hover_bind_group, menu_hover_transform_buffer) = make_solid_bind_group(
+            &device,
+            &queue,
+            &bind_group_layout,
+            &sampler,
+            MENU_HOVER_COLOR,
+            Matrix4::identity(),
+        );
+
+        // The about overlay is described by the plugin's metadata, so that forks show their own
+        // name.
+        let (about_bind_group, _) = make_solid_bind_group(
+            &device,
+            &queue,
+            &bind_group_layout,
+            &sampler,
+            ABOUT_PANEL_COLOR,
+            image_rect_transform(
+                ORIG_ABOUT_PANEL_LEFT,
+                ORIG_ABOUT_PANEL_TOP,
+                ORIG_ABOUT_PANEL_RIGHT,
+                ORIG_ABOUT_PANEL_BOTTOM,
+            ),
+        );
+        let metadata = crate::metadata::plugin_metadata();
+        let about_lines = vec![
+            metadata.name,
+            format!("Version {}", env!("CARGO_PKG_VERSION")),
+            format!("by {}", metadata.vendor),
+            "Click anywhere to close".to_string(),
+        ];
+
         // Font rendering is conveniently handled by `wgpu_glyph` :)
-        let fonts: Vec<wgpu_glyph::ab_glyph::FontArc> =
-            vec![wgpu_glyph::ab_glyph::FontArc::try_from_slice(FONT).unwrap()];
+        let fonts: Vec<wgpu_glyph::ab_glyph::FontArc> = vec![theme.font()];
         let text_renderer = GlyphBrushBuilder::using_fonts(fonts).build(&device, render_format);
 
-        Self {
+        Ok(Self {
             device,
             queue,
             multisampled_framebuffer,
+            sample_count,
             surface,
+            surface_config: config,
+            layout,
 
             text_renderer,
             local_pool: futures::executor::LocalPool::new(),
@@ -312,112 +902,674 @@ impl Renderer {
             rectangle_vertex_buffer,
 
             background_bind_group,
+            background_tint_buffer,
+            pointer_tint_buffer,
+
+            knobs,
+
+            meters,
+
+            arc_vertex_buffer,
+            arc_bind_group,
+            boost_arc_bind_group,
+
+            highlight_vertex_buffer,
+            highlight_bind_group,
+
+            tick_vertex_buffer,
+            tick_vertex_count: tick_vertices.len() as u32,
+            tick_bind_group,
+
+            line_pipeline,
+            scope_vertex_buffer,
+            scope_track_bind_group,
+            scope_bind_group,
+
+            theme_toggle_bind_groups,
 
-            pointer_bind_group,
-            pointer_transform_buffer,
+            overlay_pipeline,
+            menu_bind_group,
+            menu_transform_buffer,
+            menu_hover_bind_group,
+            menu_hover_transform_buffer,
+            about_bind_group,
+            about_lines,
+        })
+    }
+
+    /// Applies the current surface configuration, and recreates the multisampled framebuffer to
+    /// match.
+    fn configure_surface(&mut self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.surface_config);
+        }
+        self.multisampled_framebuffer =
+            create_multisampled_framebuffer(&self.device, &self.surface_config, self.sample_count);
+    }
+
+    /// Gets the next texture to draw a frame on. If the surface has become outdated or lost, for
+    /// example because the display configuration changed, it is reconfigured before trying once
+    /// more. Returns `None` if no texture is available, in which case the frame should be skipped.
+    fn acquire_frame(&mut self) -> Option<wgpu::SurfaceTexture> {
+        match self.surface.as_ref()?.get_current_texture() {
+            Ok(frame) => Some(frame),
+            Err(wgpu::SurfaceError::Outdated) | Err(wgpu::SurfaceError::Lost) => {
+                self.configure_surface();
+                self.surface.as_ref()?.get_current_texture().ok()
+            }
+            Err(wgpu::SurfaceError::Timeout) | Err(wgpu::SurfaceError::OutOfMemory) => None,
         }
     }
 
     /// Render a single frame of the given interface state to the screen.
     pub fn draw_frame(&mut self, state: &super::state::InterfaceState) {
-        if let Ok(frame) = self.surface.get_current_texture() {
+        if let Some(frame) = self.acquire_frame() {
+            let view = frame
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default());
             let mut encoder = self
                 .device
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-            {
-                // Pointer starts at top position in source image. Knob limits are 150 degrees in
-                // both directions.
-                let data = TransformUniform {
-                    transform: (*SCALE_MOVE_KNOB_TRANSFORM
-                        * Matrix4::from_angle_z(cgmath::Deg(-state.amplitude_value * 300. + 150.)))
-                    .into(),
-                };
-                self.queue.write_buffer(
-                    &self.pointer_transform_buffer,
-                    0 as wgpu::BufferAddress,
-                    data.as_bytes(),
-                );
+            self.render(state, &mut encoder, &view);
+            self.submit(encoder);
+            frame.present();
+        }
+    }
 
-                let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    /// Draws a single frame of the given interface state off-screen, and reads it back from the GPU
+    /// as an RGBA image with the size of the window.
+    #[cfg(test)]
+    pub fn render_to_image(&mut self, state: &super::state::InterfaceState) -> Image {
+        let (width, height) = self.layout.window_size;
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.surface_config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.render(state, &mut encoder, &view);
 
-                {
-                    let mut rpass = Self::start_renderpass(
-                        &mut encoder,
-                        &view,
-                        &self.multisampled_framebuffer,
-                    );
-                    rpass.set_pipeline(&self.pipeline);
-                    rpass.set_index_buffer(self.rectangle_index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                    rpass.set_vertex_buffer(0, self.rectangle_vertex_buffer.slice(..));
+        // Rows of a texture copy must be padded to a multiple of `COPY_BYTES_PER_ROW_ALIGNMENT`.
+        let row_len = width as usize * 4;
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+        let padded_row_len = row_len.div_ceil(alignment) * alignment;
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (padded_row_len * height as usize) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(padded_row_len as u32),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.submit(encoder);
+
+        let slice = readback_buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+        futures::executor::block_on(mapping).expect("Map readback buffer");
+        // Frames are drawn in BGRA order, so the red and blue channels are swapped back.
+        let data = slice
+            .get_mapped_range()
+            .chunks_exact(padded_row_len)
+            .flat_map(|row| row[..row_len].chunks_exact(4))
+            .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+            .collect();
+        Image {
+            data,
+            size: (width, height),
+        }
+    }
+
+    /// Submits the commands recorded by `encoder` to the GPU, along with any text uploads.
+    fn submit(&mut self, encoder: wgpu::CommandEncoder) {
+        self.staging_belt.finish();
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        use futures::task::SpawnExt;
+        self.local_pool
+            .spawner()
+            .spawn(self.staging_belt.recall())
+            .expect("Recall staging belt");
+        self.local_pool.run_until_stalled();
+    }
+
+    /// Records the commands that draw a frame of the given interface state onto `view`, which has
+    /// the size of the window.
+    fn render(
+        &mut self,
+        state: &super::state::InterfaceState,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        let theme = &THEMES[state.theme];
+        for (buffer, color) in [
+            (&self.background_tint_buffer, theme.background_tint),
+            (&self.pointer_tint_buffer, theme.pointer_tint),
+        ] {
+            self.queue.write_buffer(
+                buffer,
+                0 as wgpu::BufferAddress,
+                TintUniform { color }.as_bytes(),
+            );
+        }
+
+        for (knob, value) in self.knobs.iter().zip(state.displayed_knob_values.iter()) {
+            let data = TransformUniform {
+                transform: (knob.base_transform
+                    * Matrix4::from_angle_z(cgmath::Deg(knob_angle(*value))))
+                .into(),
+            };
+            self.queue.write_buffer(
+                &knob.pointer_transform_buffer,
+                0 as wgpu::BufferAddress,
+                data.as_bytes(),
+            );
+        }
+
+        // A single meter shows the left channel, which mono output is reported in.
+        for ((meter, level), peak_hold) in self
+            .meters
+            .iter()
+            .zip(state.meter_levels.iter())
+            .zip(state.peak_holds.iter())
+        {
+            let data = TransformUniform {
+                transform: image_rect_transform(
+                    meter.span.0,
+                    meter_level_y(*level),
+                    meter.span.1,
+                    ORIG_METER_BOTTOM as f64,
+                )
+                .into(),
+            };
+            self.queue.write_buffer(
+                &meter.bar_transform_buffer,
+                0 as wgpu::BufferAddress,
+                data.as_bytes(),
+            );
+            let peak_hold_y = meter_level_y(*peak_hold);
+            let data = TransformUniform {
+                transform: image_rect_transform(
+                    meter.span.0,
+                    peak_hold_y,
+                    meter.span.1,
+                    peak_hold_y + ORIG_PEAK_HOLD_THICKNESS,
+                )
+                .into(),
+            };
+            self.queue.write_buffer(
+                &meter.peak_hold_transform_buffer,
+                0 as wgpu::BufferAddress,
+                data.as_bytes(),
+            );
+        }
+
+        // Each arc is drawn separately, so that its color can depend on its knob.
+        let mut knob_arcs = Vec::with_capacity(KNOBS.len());
+        let mut all_arc_vertices: Vec<Vertex> = Vec::new();
+        for (knob, value) in KNOBS.iter().zip(state.displayed_knob_values.iter()) {
+            let start = all_arc_vertices.len() as u32;
+            all_arc_vertices.extend(arc_vertices(knob, *value));
+            knob_arcs.push((
+                start..all_arc_vertices.len() as u32,
+                knob.is_boosting(*value),
+            ));
+        }
+        if !all_arc_vertices.is_empty() {
+            self.queue.write_buffer(
+                &self.arc_vertex_buffer,
+                0 as wgpu::BufferAddress,
+                all_arc_vertices.as_bytes(),
+            );
+        }
+
+        let highlight_vertices: Vec<Vertex> = KNOBS
+            .iter()
+            .zip(state.automation_highlights.iter())
+            .flat_map(|(knob, strength)| highlight_vertices(knob, *strength))
+            .collect();
+        if !highlight_vertices.is_empty() {
+            self.queue.write_buffer(
+                &self.highlight_vertex_buffer,
+                0 as wgpu::BufferAddress,
+                highlight_vertices.as_bytes(),
+            );
+        }
+
+        self.queue.write_buffer(
+            &self.scope_vertex_buffer,
+            0 as wgpu::BufferAddress,
+            scope_vertices(&state.scope_samples).as_bytes(),
+        );
+
+        {
+            let mut rpass = Self::start_renderpass(
+                encoder,
+                view,
+                self.multisampled_framebuffer.as_ref(),
+                theme.clear_color,
+            );
+            // Only draw within the area of the window covered by the interface, so that
+            // the background image keeps its aspect ratio.
+            let (interface_width, interface_height) = self.layout.interface_size();
+            rpass.set_viewport(
+                self.layout.origin.0 as f32,
+                self.layout.origin.1 as f32,
+                interface_width as f32,
+                interface_height as f32,
+                0.,
+                1.,
+            );
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_index_buffer(
+                self.rectangle_index_buffer.slice(..),
+                wgpu::IndexFormat::Uint32,
+            );
+            rpass.set_vertex_buffer(0, self.rectangle_vertex_buffer.slice(..));
 
-                    // draw background
-                    rpass.set_bind_group(0, &self.background_bind_group, &[]);
+            // draw background
+            rpass.set_bind_group(0, &self.background_bind_group, &[]);
+            rpass.draw_indexed(0..6, 0, 0..1);
+
+            // draw highlights of knobs changed from outside of the editor, underneath
+            // their value arcs
+            if !highlight_vertices.is_empty() {
+                rpass.set_bind_group(0, &self.highlight_bind_group, &[]);
+                rpass.set_vertex_buffer(0, self.highlight_vertex_buffer.slice(..));
+                rpass.draw(0..highlight_vertices.len() as u32, 0..1);
+            }
+
+            // draw knob value arcs
+            rpass.set_vertex_buffer(0, self.arc_vertex_buffer.slice(..));
+            for (vertices, is_boosting) in knob_arcs {
+                rpass.set_bind_group(
+                    0,
+                    if is_boosting {
+                        &self.boost_arc_bind_group
+                    } else {
+                        &self.arc_bind_group
+                    },
+                    &[],
+                );
+                rpass.draw(vertices, 0..1);
+            }
+
+            // draw tick marks
+            rpass.set_bind_group(0, &self.tick_bind_group, &[]);
+            rpass.set_vertex_buffer(0, self.tick_vertex_buffer.slice(..));
+            rpass.draw(0..self.tick_vertex_count, 0..1);
+            rpass.set_vertex_buffer(0, self.rectangle_vertex_buffer.slice(..));
+
+            // draw knobs
+            for knob in self.knobs.iter() {
+                if let Some(body_bind_group) = &knob.body_bind_group {
+                    rpass.set_bind_group(0, body_bind_group, &[]);
                     rpass.draw_indexed(0..6, 0, 0..1);
+                }
+                rpass.set_bind_group(0, &knob.pointer_bind_group, &[]);
+                rpass.draw_indexed(0..6, 0, 0..1);
+            }
 
-                    // draw knob pointer
-                    rpass.set_bind_group(0, &self.pointer_bind_group, &[]);
+            // draw output level meters
+            for ((meter, peak_hold), clip_hold) in self
+                .meters
+                .iter()
+                .zip(state.peak_holds.iter())
+                .zip(state.clip_holds.iter())
+            {
+                rpass.set_bind_group(0, &meter.track_bind_group, &[]);
+                rpass.draw_indexed(0..6, 0, 0..1);
+                rpass.set_bind_group(0, &meter.bar_bind_group, &[]);
+                rpass.draw_indexed(0..6, 0, 0..1);
+                if meter_fraction(*peak_hold) > 0. {
+                    rpass.set_bind_group(0, &meter.peak_hold_bind_group, &[]);
                     rpass.draw_indexed(0..6, 0, 0..1);
                 }
+                if *clip_hold {
+                    rpass.set_bind_group(0, &meter.clip_hold_bind_group, &[]);
+                    rpass.draw_indexed(0..6, 0, 0..1);
+                }
+            }
 
-                let display_val = state.amplitude_value * 2.;
+            // draw theme toggle
+            rpass.set_bind_group(0, &self.theme_toggle_bind_groups[state.theme], &[]);
+            rpass.draw_indexed(0..6, 0, 0..1);
 
-                let int_text = display_val.trunc() as u8;
-                let frac_text = (display_val.fract() * 100.).trunc() as u8;
-                let text = if frac_text < 10 {
-                    format!("{}.0{}", int_text, frac_text)
-                } else {
-                    format!("{}.{}", int_text, frac_text)
-                };
+            // draw scope
+            rpass.set_bind_group(0, &self.scope_track_bind_group, &[]);
+            rpass.draw_indexed(0..6, 0, 0..1);
+            rpass.set_pipeline(&self.line_pipeline);
+            rpass.set_bind_group(0, &self.scope_bind_group, &[]);
+            rpass.set_vertex_buffer(0, self.scope_vertex_buffer.slice(..));
+            rpass.draw(0..SCOPE_LEN as u32, 0..1);
+        }
+
+        let text = match &state.text_entry {
+            Some(entered) => format!("{}|", entered),
+            None => format_amplitude(state.knob_values[AMPLITUDE_KNOB]),
+        };
+        // The readout is dimmed until the audio catches up with the displayed value.
+        let mut text_color = theme.font_color;
+        if state.smoothing_active && state.text_entry.is_none() {
+            text_color[3] *= SMOOTHING_TEXT_ALPHA;
+        }
+
+        let (window_width, window_height) = self.layout.window_size;
+        let (text_x, text_y) = self
+            .layout
+            .to_window_coords((ORIG_TEXT_RIGHT_ANCHOR, ORIG_TEXT_CENTER_Y_ANCHOR));
+        self.text_renderer.queue(wgpu_glyph::Section {
+            text: vec![wgpu_glyph::Text::default()
+                .with_text(&text)
+                .with_color(text_color)
+                .with_font_id(wgpu_glyph::FontId(0))
+                .with_scale((ORIG_TEXT_SCALE * self.layout.scale) as f32)],
+            layout: wgpu_glyph::Layout::default_single_line()
+                .h_align(wgpu_glyph::HorizontalAlign::Right)
+                .v_align(wgpu_glyph::VerticalAlign::Center),
+            screen_position: (text_x as f32, text_y as f32),
+            bounds: (window_width as f32, window_height as f32),
+        });
 
-                self.text_renderer.queue(wgpu_glyph::Section {
-                    text: vec![wgpu_glyph::Text::default()
-                        .with_text(&text)
-                        .with_color(FONT_COLOR)
-                        .with_font_id(wgpu_glyph::FontId(0))
-                        .with_scale(100. * SCALE as f32)],
-                    layout: wgpu_glyph::Layout::default_single_line()
-                        .h_align(wgpu_glyph::HorizontalAlign::Right)
-                        .v_align(wgpu_glyph::VerticalAlign::Center),
-                    screen_position: (TEXT_RIGHT_ANCHOR, TEXT_CENTER_Y_ANCHOR),
-                    bounds: (SIZE_X as f32, SIZE_Y as f32),
-                });
-                self.text_renderer
-                    .draw_queued(
-                        &self.device,
-                        &mut self.staging_belt,
-                        &mut encoder,
-                        &view,
-                        SIZE_X as u32,
-                        SIZE_Y as u32,
-                    )
-                    .unwrap();
+        let snapshot_buttons = std::iter::once((
+            ORIG_SNAPSHOT_TOGGLE_SPAN,
+            format!("A/B: {}", SNAPSHOT_NAMES[state.active_snapshot]),
+        ))
+        .chain(
+            ORIG_SNAPSHOT_STORE_SPANS
+                .iter()
+                .zip(SNAPSHOT_NAMES.iter())
+                .map(|(&span, name)| (span, format!("Store {}", name))),
+        )
+        .map(|(span, label)| {
+            let vertical_span = (ORIG_SNAPSHOT_BUTTON_TOP, ORIG_SNAPSHOT_BUTTON_BOTTOM);
+            (span, vertical_span, label, theme.font_color)
+        });
+        // The mute button stands out while the output is muted.
+        let mute_button = (
+            (ORIG_MUTE_BUTTON_LEFT, ORIG_MUTE_BUTTON_RIGHT),
+            (ORIG_MUTE_BUTTON_TOP, ORIG_MUTE_BUTTON_BOTTOM),
+            if state.muted { "Muted" } else { "Mute" }.to_string(),
+            if state.muted {
+                MUTED_TEXT_COLOR
+            } else {
+                theme.font_color
+            },
+        );
+        let trim_control = (
+            (ORIG_TRIM_CONTROL_LEFT, ORIG_TRIM_CONTROL_RIGHT),
+            (ORIG_TRIM_CONTROL_TOP, ORIG_TRIM_CONTROL_BOTTOM),
+            format!("Trim {:+.1}dB", state.output_trim),
+            theme.font_color,
+        );
+        let about_button = (
+            (ORIG_ABOUT_BUTTON_LEFT, ORIG_ABOUT_BUTTON_RIGHT),
+            (ORIG_ABOUT_BUTTON_TOP, ORIG_ABOUT_BUTTON_BOTTOM),
+            ABOUT_BUTTON_TEXT.to_string(),
+            theme.font_color,
+        );
+        let buttons = snapshot_buttons
+            .chain(std::iter::once(mute_button))
+            .chain(std::iter::once(trim_control))
+            .chain(std::iter::once(about_button));
+        for ((left, right), (top, bottom), label, color) in buttons {
+            let (label_x, label_y) = self
+                .layout
+                .to_window_coords(((left + right) as f64 / 2., (top + bottom) as f64 / 2.));
+            self.text_renderer.queue(wgpu_glyph::Section {
+                text: vec![wgpu_glyph::Text::default()
+                    .with_text(&label)
+                    .with_color(color)
+                    .with_font_id(wgpu_glyph::FontId(0))
+                    .with_scale((ORIG_BUTTON_TEXT_SCALE * self.layout.scale) as f32)],
+                layout: wgpu_glyph::Layout::default_single_line()
+                    .h_align(wgpu_glyph::HorizontalAlign::Center)
+                    .v_align(wgpu_glyph::VerticalAlign::Center),
+                screen_position: (label_x as f32, label_y as f32),
+                bounds: (window_width as f32, window_height as f32),
+            });
+        }
+        if state.input_clip_indicator {
+            let (clip_x, clip_y) = self.layout.to_window_coords((
+                (ORIG_METER_LEFT + ORIG_METER_RIGHT) as f64 / 2.,
+                ORIG_INPUT_CLIP_CENTER_Y,
+            ));
+            self.text_renderer.queue(wgpu_glyph::Section {
+                text: vec![wgpu_glyph::Text::default()
+                    .with_text(INPUT_CLIP_TEXT)
+                    .with_color(INPUT_CLIP_TEXT_COLOR)
+                    .with_font_id(wgpu_glyph::FontId(0))
+                    .with_scale((ORIG_BUTTON_TEXT_SCALE * self.layout.scale) as f32)],
+                layout: wgpu_glyph::Layout::default_single_line()
+                    .h_align(wgpu_glyph::HorizontalAlign::Center)
+                    .v_align(wgpu_glyph::VerticalAlign::Center),
+                screen_position: (clip_x as f32, clip_y as f32),
+                bounds: (window_width as f32, window_height as f32),
+            });
+        }
+        self.text_renderer
+            .draw_queued(
+                &self.device,
+                &mut self.staging_belt,
+                encoder,
+                view,
+                window_width,
+                window_height,
+            )
+            .unwrap();
+
+        if state.context_menu.is_some() {
+            self.draw_context_menu(state, encoder, view);
+        }
+        if state.about_open {
+            self.draw_about_overlay(state, encoder, view);
+        }
+    }
+
+    /// Draws the open context menu of `state` over everything else on the frame `view`, including
+    /// text.
+    fn draw_context_menu(
+        &mut self,
+        state: &super::state::InterfaceState,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        let menu = match state.context_menu {
+            Some(menu) => menu,
+            None => return,
+        };
+        let (left, top, right, bottom) = menu.bounds();
+        let data = TransformUniform {
+            transform: image_rect_transform(left, top, right, bottom).into(),
+        };
+        self.queue.write_buffer(
+            &self.menu_transform_buffer,
+            0 as wgpu::BufferAddress,
+            data.as_bytes(),
+        );
+        let hovered = state.hovered_menu_item();
+        if let Some(index) = hovered {
+            let (left, top, right, bottom) = menu.item_bounds(index);
+            let data = TransformUniform {
+                transform: image_rect_transform(left, top, right, bottom).into(),
+            };
+            self.queue.write_buffer(
+                &self.menu_hover_transform_buffer,
+                0 as wgpu::BufferAddress,
+                data.as_bytes(),
+            );
+        }
+
+        {
+            let mut rpass = self.start_overlay_renderpass(encoder, view);
+            rpass.set_bind_group(0, &self.menu_bind_group, &[]);
+            rpass.draw_indexed(0..6, 0, 0..1);
+            if hovered.is_some() {
+                rpass.set_bind_group(0, &self.menu_hover_bind_group, &[]);
+                rpass.draw_indexed(0..6, 0, 0..1);
             }
-            self.staging_belt.finish();
-            self.queue.submit(std::iter::once(encoder.finish()));
+        }
+
+        let font_color = THEMES[state.theme].font_color;
+        let (window_width, window_height) = self.layout.window_size;
+        for (index, item) in MENU_ITEMS.iter().enumerate() {
+            let (left, top, _, bottom) = menu.item_bounds(index);
+            let (label_x, label_y) = self
+                .layout
+                .to_window_coords((left + ORIG_MENU_TEXT_PADDING, (top + bottom) / 2.));
+            self.text_renderer.queue(wgpu_glyph::Section {
+                text: vec![wgpu_glyph::Text::default()
+                    .with_text(item.label())
+                    .with_color(font_color)
+                    .with_font_id(wgpu_glyph::FontId(0))
+                    .with_scale((ORIG_BUTTON_TEXT_SCALE * self.layout.scale) as f32)],
+                layout: wgpu_glyph::Layout::default_single_line()
+                    .h_align(wgpu_glyph::HorizontalAlign::Left)
+                    .v_align(wgpu_glyph::VerticalAlign::Center),
+                screen_position: (label_x as f32, label_y as f32),
+                bounds: (
+                    ((ORIG_MENU_WIDTH - ORIG_MENU_TEXT_PADDING) * self.layout.scale) as f32,
+                    window_height as f32,
+                ),
+            });
+        }
+        self.text_renderer
+            .draw_queued(
+                &self.device,
+                &mut self.staging_belt,
+                encoder,
+                view,
+                window_width,
+                window_height,
+            )
+            .unwrap();
+    }
+
+    /// Begins a render pass that draws solid rectangles over the finished frame `view`, within the
+    /// area covered by the interface.
+    fn start_overlay_renderpass<'a>(
+        &'a self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &'a wgpu::TextureView,
+    ) -> wgpu::RenderPass<'a> {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        let (interface_width, interface_height) = self.layout.interface_size();
+        rpass.set_viewport(
+            self.layout.origin.0 as f32,
+            self.layout.origin.1 as f32,
+            interface_width as f32,
+            interface_height as f32,
+            0.,
+            1.,
+        );
+        rpass.set_pipeline(&self.overlay_pipeline);
+        rpass.set_index_buffer(
+            self.rectangle_index_buffer.slice(..),
+            wgpu::IndexFormat::Uint32,
+        );
+        rpass.set_vertex_buffer(0, self.rectangle_vertex_buffer.slice(..));
+        rpass
+    }
 
-            use futures::task::SpawnExt;
-            self.local_pool
-                .spawner()
-                .spawn(self.staging_belt.recall())
-                .expect("Recall staging belt");
-            self.local_pool.run_until_stalled();
+    /// Draws the about overlay over everything else on the frame `view`, including text.
+    fn draw_about_overlay(
+        &mut self,
+        state: &super::state::InterfaceState,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        {
+            let mut rpass = self.start_overlay_renderpass(encoder, view);
+            rpass.set_bind_group(0, &self.about_bind_group, &[]);
+            rpass.draw_indexed(0..6, 0, 0..1);
+        }
 
-            frame.present();
+        let font_color = THEMES[state.theme].font_color;
+        let (window_width, window_height) = self.layout.window_size;
+        // The lines are centered on the panel as a group.
+        let center_x = (ORIG_ABOUT_PANEL_LEFT + ORIG_ABOUT_PANEL_RIGHT) / 2.;
+        let first_y = (ORIG_ABOUT_PANEL_TOP + ORIG_ABOUT_PANEL_BOTTOM) / 2.
+            - ORIG_ABOUT_LINE_SPACING * (self.about_lines.len() - 1) as f64 / 2.;
+        for (index, line) in self.about_lines.iter().enumerate() {
+            let (line_x, line_y) = self
+                .layout
+                .to_window_coords((center_x, first_y + ORIG_ABOUT_LINE_SPACING * index as f64));
+            self.text_renderer.queue(wgpu_glyph::Section {
+                text: vec![wgpu_glyph::Text::default()
+                    .with_text(line)
+                    .with_color(font_color)
+                    .with_font_id(wgpu_glyph::FontId(0))
+                    .with_scale((ORIG_BUTTON_TEXT_SCALE * self.layout.scale) as f32)],
+                layout: wgpu_glyph::Layout::default_single_line()
+                    .h_align(wgpu_glyph::HorizontalAlign::Center)
+                    .v_align(wgpu_glyph::VerticalAlign::Center),
+                screen_position: (line_x as f32, line_y as f32),
+                bounds: (window_width as f32, window_height as f32),
+            });
         }
+        self.text_renderer
+            .draw_queued(
+                &self.device,
+                &mut self.staging_belt,
+                encoder,
+                view,
+                window_width,
+                window_height,
+            )
+            .unwrap();
     }
 
     /// Begin a renderpass for the background and knob pointer. Text will be drawn in a separate
     /// pass by `wgpu_glyph`.
+    ///
+    /// If there is a `multisampled_framebuffer`, it is drawn to and then resolved onto `view`.
     fn start_renderpass<'a>(
         encoder: &'a mut wgpu::CommandEncoder,
         view: &'a wgpu::TextureView,
-        multisampled_framebuffer: &'a wgpu::TextureView,
+        multisampled_framebuffer: Option<&'a wgpu::TextureView>,
+        clear_color: wgpu::Color,
     ) -> wgpu::RenderPass<'a> {
         let rpass_color_attachment = wgpu::RenderPassColorAttachment {
-            view: multisampled_framebuffer,
-            resolve_target: Some(view),
+            view: multisampled_framebuffer.unwrap_or(view),
+            resolve_target: multisampled_framebuffer.map(|_| view),
             ops: wgpu::Operations {
-                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                load: wgpu::LoadOp::Clear(clear_color),
                 store: true,
             },
         };
@@ -432,13 +1584,70 @@ impl Renderer {
 
 /// Different bind groups are used to render sets of geometry in different ways. In this case, the
 /// two geometries on the interface (background and knob pointer) are rendered with different
-/// textures and 2D positions.
+/// textures and 2D positions. The image is tinted by the color held in `tint_buffer`, which may be
+/// shared with other bind groups.
 fn make_bind_group(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     bind_group_layout: &wgpu::BindGroupLayout,
     sampler: &wgpu::Sampler,
-    png_image: &[u8],
+    image: &Image,
+    tint_buffer: &wgpu::Buffer,
+    initial_transform: Matrix4<f32>,
+) -> (wgpu::BindGroup, wgpu::Buffer) {
+    make_texture_bind_group(
+        device,
+        queue,
+        bind_group_layout,
+        sampler,
+        &image.data,
+        image.size,
+        tint_buffer,
+        initial_transform,
+    )
+}
+
+/// Creates a bind group used to render geometry in a single solid `color`.
+fn make_solid_bind_group(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    color: [u8; 4],
+    initial_transform: Matrix4<f32>,
+) -> (wgpu::BindGroup, wgpu::Buffer) {
+    make_texture_bind_group(
+        device,
+        queue,
+        bind_group_layout,
+        sampler,
+        &color,
+        (1, 1),
+        &make_tint_buffer(device, WHITE_TINT),
+        initial_transform,
+    )
+}
+
+/// Creates a uniform buffer holding a tint `color`, which can be updated later.
+fn make_tint_buffer(device: &wgpu::Device, color: [f32; 4]) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: None,
+        contents: TintUniform { color }.as_bytes(),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    })
+}
+
+/// Creates a bind group from raw RGBA `image_data` with the given `(width, height)` dimensions,
+/// tinted by the color held in `tint_buffer`.
+#[allow(clippy::too_many_arguments)]
+fn make_texture_bind_group(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    image_data: &[u8],
+    (width, height): (u32, u32),
+    tint_buffer: &wgpu::Buffer,
     initial_transform: Matrix4<f32>,
 ) -> (wgpu::BindGroup, wgpu::Buffer) {
     let uniform_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -450,14 +1659,9 @@ fn make_bind_group(
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     });
 
-    let decoder = png::Decoder::new(png_image);
-    let (info, mut reader) = decoder.read_info().unwrap();
-    let mut image_data = vec![0; info.buffer_size()];
-    reader.next_frame(&mut image_data).unwrap();
-
     let texture_extent = wgpu::Extent3d {
-        width: info.width,
-        height: info.height,
+        width,
+        height,
         depth_or_array_layers: 1,
     };
     let texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -473,17 +1677,17 @@ fn make_bind_group(
     let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
     queue.write_texture(
         texture.as_image_copy(),
-        &image_data,
+        image_data,
         wgpu::ImageDataLayout {
             offset: 0,
-            bytes_per_row: Some(std::num::NonZeroU32::new(4 * info.width).unwrap()),
+            bytes_per_row: Some(std::num::NonZeroU32::new(4 * width).unwrap()),
             rows_per_image: None,
         },
         texture_extent,
     );
 
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        layout: &bind_group_layout,
+        layout: bind_group_layout,
         entries: &[
             wgpu::BindGroupEntry {
                 binding: 0,
@@ -495,7 +1699,11 @@ fn make_bind_group(
             },
             wgpu::BindGroupEntry {
                 binding: 2,
-                resource: wgpu::BindingResource::Sampler(&sampler),
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: tint_buffer.as_entire_binding(),
             },
         ],
         label: None,
@@ -504,13 +1712,71 @@ fn make_bind_group(
     (bind_group, uniform_buf)
 }
 
+/// Creates a render pipeline that draws geometry with `shader_module` onto a surface of the given
+/// `format` with `sample_count` samples per pixel, assembling primitives as described by
+/// `primitive`.
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader_module: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    primitive: wgpu::PrimitiveState,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader_module,
+            entry_point: "vs_main",
+            buffers: &[wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x2],
+            }],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader_module,
+            entry_point: "fs_main",
+            targets: &[wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            }],
+        }),
+        primitive,
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
 /// Creates a new buffer that is sampled `sample_count` times more densely than the target output
-/// surface, producing a more smooth anti-aliased appearance.
+/// surface, producing a more smooth anti-aliased appearance. No buffer is needed if only one
+/// sample is taken per pixel.
 fn create_multisampled_framebuffer(
     device: &wgpu::Device,
     sc_desc: &wgpu::SurfaceConfiguration,
     sample_count: u32,
-) -> wgpu::TextureView {
+) -> Option<wgpu::TextureView> {
+    if sample_count <= 1 {
+        return None;
+    }
     let multisampled_texture_extent = wgpu::Extent3d {
         width: sc_desc.width,
         height: sc_desc.height,
@@ -526,7 +1792,306 @@ fn create_multisampled_framebuffer(
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
     };
 
-    device
-        .create_texture(multisampled_frame_descriptor)
-        .create_view(&wgpu::TextureViewDescriptor::default())
+    Some(
+        device
+            .create_texture(multisampled_frame_descriptor)
+            .create_view(&wgpu::TextureViewDescriptor::default()),
+    )
+}
+
+/// Returns the number of samples to take per pixel on an adapter of the given `device_type`, as
+/// near as possible to `requested`. Software adapters, like llvmpipe on headless or virtualized
+/// systems, only take one, since multisampling is prohibitively slow without a GPU.
+fn adapter_sample_count(requested: u32, device_type: wgpu::DeviceType) -> u32 {
+    if device_type == wgpu::DeviceType::Cpu {
+        1
+    } else {
+        supported_sample_count(requested)
+    }
+}
+
+/// Returns the supported sample count nearest to `requested`, preferring the lower count when two
+/// are equally near.
+fn supported_sample_count(requested: u32) -> u32 {
+    SUPPORTED_SAMPLE_COUNTS
+        .iter()
+        .copied()
+        .min_by_key(|&count| (count as i64 - requested as i64).abs())
+        .unwrap_or(1)
+}
+
+/// Returns a transform that maps the rectangle ([-1,1],[-1,1]) onto the given bounds, specified in
+/// pixels of the original background image.
+fn image_rect_transform(left: f64, top: f64, right: f64, bottom: f64) -> Matrix4<f32> {
+    let center_x = (left + right) / 2.;
+    let center_y = (top + bottom) / 2.;
+    Matrix4::from_translation(Vector3::new(
+        (2. * center_x / ORIG_BG_SIZE_X as f64 - 1.) as f32,
+        (1. - 2. * center_y / ORIG_BG_SIZE_Y as f64) as f32,
+        0.,
+    )) * Matrix4::from_nonuniform_scale(
+        ((right - left) / ORIG_BG_SIZE_X as f64) as f32,
+        ((bottom - top) / ORIG_BG_SIZE_Y as f64) as f32,
+        1.,
+    )
+}
+
+/// Rotation of the knob pointer from its position in the source image, in degrees, for a knob
+/// value from 0 to 1.
+fn knob_angle(value: f32) -> f32 {
+    KNOB_START_ANGLE + (KNOB_END_ANGLE - KNOB_START_ANGLE) * value
+}
+
+/// Returns a list of triangles forming an arc around `knob`, sweeping from its minimum position up
+/// to the position of `value`.
+fn arc_vertices(knob: &Knob, value: f32) -> Vec<Vertex> {
+    let segments = (value.clamp(0., 1.) * ARC_SEGMENTS as f32).ceil() as usize;
+    let mut vertices = Vec::with_capacity(segments * 6);
+    for segment in 0..segments {
+        let start = knob_angle(value * segment as f32 / segments as f32);
+        let end = knob_angle(value * (segment + 1) as f32 / segments as f32);
+        vertices.extend_from_slice(&ring_section(
+            knob,
+            start,
+            end,
+            ARC_INNER_RADIUS,
+            ARC_OUTER_RADIUS,
+        ));
+    }
+    vertices
+}
+
+/// Returns a list of triangles forming a full ring around `knob`, centered on its value arc, whose
+/// width is proportional to `strength` from 0 to 1. No triangles are returned at a strength of 0.
+fn highlight_vertices(knob: &Knob, strength: f32) -> Vec<Vertex> {
+    if strength <= 0. {
+        return Vec::new();
+    }
+    let center = (ARC_INNER_RADIUS + ARC_OUTER_RADIUS) / 2.;
+    let half_width = HIGHLIGHT_HALF_WIDTH * strength.min(1.) as f64;
+    (0..HIGHLIGHT_SEGMENTS)
+        .flat_map(|segment| {
+            let start = 360. * segment as f32 / HIGHLIGHT_SEGMENTS as f32;
+            let end = 360. * (segment + 1) as f32 / HIGHLIGHT_SEGMENTS as f32;
+            // Angles run counterclockwise, so the section runs from `end` to `start`.
+            ring_section(knob, end, start, center - half_width, center + half_width)
+        })
+        .collect()
+}
+
+/// Returns a list of triangles forming `NUM_TICKS` evenly spaced tick marks around `knob`, from
+/// its minimum to its maximum position.
+fn tick_vertices(knob: &Knob) -> Vec<Vertex> {
+    (0..NUM_TICKS)
+        .flat_map(|tick| {
+            let angle = knob_angle(tick as f32 / (NUM_TICKS - 1) as f32);
+            ring_section(
+                knob,
+                angle + TICK_WIDTH_DEGREES / 2.,
+                angle - TICK_WIDTH_DEGREES / 2.,
+                TICK_INNER_RADIUS,
+                TICK_OUTER_RADIUS,
+            )
+        })
+        .collect()
+}
+
+/// Returns two triangles covering the section of a ring around `knob` between the angles `start`
+/// and `end`, in degrees counterclockwise from the top of the knob, where `end` is clockwise from
+/// `start`. The inner and outer radius of the ring are given as proportions of the knob's radius.
+fn ring_section(
+    knob: &Knob,
+    start: f32,
+    end: f32,
+    inner_radius: f64,
+    outer_radius: f64,
+) -> [Vertex; 6] {
+    let radius = knob.radius as f64;
+    // Converts an angle and a distance from the center of the knob into normalized device
+    // coordinates.
+    let point = |angle: f32, distance: f64| {
+        let (sin, cos) = (angle as f64).to_radians().sin_cos();
+        let x = knob.center.0 as f64 - distance * sin;
+        let y = knob.center.1 as f64 - distance * cos;
+        Vertex::new(
+            (2. * x / ORIG_BG_SIZE_X as f64 - 1.) as f32,
+            (1. - 2. * y / ORIG_BG_SIZE_Y as f64) as f32,
+            0.,
+            0.,
+        )
+    };
+    let inner_start = point(start, radius * inner_radius);
+    let outer_start = point(start, radius * outer_radius);
+    let inner_end = point(end, radius * inner_radius);
+    let outer_end = point(end, radius * outer_radius);
+    // The section runs clockwise, so these triangles are wound counterclockwise to avoid being
+    // culled.
+    [
+        inner_start,
+        outer_end,
+        outer_start,
+        inner_start,
+        inner_end,
+        outer_end,
+    ]
+}
+
+/// Returns the points of a line drawing `samples` across the scope, in normalized device
+/// coordinates. Samples beyond full scale are drawn at the edge of the scope.
+fn scope_vertices(samples: &[f32; SCOPE_LEN]) -> [Vertex; SCOPE_LEN] {
+    let mut vertices = [Vertex::new(0., 0., 0., 0.); SCOPE_LEN];
+    let center_y = (ORIG_SCOPE_TOP + ORIG_SCOPE_BOTTOM) / 2.;
+    let half_height = (ORIG_SCOPE_BOTTOM - ORIG_SCOPE_TOP) / 2.;
+    for (i, (vertex, sample)) in vertices.iter_mut().zip(samples.iter()).enumerate() {
+        let x = ORIG_SCOPE_LEFT
+            + (ORIG_SCOPE_RIGHT - ORIG_SCOPE_LEFT) * i as f64 / (SCOPE_LEN - 1) as f64;
+        let y = center_y - half_height * sample.clamp(-1., 1.) as f64;
+        *vertex = Vertex::new(
+            (2. * x / ORIG_BG_SIZE_X as f64 - 1.) as f32,
+            (1. - 2. * y / ORIG_BG_SIZE_Y as f64) as f32,
+            0.,
+            0.,
+        );
+    }
+    vertices
+}
+
+/// Returns the left and right edges of each of the `NUM_METERS` meters, in pixels of the original
+/// background image.
+fn meter_spans() -> impl Iterator<Item = (f64, f64)> {
+    let width = ((ORIG_METER_RIGHT - ORIG_METER_LEFT) as f64
+        - ORIG_METER_GAP * (NUM_METERS - 1) as f64)
+        / NUM_METERS as f64;
+    (0..NUM_METERS).map(move |meter| {
+        let left = ORIG_METER_LEFT as f64 + (width + ORIG_METER_GAP) * meter as f64;
+        (left, left + width)
+    })
+}
+
+/// Returns the vertical position on the meters of a linear output level, in pixels of the
+/// original background image.
+fn meter_level_y(level: f32) -> f64 {
+    let (top, bottom) = (ORIG_METER_TOP as f64, ORIG_METER_BOTTOM as f64);
+    bottom - (bottom - top) * meter_fraction(level)
+}
+
+/// Maps a linear output level onto the proportion of the meter that should be filled.
+fn meter_fraction(level: f32) -> f64 {
+    let db = 20. * level.log10();
+    (((db - METER_MIN_DB) / (METER_MAX_DB - METER_MIN_DB)).clamp(0., 1.)) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_count_falls_back_to_nearest_supported() {
+        assert_eq!(supported_sample_count(4), 4);
+        assert_eq!(supported_sample_count(1), 1);
+        assert_eq!(supported_sample_count(0), 1);
+        assert_eq!(supported_sample_count(2), 1);
+        assert_eq!(supported_sample_count(3), 4);
+        assert_eq!(supported_sample_count(16), 4);
+    }
+
+    #[test]
+    fn software_adapters_disable_anti_aliasing() {
+        assert_eq!(adapter_sample_count(4, wgpu::DeviceType::Cpu), 1);
+        assert_eq!(adapter_sample_count(4, wgpu::DeviceType::IntegratedGpu), 4);
+        assert_eq!(adapter_sample_count(1, wgpu::DeviceType::DiscreteGpu), 1);
+    }
+
+    #[test]
+    fn backend_defaults_to_primary() {
+        assert_eq!(backends(None), wgpu::Backends::PRIMARY);
+        assert_eq!(backends(Some("nonsense")), wgpu::Backends::PRIMARY);
+        assert_eq!(backends(Some("dx12")), wgpu::Backends::DX12);
+        assert_eq!(backends(Some(" Vulkan ")), wgpu::Backends::VULKAN);
+        assert_eq!(backends(Some("gl")), wgpu::Backends::GL);
+    }
+
+    /// Frame drawn from the default interface state, against which new frames are compared.
+    const REFERENCE_FRAME_PATH: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/reference_frame.png");
+    /// Replaces the reference frame with a newly drawn one when set, after an intended change to
+    /// the interface's appearance.
+    const UPDATE_REFERENCE_VAR: &str = "AMPLIFE_UPDATE_REFERENCE_FRAME";
+    /// Largest difference in any channel for which a pixel is considered unchanged, allowing for
+    /// small differences in rasterization between GPUs.
+    const PIXEL_TOLERANCE: u8 = 16;
+    /// Largest proportion of pixels that may be changed before the frame is considered different.
+    const CHANGED_PIXEL_TOLERANCE: f64 = 0.005;
+
+    /// Writes `image` to `path` as a PNG image.
+    fn write_png(path: &str, image: &Image) {
+        let path = std::path::Path::new(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+        let mut encoder = png::Encoder::new(file, image.size.0, image.size.1);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&image.data)
+            .unwrap();
+    }
+
+    /// Compares a frame drawn off-screen with the committed reference. Ignored until a reference
+    /// has been drawn on a machine with a GPU and committed, since the comparison fails without
+    /// one. Run with `cargo test -- --ignored rendered_frame_matches_reference`.
+    #[test]
+    #[ignore]
+    fn rendered_frame_matches_reference() {
+        // Anti-aliasing varies the most between GPUs, so it's disabled.
+        let layout = Layout::new((super::super::SIZE_X as u32, super::super::SIZE_Y as u32));
+        let mut renderer = match Renderer::offscreen(layout, ThemeAssets::default(), 1) {
+            Ok(renderer) => renderer,
+            Err(RendererError::NoAdapter) => {
+                eprintln!("skipping frame comparison, since no GPU is available");
+                return;
+            }
+            Err(e) => panic!("{}", e),
+        };
+        let state = super::super::state::InterfaceState::new(|_| 0.5, 0, 0, false, 0.);
+        let frame = renderer.render_to_image(&state);
+
+        if std::env::var_os(UPDATE_REFERENCE_VAR).is_some() {
+            write_png(REFERENCE_FRAME_PATH, &frame);
+            eprintln!("wrote a new reference frame to {}", REFERENCE_FRAME_PATH);
+            return;
+        }
+        // A missing reference is a failure rather than a fresh start, so that a frame is never
+        // accepted without being asked for.
+        let reference = std::fs::read(REFERENCE_FRAME_PATH)
+            .ok()
+            .and_then(|data| super::super::theme::decode_png(&data))
+            .unwrap_or_else(|| {
+                panic!(
+                    "no reference frame could be read from {}; set {} to draw one",
+                    REFERENCE_FRAME_PATH, UPDATE_REFERENCE_VAR
+                )
+            });
+        assert_eq!(frame.size, reference.size);
+        let changed_pixels = frame
+            .data
+            .chunks_exact(4)
+            .zip(reference.data.chunks_exact(4))
+            .filter(|(pixel, reference)| {
+                pixel
+                    .iter()
+                    .zip(reference.iter())
+                    .any(|(a, b)| a.abs_diff(*b) > PIXEL_TOLERANCE)
+            })
+            .count();
+        let num_pixels = (frame.size.0 * frame.size.1) as f64;
+        assert!(
+            changed_pixels as f64 <= num_pixels * CHANGED_PIXEL_TOLERANCE,
+            "{} pixels differ from {}; set {} to accept the new frame",
+            changed_pixels,
+            REFERENCE_FRAME_PATH,
+            UPDATE_REFERENCE_VAR
+        );
+    }
 }
@@ -0,0 +1,138 @@
+//! Right-clicking a knob opens a small context menu of actions on its value, or on every
+//! parameter. This module describes the menu's entries and where it is placed on the interface;
+//! `InterfaceState` decides when it is open and carries out the chosen action.
+
+use super::image_consts::{ORIG_BG_SIZE_X, ORIG_BG_SIZE_Y};
+
+/// Original width of the menu, in pixels.
+pub(super) const ORIG_MENU_WIDTH: f64 = 300.;
+/// Original height of each entry in the menu, in pixels.
+pub(super) const ORIG_MENU_ITEM_HEIGHT: f64 = 48.;
+
+/// Actions that can be chosen from the context menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(in crate::editor) enum MenuItem {
+    /// Resets the knob to its default value.
+    ResetToDefault,
+    /// Copies the knob's displayed value.
+    CopyValue,
+    /// Sets the knob to a previously copied value, if it's valid for the knob.
+    PasteValue,
+    /// Starts typing a new value for the knob into the readout.
+    EnterValue,
+    /// Resets every parameter, not only the knob, to its default value.
+    ResetAll,
+    /// Switches vertical knob drags between linear and accelerated.
+    ToggleDragAcceleration,
+}
+
+/// Every entry of the context menu, from top to bottom.
+pub(super) const MENU_ITEMS: [MenuItem; 6] = [
+    MenuItem::ResetToDefault,
+    MenuItem::CopyValue,
+    MenuItem::PasteValue,
+    MenuItem::EnterValue,
+    MenuItem::ResetAll,
+    MenuItem::ToggleDragAcceleration,
+];
+
+impl MenuItem {
+    /// Text shown for the entry.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ResetToDefault => "Reset to default",
+            Self::CopyValue => "Copy value",
+            Self::PasteValue => "Paste value",
+            Self::EnterValue => "Enter value...",
+            Self::ResetAll => "Reset all parameters",
+            Self::ToggleDragAcceleration => "Accelerate drags",
+        }
+    }
+}
+
+/// An open context menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(in crate::editor) struct ContextMenu {
+    /// Index into `KNOBS` of the knob that the menu acts on.
+    pub knob: usize,
+    /// Original coordinates of the menu's top-left corner, in pixels.
+    origin: (f64, f64),
+}
+
+impl ContextMenu {
+    /// Opens a menu for the knob at index `knob`, with its top-left corner at the original pixel
+    /// coordinates `position` where possible. The menu is moved as needed to fit entirely within
+    /// the interface.
+    pub fn new(knob: usize, position: (f64, f64)) -> Self {
+        let height = ORIG_MENU_ITEM_HEIGHT * MENU_ITEMS.len() as f64;
+        let origin = (
+            position
+                .0
+                .min(ORIG_BG_SIZE_X as f64 - ORIG_MENU_WIDTH)
+                .max(0.),
+            position.1.min(ORIG_BG_SIZE_Y as f64 - height).max(0.),
+        );
+        Self { knob, origin }
+    }
+
+    /// Original bounds of the entry at `index` into `MENU_ITEMS`, in pixels, as
+    /// `(left, top, right, bottom)`.
+    pub fn item_bounds(&self, index: usize) -> (f64, f64, f64, f64) {
+        let top = self.origin.1 + ORIG_MENU_ITEM_HEIGHT * index as f64;
+        (
+            self.origin.0,
+            top,
+            self.origin.0 + ORIG_MENU_WIDTH,
+            top + ORIG_MENU_ITEM_HEIGHT,
+        )
+    }
+
+    /// Original bounds of the whole menu, in pixels, as `(left, top, right, bottom)`.
+    pub fn bounds(&self) -> (f64, f64, f64, f64) {
+        let (left, top, right, _) = self.item_bounds(0);
+        let (_, _, _, bottom) = self.item_bounds(MENU_ITEMS.len() - 1);
+        (left, top, right, bottom)
+    }
+
+    /// Returns the index into `MENU_ITEMS` of the entry at the given original pixel coordinates,
+    /// or `None` if they're outside of the menu.
+    pub fn item_index_at(&self, (x, y): (f64, f64)) -> Option<usize> {
+        (0..MENU_ITEMS.len()).find(|&index| {
+            let (left, top, right, bottom) = self.item_bounds(index);
+            (left..right).contains(&x) && (top..bottom).contains(&y)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menu_fits_within_interface() {
+        let menu = ContextMenu::new(0, (100., 200.));
+        assert_eq!(menu.bounds().0, 100.);
+        assert_eq!(menu.bounds().1, 200.);
+
+        let (left, top, right, bottom) =
+            ContextMenu::new(0, (ORIG_BG_SIZE_X as f64, ORIG_BG_SIZE_Y as f64 - 10.)).bounds();
+        assert_eq!(right, ORIG_BG_SIZE_X as f64);
+        assert_eq!(bottom, ORIG_BG_SIZE_Y as f64);
+        assert!(left >= 0. && top >= 0.);
+    }
+
+    #[test]
+    fn entries_are_hit_tested_in_order() {
+        let menu = ContextMenu::new(0, (100., 200.));
+        assert_eq!(menu.item_index_at((150., 210.)), Some(0));
+        assert_eq!(
+            menu.item_index_at((150., 200. + ORIG_MENU_ITEM_HEIGHT * 2.5)),
+            Some(2)
+        );
+        assert_eq!(menu.item_index_at((99., 210.)), None);
+        assert_eq!(
+            menu.item_index_at((150., 200. + ORIG_MENU_ITEM_HEIGHT * MENU_ITEMS.len() as f64)),
+            None
+        );
+    }
+}
@@ -4,15 +4,21 @@
 //! input events, both of which are managed within the `EditorInterface` type.
 
 use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
 
-use vst_window::{EditorWindow, EventSource};
+use vst::editor::KeyCode;
+use vst_window::{EditorWindow, EventSource, WindowEvent};
 
-use crate::plugin_state::StateUpdate;
+use crate::plugin_state::{DspReportReader, StateUpdate};
 
+mod clipboard;
 mod graphics;
+mod menu;
 mod state;
+mod theme;
 
-use super::EditorRemoteState;
+use super::{physical_size, EditorRemoteState};
+pub(super) use graphics::RendererError;
 pub(super) use state::InterfaceState;
 
 /// Dimensions and layout of image assets.
@@ -28,57 +34,520 @@ mod image_consts {
     pub const ORIG_KNOB_X: usize = 800;
     /// Original center y-coordinate of the knob image, in pixels.
     pub const ORIG_KNOB_Y: usize = 500;
+
+    /// Original radius of the make-up gain knob, in pixels.
+    pub const ORIG_GAIN_KNOB_RADIUS: usize = 80;
+    /// Original center x-coordinate of the make-up gain knob, in pixels.
+    pub const ORIG_GAIN_KNOB_X: usize = 337;
+    /// Original center y-coordinate of the make-up gain knob, in pixels.
+    pub const ORIG_GAIN_KNOB_Y: usize = 700;
+
+    /// Total sweep of the knob pointer between its minimum and maximum values, in degrees.
+    pub const KNOB_ANGLE_RANGE: f32 = 300.;
+    /// Angle of the pointer at the knob's minimum value, in degrees counterclockwise from the top
+    /// position it has in the source image.
+    pub const KNOB_START_ANGLE: f32 = KNOB_ANGLE_RANGE / 2.;
+    /// Angle of the pointer at the knob's maximum value, in degrees counterclockwise from the top
+    /// position it has in the source image.
+    pub const KNOB_END_ANGLE: f32 = KNOB_START_ANGLE - KNOB_ANGLE_RANGE;
+
+    /// Original left edge of the readout text box, in pixels.
+    pub const ORIG_READOUT_LEFT: usize = 175;
+    /// Original top edge of the readout text box, in pixels.
+    pub const ORIG_READOUT_TOP: usize = 400;
+    /// Original right edge of the readout text box, in pixels.
+    pub const ORIG_READOUT_RIGHT: usize = 500;
+    /// Original bottom edge of the readout text box, in pixels.
+    pub const ORIG_READOUT_BOTTOM: usize = 600;
+
+    /// Original bounds of the theme toggle, in pixels.
+    pub const ORIG_THEME_TOGGLE_LEFT: usize = 1030;
+    pub const ORIG_THEME_TOGGLE_TOP: usize = 730;
+    pub const ORIG_THEME_TOGGLE_RIGHT: usize = 1080;
+    pub const ORIG_THEME_TOGGLE_BOTTOM: usize = 760;
+
+    /// Original top and bottom edges of the snapshot buttons, in pixels.
+    pub const ORIG_SNAPSHOT_BUTTON_TOP: usize = 725;
+    pub const ORIG_SNAPSHOT_BUTTON_BOTTOM: usize = 765;
+    /// Original left and right edges of the button that toggles between the A/B snapshots, in
+    /// pixels.
+    pub const ORIG_SNAPSHOT_TOGGLE_SPAN: (usize, usize) = (540, 680);
+    /// Original left and right edges of the buttons that store each snapshot, in pixels.
+    pub const ORIG_SNAPSHOT_STORE_SPANS: [(usize, usize); 2] = [(700, 840), (860, 1000)];
+
+    /// Original bounds of the mute button, in pixels.
+    pub const ORIG_MUTE_BUTTON_LEFT: usize = 430;
+    pub const ORIG_MUTE_BUTTON_TOP: usize = 725;
+    pub const ORIG_MUTE_BUTTON_RIGHT: usize = 520;
+    pub const ORIG_MUTE_BUTTON_BOTTOM: usize = 765;
+
+    /// Original bounds of the output level meters, in pixels. Both meters share this space in
+    /// stereo builds.
+    pub const ORIG_METER_LEFT: usize = 1040;
+    pub const ORIG_METER_TOP: usize = 300;
+    pub const ORIG_METER_RIGHT: usize = 1070;
+    pub const ORIG_METER_BOTTOM: usize = 700;
+    /// Original top and bottom edges of the clip indicators above each meter, in pixels.
+    pub const ORIG_CLIP_INDICATOR_TOP: usize = 284;
+    pub const ORIG_CLIP_INDICATOR_BOTTOM: usize = 296;
+
+    /// Original bounds of the output trim control, in pixels.
+    pub const ORIG_TRIM_CONTROL_LEFT: usize = 40;
+    pub const ORIG_TRIM_CONTROL_TOP: usize = 725;
+    pub const ORIG_TRIM_CONTROL_RIGHT: usize = 240;
+    pub const ORIG_TRIM_CONTROL_BOTTOM: usize = 765;
+
+    /// Original bounds of the button that opens the about overlay, in pixels.
+    pub const ORIG_ABOUT_BUTTON_LEFT: usize = 1100;
+    pub const ORIG_ABOUT_BUTTON_TOP: usize = 725;
+    pub const ORIG_ABOUT_BUTTON_RIGHT: usize = 1180;
+    pub const ORIG_ABOUT_BUTTON_BOTTOM: usize = 765;
+}
+
+/// Describes an interactive knob on the interface, and the parameter it controls.
+#[derive(Clone, Copy)]
+struct Knob {
+    /// Index of the controlled parameter.
+    parameter: usize,
+    /// Value the knob is reset to by a double-click or from its context menu.
+    default: f32,
+    /// Converts a value from 0 to 1 into text, as the host displays the parameter.
+    format: fn(f32) -> String,
+    /// Converts user-entered text into a value from 0 to 1, if valid.
+    parse: fn(&str) -> Option<f32>,
+    /// Original center coordinates of the knob, in pixels.
+    center: (usize, usize),
+    /// Original radius of the knob, in pixels.
+    radius: usize,
+    /// Whether the knob's body must be drawn underneath its pointer, rather than being part of the
+    /// background image.
+    draws_body: bool,
+    /// Value at which the knob leaves the signal level unchanged, if it has one. The knob is drawn
+    /// in a warning color above this value, since the signal is then being boosted.
+    unity: Option<f32>,
+    /// Spacing of the values that the knob snaps to while dragged with snapping enabled, as a
+    /// fraction of its range.
+    snap_step: f32,
 }
 
-/// Display scale of the entire UI.
-const SCALE: f64 = 0.5;
+impl Knob {
+    /// Whether the given coordinate of the original image assets is within this knob.
+    fn contains(&self, (x, y): (f64, f64)) -> bool {
+        (x - self.center.0 as f64).powi(2) + (y - self.center.1 as f64).powi(2)
+            < (self.radius as f64).powi(2)
+    }
+
+    /// Whether the knob boosts the signal level at the given `value`.
+    fn is_boosting(&self, value: f32) -> bool {
+        self.unity.is_some_and(|unity| value > unity)
+    }
+}
+
+/// Every knob on the interface, in drawing order. Adding a knob only requires a new entry here,
+/// along with handling of its parameter's `StateUpdate` variant in `InterfaceState`.
+const KNOBS: [Knob; 2] = [
+    Knob {
+        parameter: crate::parameters::AMPLITUDE,
+        default: crate::parameters::AMPLITUDE_DEFAULT,
+        format: crate::parameters::format_amplitude,
+        parse: crate::parameters::parse_amplitude,
+        center: (image_consts::ORIG_KNOB_X, image_consts::ORIG_KNOB_Y),
+        radius: image_consts::ORIG_KNOB_RADIUS,
+        draws_body: false,
+        unity: Some(crate::parameters::AMPLITUDE_DEFAULT),
+        // Multiples of 0.1x gain.
+        snap_step: 0.05,
+    },
+    Knob {
+        parameter: crate::parameters::MAKEUP_GAIN,
+        default: crate::parameters::MAKEUP_GAIN_DEFAULT,
+        format: crate::parameters::format_makeup_gain,
+        parse: crate::parameters::parse_makeup_gain,
+        center: (
+            image_consts::ORIG_GAIN_KNOB_X,
+            image_consts::ORIG_GAIN_KNOB_Y,
+        ),
+        radius: image_consts::ORIG_GAIN_KNOB_RADIUS,
+        draws_body: true,
+        // Make-up gain is always a boost, so there's no need to warn about it.
+        unity: None,
+        // Whole decibels.
+        snap_step: 1. / crate::dsp::MAX_MAKEUP_GAIN_DB,
+    },
+];
+const NUM_KNOBS: usize = KNOBS.len();
+/// Index into `KNOBS` of the amplitude knob, whose value is shown in the readout.
+const AMPLITUDE_KNOB: usize = 0;
+
+/// Default display scale of the entire UI.
+const DEFAULT_SCALE: f64 = 0.5;
+/// Display scales that the editor window can be zoomed between, in increasing order.
+const ZOOM_LEVELS: [f64; 4] = [0.5, 0.75, 1., 1.5];
+/// Difference in scale within which window sizes are treated as the same zoom level, allowing for
+/// rounding of the window size to whole pixels.
+const ZOOM_TOLERANCE: f64 = 0.01;
 
-/// Actual pixel width of the editor window.
-pub(super) const SIZE_X: usize = (image_consts::ORIG_BG_SIZE_X as f64 * SCALE) as usize;
-/// Actual pixel height of the editor window.
-pub(super) const SIZE_Y: usize = (image_consts::ORIG_BG_SIZE_Y as f64 * SCALE) as usize;
+/// Default pixel width of the editor window.
+pub(super) const SIZE_X: usize = (image_consts::ORIG_BG_SIZE_X as f64 * DEFAULT_SCALE) as usize;
+/// Default pixel height of the editor window.
+pub(super) const SIZE_Y: usize = (image_consts::ORIG_BG_SIZE_Y as f64 * DEFAULT_SCALE) as usize;
+
+/// Describes where the interface is drawn within an editor window of arbitrary size. The interface
+/// is scaled uniformly to fit the window and centered along its other axis, so that the background
+/// image is never stretched.
+#[derive(Clone, Copy, Debug)]
+struct Layout {
+    /// Pixel dimensions of the editor window.
+    window_size: (u32, u32),
+    /// Number of window pixels per pixel of the original image assets.
+    scale: f64,
+    /// Window pixel coordinate of the top-left corner of the interface.
+    origin: (f64, f64),
+}
+
+impl Layout {
+    fn new(window_size: (u32, u32)) -> Self {
+        let scale = (window_size.0 as f64 / image_consts::ORIG_BG_SIZE_X as f64)
+            .min(window_size.1 as f64 / image_consts::ORIG_BG_SIZE_Y as f64);
+        let origin = (
+            (window_size.0 as f64 - image_consts::ORIG_BG_SIZE_X as f64 * scale) / 2.,
+            (window_size.1 as f64 - image_consts::ORIG_BG_SIZE_Y as f64 * scale) / 2.,
+        );
+        Self {
+            window_size,
+            scale,
+            origin,
+        }
+    }
+
+    /// Pixel dimensions of the interface within the window.
+    fn interface_size(&self) -> (f64, f64) {
+        (
+            image_consts::ORIG_BG_SIZE_X as f64 * self.scale,
+            image_consts::ORIG_BG_SIZE_Y as f64 * self.scale,
+        )
+    }
+
+    /// Converts a window pixel coordinate into a pixel coordinate of the original image assets.
+    fn to_image_coords(self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            (x - self.origin.0) / self.scale,
+            (y - self.origin.1) / self.scale,
+        )
+    }
+
+    /// Converts a pixel coordinate of the original image assets into a window pixel coordinate.
+    fn to_window_coords(self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            x * self.scale + self.origin.0,
+            y * self.scale + self.origin.1,
+        )
+    }
+}
+
+/// Returns the window size for the next zoom level above (if `zoom_in` is set) or below the scale
+/// of a window of the given `size`, or `None` if it is already at the limit.
+pub(super) fn zoomed_size(size: (u32, u32), zoom_in: bool) -> Option<(u32, u32)> {
+    let scale = Layout::new(size).scale;
+    let new_scale = if zoom_in {
+        ZOOM_LEVELS
+            .iter()
+            .find(|&&zoom| zoom > scale + ZOOM_TOLERANCE)
+    } else {
+        ZOOM_LEVELS
+            .iter()
+            .rev()
+            .find(|&&zoom| zoom < scale - ZOOM_TOLERANCE)
+    }?;
+    Some((
+        (image_consts::ORIG_BG_SIZE_X as f64 * new_scale) as u32,
+        (image_consts::ORIG_BG_SIZE_Y as f64 * new_scale) as u32,
+    ))
+}
+
+/// Whether both dimensions of a window of the given `size` are within the range that
+/// `zoomed_size` steps between.
+pub(super) fn is_within_zoom_range(size: (u32, u32)) -> bool {
+    let min_scale = ZOOM_LEVELS[0] - ZOOM_TOLERANCE;
+    let max_scale = ZOOM_LEVELS[ZOOM_LEVELS.len() - 1] + ZOOM_TOLERANCE;
+    let in_range = |length: u32, orig_length: usize| {
+        (orig_length as f64 * min_scale..=orig_length as f64 * max_scale).contains(&(length as f64))
+    };
+    in_range(size.0, image_consts::ORIG_BG_SIZE_X) && in_range(size.1, image_consts::ORIG_BG_SIZE_Y)
+}
+
+/// Draws the interface into the editor window. Abstracted so that the rest of `EditorInterface`
+/// can be tested without a GPU.
+trait Render {
+    /// Draws the current state of the interface.
+    fn draw_frame(&mut self, state: &InterfaceState);
+}
+
+impl Render for graphics::Renderer {
+    fn draw_frame(&mut self, state: &InterfaceState) {
+        graphics::Renderer::draw_frame(self, state)
+    }
+}
+
+/// Provides input events from the editor window. Abstracted so that the rest of
+/// `EditorInterface` can be tested without a window.
+trait PollEvents {
+    /// Returns the next input event, if one is available.
+    fn poll_event(&self) -> Option<WindowEvent>;
+}
+
+impl PollEvents for EventSource {
+    fn poll_event(&self) -> Option<WindowEvent> {
+        EventSource::poll_event(self)
+    }
+}
 
 /// Represents a window containing an editor interface. A new one is used each time the parent
 /// window provided by the host DAW is opened or closed.
 pub(super) struct EditorInterface {
-    renderer: graphics::Renderer,
-    event_source: EventSource,
+    renderer: Box<dyn Render>,
+    event_source: Box<dyn PollEvents>,
     state: InterfaceState,
+    /// Reads the output levels and other measurements reported by the audio processing thread.
+    reports: DspReportReader,
+    /// Number of physical display pixels per logical pixel of the window.
+    scale_factor: f32,
+    /// Time at which the interface was last drawn, or `None` if it hasn't been drawn yet.
+    last_frame: Option<Instant>,
 }
 
+/// Frames are only drawn when something visible has changed, but at least this often regardless,
+/// in case the window system discarded the last frame presented.
+const MAX_FRAME_INTERVAL: Duration = Duration::from_secs(1);
+/// Frames are drawn at most this often, however often the host asks the editor to run, which caps
+/// its CPU and GPU usage at about 60 frames per second.
+const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
 impl EditorInterface {
-    /// Setup the `EditorInterface` within the provided parent `EditorWindow` to respond to events
-    /// from the corresponding `EventSource`.
+    /// Setup the `EditorInterface` within the provided parent `EditorWindow` of the given logical
+    /// pixel `size` to respond to events from the corresponding `EventSource`. The interface is
+    /// rendered at full resolution for a display with the given `scale_factor`.
+    ///
+    /// Fails if the interface can't be rendered, for example if no GPU is available.
     pub fn new(
         window: EditorWindow,
         event_source: EventSource,
+        size: (u32, u32),
+        scale_factor: f32,
+        mut initial_state: InterfaceState,
+        reports: DspReportReader,
+    ) -> Result<Self, RendererError> {
+        // The layout scales everything to fit the window, so rendering to a layout of the physical
+        // window size keeps the interface looking the same, but with sharper images and text.
+        let layout = Layout::new(physical_size(size, scale_factor));
+        let renderer = graphics::Renderer::new(window, layout)?;
+        initial_state.set_layout(layout);
+
+        Ok(Self::from_parts(
+            Box::new(renderer),
+            Box::new(event_source),
+            scale_factor,
+            initial_state,
+            reports,
+        ))
+    }
+
+    /// Assembles an `EditorInterface` from a `renderer` and `event_source` that have already been
+    /// set up for the window, and an `initial_state` already laid out for it.
+    fn from_parts(
+        renderer: Box<dyn Render>,
+        event_source: Box<dyn PollEvents>,
+        scale_factor: f32,
         initial_state: InterfaceState,
+        reports: DspReportReader,
     ) -> Self {
-        let renderer = graphics::Renderer::new(window);
-
         Self {
             renderer,
             event_source,
             state: initial_state,
+            reports,
+            scale_factor,
+            last_frame: None,
         }
     }
 
     /// Run as much as possible of the editor interface without blocking. This means acting on any
-    /// pending state change events from remote state storage, responding to any new window input
-    /// events, and then rendering the new state of the UI.
+    /// pending state change events from remote state storage and measurements from the audio
+    /// processing thread, responding to any new window input events, and then rendering the new
+    /// state of the UI if anything visible has changed.
     pub fn run_tasks<S: EditorRemoteState>(
         &mut self,
         remote_state: &S,
         incoming: &mut Receiver<StateUpdate>,
+    ) {
+        self.run_tasks_at(remote_state, incoming, Instant::now());
+    }
+
+    /// Like `run_tasks`, with `now` as the current time.
+    fn run_tasks_at<S: EditorRemoteState>(
+        &mut self,
+        remote_state: &S,
+        incoming: &mut Receiver<StateUpdate>,
+        now: Instant,
     ) {
         while let Ok(event) = incoming.try_recv() {
             self.state.react_to_control_event(event);
         }
+        let state = &mut self.state;
+        self.reports
+            .poll(|report| state.react_to_control_event(report));
 
         while let Some(event) = self.event_source.poll_event() {
             self.state.react_to_window_event(event, remote_state);
         }
 
-        self.renderer.draw_frame(&self.state);
+        self.state.advance_time(now);
+        let since_last_frame = self
+            .last_frame
+            .map(|last_frame| now.saturating_duration_since(last_frame));
+        if since_last_frame.is_some_and(|since| since < MIN_FRAME_INTERVAL) {
+            // Anything that changed stays marked dirty, to be drawn in the next frame.
+            return;
+        }
+        let dirty = self.state.take_dirty();
+        if dirty || since_last_frame.is_none_or(|since| since >= MAX_FRAME_INTERVAL) {
+            self.renderer.draw_frame(&self.state);
+            self.last_frame = Some(now);
+        }
+    }
+
+    /// Closes the interface, returning its state so that it can be carried over to a new
+    /// interface, like one set up in a recreated window of a different size.
+    pub fn into_state(self) -> InterfaceState {
+        self.state
+    }
+
+    /// Number of physical display pixels per logical pixel of the window, as the interface was
+    /// last set up for.
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Forwards a key press from the host to the editor state. Returns `true` if the key was used.
+    pub fn key_down<S: EditorRemoteState>(&mut self, keycode: KeyCode, remote_state: &S) -> bool {
+        let used = self.state.react_to_key_down(keycode, remote_state);
+        if used {
+            self.state.mark_dirty();
+        }
+        used
+    }
+
+    /// Forwards a key release from the host to the editor state. Returns `true` if the key was
+    /// used.
+    pub fn key_up(&mut self, keycode: KeyCode) -> bool {
+        let used = self.state.react_to_key_up(keycode);
+        if used {
+            self.state.mark_dirty();
+        }
+        used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::MAKEUP_GAIN;
+    use state::tests::RecordingRemote;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::mpsc::{channel, Sender};
+    use vst_window::MouseButton;
+
+    /// Knob values of each frame drawn by a `RecordingRenderer`, in order.
+    type Frames = Rc<RefCell<Vec<[f32; NUM_KNOBS]>>>;
+
+    /// Records the knob values of every frame drawn, in place of a GPU renderer.
+    struct RecordingRenderer {
+        frames: Frames,
+    }
+
+    impl Render for RecordingRenderer {
+        fn draw_frame(&mut self, state: &InterfaceState) {
+            self.frames.borrow_mut().push(state.knob_values);
+        }
+    }
+
+    impl PollEvents for Receiver<WindowEvent> {
+        fn poll_event(&self) -> Option<WindowEvent> {
+            self.try_recv().ok()
+        }
+    }
+
+    /// Creates an interface that records its frames, along with the frames and a sender of window
+    /// events for it.
+    fn test_interface() -> (EditorInterface, Frames, Sender<WindowEvent>) {
+        let frames = Rc::new(RefCell::new(Vec::new()));
+        let (events, event_source) = channel();
+        let interface = EditorInterface::from_parts(
+            Box::new(RecordingRenderer {
+                frames: Rc::clone(&frames),
+            }),
+            Box::new(event_source),
+            1.,
+            InterfaceState::new(|_| 0.5, 0, 0, false, 0.),
+            crate::plugin_state::EditorNotifier::open().1,
+        );
+        (interface, frames, events)
+    }
+
+    #[test]
+    fn run_tasks_draws_after_changes() {
+        let remote = RecordingRemote::default();
+        let (mut interface, frames, events) = test_interface();
+        let (to_editor, mut incoming) = channel();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // The first frame is always drawn, but nothing is drawn again until something changes.
+        interface.run_tasks_at(&remote, &mut incoming, at(0));
+        interface.run_tasks_at(&remote, &mut incoming, at(100));
+        assert_eq!(frames.borrow().len(), 1);
+
+        // Changes are drawn straight away, unless a frame was drawn too recently, in which case
+        // they wait for the next one.
+        to_editor.send(StateUpdate::SetKnob(1.)).unwrap();
+        interface.run_tasks_at(&remote, &mut incoming, at(200));
+        assert_eq!(frames.borrow().len(), 2);
+        assert_eq!(frames.borrow()[1][AMPLITUDE_KNOB], 1.);
+        to_editor.send(StateUpdate::SetKnob(0.)).unwrap();
+        interface.run_tasks_at(&remote, &mut incoming, at(205));
+        assert_eq!(frames.borrow().len(), 2);
+        interface.run_tasks_at(&remote, &mut incoming, at(216));
+        assert_eq!(frames.borrow().len(), 3);
+        assert_eq!(frames.borrow()[2][AMPLITUDE_KNOB], 0.);
+
+        // Dragging a knob up sets its parameter.
+        let gain_knob = KNOBS
+            .iter()
+            .position(|knob| knob.parameter == MAKEUP_GAIN)
+            .unwrap();
+        let layout = Layout::new((SIZE_X as u32, SIZE_Y as u32));
+        let cursor = |(x, y): (f64, f64)| {
+            let (x, y) = layout.to_window_coords((x, y));
+            WindowEvent::CursorMovement(x as f32 / SIZE_X as f32, y as f32 / SIZE_Y as f32)
+        };
+        let (center_x, center_y) = KNOBS[gain_knob].center;
+        let (center_x, center_y) = (center_x as f64, center_y as f64);
+        events.send(cursor((center_x, center_y))).unwrap();
+        events
+            .send(WindowEvent::MouseClick(MouseButton::Left))
+            .unwrap();
+        events.send(cursor((center_x, center_y - 100.))).unwrap();
+        events
+            .send(WindowEvent::MouseRelease(MouseButton::Left))
+            .unwrap();
+        interface.run_tasks_at(&remote, &mut incoming, at(300));
+
+        let (index, value) = *remote.parameters.borrow().last().unwrap();
+        assert_eq!(index, MAKEUP_GAIN);
+        assert!(value > 0.5);
+        let frames = frames.borrow();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[3][gain_knob], value);
     }
 }
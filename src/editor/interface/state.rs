@@ -3,48 +3,589 @@
 //! `InterfaceState` struct along with logic to update it in response to window events like clicks,
 //! drags, etc. as well as from external state updates.
 
+use std::time::{Duration, Instant};
+
+use vst::api::ModifierKey;
+use vst::editor::{Key, KeyCode};
 use vst_window::WindowEvent;
 
 use super::{
-    image_consts::{ORIG_KNOB_RADIUS, ORIG_KNOB_X, ORIG_KNOB_Y},
-    SCALE, SIZE_X, SIZE_Y,
+    clipboard::{open_clipboard, Clipboard},
+    image_consts::{
+        KNOB_ANGLE_RANGE, KNOB_START_ANGLE, ORIG_ABOUT_BUTTON_BOTTOM, ORIG_ABOUT_BUTTON_LEFT,
+        ORIG_ABOUT_BUTTON_RIGHT, ORIG_ABOUT_BUTTON_TOP, ORIG_CLIP_INDICATOR_TOP, ORIG_METER_BOTTOM,
+        ORIG_METER_LEFT, ORIG_METER_RIGHT, ORIG_MUTE_BUTTON_BOTTOM, ORIG_MUTE_BUTTON_LEFT,
+        ORIG_MUTE_BUTTON_RIGHT, ORIG_MUTE_BUTTON_TOP, ORIG_READOUT_BOTTOM, ORIG_READOUT_LEFT,
+        ORIG_READOUT_RIGHT, ORIG_READOUT_TOP, ORIG_SNAPSHOT_BUTTON_BOTTOM,
+        ORIG_SNAPSHOT_BUTTON_TOP, ORIG_SNAPSHOT_STORE_SPANS, ORIG_SNAPSHOT_TOGGLE_SPAN,
+        ORIG_THEME_TOGGLE_BOTTOM, ORIG_THEME_TOGGLE_LEFT, ORIG_THEME_TOGGLE_RIGHT,
+        ORIG_THEME_TOGGLE_TOP, ORIG_TRIM_CONTROL_BOTTOM, ORIG_TRIM_CONTROL_LEFT,
+        ORIG_TRIM_CONTROL_RIGHT, ORIG_TRIM_CONTROL_TOP,
+    },
+    menu::{ContextMenu, MenuItem, MENU_ITEMS},
+    theme::THEMES,
+    Knob, Layout, AMPLITUDE_KNOB, KNOBS, NUM_KNOBS, SIZE_X, SIZE_Y,
 };
-use crate::plugin_state::StateUpdate;
+use crate::dsp::MAX_OUTPUT_TRIM_DB;
+use crate::parameters::{AMPLITUDE, MAKEUP_GAIN};
+use crate::plugin_state::{StateUpdate, NUM_SNAPSHOTS};
 
 /// All the possible ways a click+drag operation on the interface window might be interpreted.
 enum DragBehavior {
-    TurnAmplitudeKnob { click_y: isize, original_value: f32 },
+    /// Turns the knob at the given index into `KNOBS`.
+    TurnKnob {
+        knob: usize,
+        click_y: isize,
+        original_value: f32,
+        /// Value of the knob when the drag started, restored if the drag is undone.
+        /// `original_value` changes whenever fine adjustment is toggled, but this doesn't.
+        drag_start_value: f32,
+        /// Angle of the cursor around the knob's center when it was last moved, in degrees
+        /// clockwise from the top, or `None` if it hasn't left the dead zone around the center yet.
+        /// Only used by `KnobDragMode::Circular`.
+        last_angle: Option<f32>,
+        /// Value that a circular drag has turned the knob to, before any snapping, so that small
+        /// movements add up rather than being rounded away. Only used by
+        /// `KnobDragMode::Circular`.
+        unsnapped_value: f32,
+    },
+    /// Adjusts the output trim, like a vertical knob drag.
+    AdjustTrim { click_y: isize, original_trim: f32 },
+}
+
+/// A change made from the editor that can be undone.
+enum UndoStep {
+    /// The knob at the given index into `KNOBS` was changed from the given value.
+    Knob(usize, f32),
+    /// Every parameter was reset to its default, from the given values.
+    ResetAll(Vec<f32>),
+}
+
+/// Ways that dragging the cursor can turn a knob.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(in crate::editor) enum KnobDragMode {
+    /// Dragging upwards increases the value, and dragging downwards decreases it.
+    #[default]
+    Vertical,
+    /// Moving the cursor clockwise around the knob's center increases the value, and moving it
+    /// counterclockwise decreases it, as if turning the knob directly.
+    Circular,
+}
+
+impl KnobDragMode {
+    /// Returns the other drag mode.
+    fn toggled(self) -> Self {
+        match self {
+            Self::Vertical => Self::Circular,
+            Self::Circular => Self::Vertical,
+        }
+    }
+}
+
+/// How the distance of a vertical knob drag is mapped onto a change in the knob's value.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(in crate::editor) enum DragCurve {
+    /// The change is proportional to the distance dragged.
+    #[default]
+    Linear,
+    /// Short drags change the value more finely than `Linear`, and long drags more coarsely, so
+    /// that both small adjustments and large sweeps are easy.
+    Accelerated,
+}
+
+impl DragCurve {
+    /// Maps `distance`, a signed proportion of the interface's height, onto the distance that a
+    /// linear drag would need to make the same change.
+    fn apply(self, distance: f32) -> f32 {
+        match self {
+            Self::Linear => distance,
+            Self::Accelerated => {
+                distance * distance.abs().powf(ACCELERATION_EXPONENT - 1.)
+                    / ACCELERATION_CROSSOVER.powf(ACCELERATION_EXPONENT - 1.)
+            }
+        }
+    }
+
+    /// Returns the other curve.
+    fn toggled(self) -> Self {
+        match self {
+            Self::Linear => Self::Accelerated,
+            Self::Accelerated => Self::Linear,
+        }
+    }
+}
+
+/// Moves the displayed knob positions to their values in a straight line, in sync with the audio
+/// processing thread's crossfade to a newly loaded preset.
+struct KnobCrossfade {
+    /// Displayed knob positions when the preset was loaded.
+    from: [f32; NUM_KNOBS],
+    /// Time since the preset was loaded, in seconds.
+    elapsed: f32,
+    /// Length of the crossfade, in seconds.
+    duration: f32,
 }
 
 /// Holds any state required to render and update the editor interface.
 pub(in crate::editor) struct InterfaceState {
-    /// Represents the position of the knob, from 0 to 1.
-    pub amplitude_value: f32,
+    /// Represents the position of each knob in `KNOBS`, from 0 to 1.
+    pub knob_values: [f32; NUM_KNOBS],
+    /// Position at which each knob is drawn, from 0 to 1. This follows `knob_values` smoothly when
+    /// they are changed from outside of the editor, like by host automation, so that the knobs
+    /// move smoothly even if updates arrive infrequently. It's never used as a parameter value.
+    pub displayed_knob_values: [f32; NUM_KNOBS],
+    /// Linear peak output levels of the left and right channels shown by the meters. Each jumps
+    /// up to new peaks immediately, and decays smoothly otherwise.
+    pub meter_levels: [f32; 2],
+    /// Highest linear level recently shown by each meter, which is marked by a line. Each is held
+    /// for `PEAK_HOLD_SECONDS` after being reached, and then decays like the meters.
+    pub peak_holds: [f32; 2],
+    /// Time left before each held peak starts to decay, in seconds.
+    peak_hold_times: [f32; 2],
+    /// Whether each meter has reached full scale since the meters were last clicked.
+    pub clip_holds: [bool; 2],
+    /// Recent downsampled output samples shown by the scope, oldest first.
+    pub scope_samples: [f32; SCOPE_LEN],
+    /// Whether the audio processing thread is still smoothing a parameter towards a new value.
+    pub smoothing_active: bool,
+    /// Whether the input clipping indicator is shown. It stays on while the input is clipping, and
+    /// for `INPUT_CLIP_HOLD_SECONDS` afterwards.
+    pub input_clip_indicator: bool,
+    /// Whether the audio processing thread last reported that the input is clipping.
+    input_clipping: bool,
+    /// Time left before the input clipping indicator turns off, in seconds, once the input has
+    /// stopped clipping.
+    input_clip_hold: f32,
+    /// Strength of the highlight drawn around each knob in `KNOBS` after its value is changed from
+    /// outside of the editor, like by host automation. Set to 1 by each change, and fades to 0
+    /// over `AUTOMATION_HIGHLIGHT_SECONDS`.
+    pub automation_highlights: [f32; NUM_KNOBS],
+    /// Time over which loading a preset crossfades to its parameters, in seconds.
+    preset_crossfade_seconds: f32,
+    /// Preset crossfade that the displayed knob positions are following, if any.
+    knob_crossfade: Option<KnobCrossfade>,
+    /// Time up to which animations like the meter decay have been advanced.
+    last_tick: Instant,
     /// (X, Y) pixel coordinate of the cursor, from the top-left corner.
     /// Coordinates could be negative if the cursor is dragged outside of the window!
     cursor_pos: (isize, isize),
     drag_behavior: Option<DragBehavior>,
+    /// Whether the Shift key is held, reducing the sensitivity of knob drags.
+    fine_adjust: bool,
+    /// Whether the Ctrl key is held, snapping knob drags to each knob's grid.
+    snap_held: bool,
+    /// Whether knob drags always snap to each knob's grid, as if Ctrl were held. Toggled with
+    /// Ctrl+G.
+    pub snap_to_grid: bool,
+    /// How dragging the cursor turns a knob. Toggled with Ctrl+R.
+    pub knob_drag_mode: KnobDragMode,
+    /// How the distance of a vertical knob drag is mapped onto a change in value. Toggled with
+    /// Ctrl+A.
+    pub drag_curve: DragCurve,
+    /// Whether clicking a knob immediately sets it to the value pointing towards the cursor,
+    /// before any drag continues from there. Toggled with Ctrl+J.
+    pub jump_to_value: bool,
+    /// Index and time of the last left click on a knob, used to detect double-clicks.
+    last_knob_click: Option<(usize, Instant)>,
+    /// Index of the knob nudged by the arrow keys, which is the most recently clicked knob.
+    focused_knob: usize,
+    /// Placement of the interface within the editor window, used to interpret cursor positions.
+    layout: Layout,
+    /// Text typed into the readout since it was clicked, or `None` if the readout isn't being
+    /// edited.
+    pub text_entry: Option<String>,
+    /// Index of the knob set by the text being typed into the readout.
+    text_entry_knob: usize,
+    /// Menu of actions on a knob's value, opened by right-clicking the knob.
+    pub context_menu: Option<ContextMenu>,
+    /// Whether the overlay showing the plugin's name and version is open.
+    pub about_open: bool,
+    /// Index into `THEMES` of the color theme used to draw the interface.
+    pub theme: usize,
+    /// Index of the A/B snapshot most recently stored or recalled.
+    pub active_snapshot: usize,
+    /// Whether the output is muted. Unlike the knobs, this doesn't correspond to a parameter.
+    pub muted: bool,
+    /// Gain applied to the output after all other processing, in decibels. Like the mute, this
+    /// doesn't correspond to a parameter.
+    pub output_trim: f32,
+    /// Recent changes that can be undone, most recent last.
+    undo_stack: Vec<UndoStep>,
+    /// Where knob values are copied to and pasted from.
+    clipboard: Box<dyn Clipboard>,
+    /// Whether anything visible has changed since the interface was last drawn.
+    dirty: bool,
 }
 
-const KNOB_CENTER_X: usize = (ORIG_KNOB_X as f64 * SCALE) as usize;
-const KNOB_CENTER_Y: usize = (ORIG_KNOB_Y as f64 * SCALE) as usize;
-const KNOB_RADIUS: usize = (ORIG_KNOB_RADIUS as f64 * SCALE) as usize;
-
 const KNOB_CHANGE_SPEED: f32 = 0.5;
+/// Power to which the distance of an accelerated knob drag is raised.
+const ACCELERATION_EXPONENT: f32 = 2.;
+/// Accelerated knob drags change the value at the same rate as linear drags once they cover this
+/// proportion of the interface's height. Shorter drags are finer, and longer drags coarser.
+const ACCELERATION_CROSSOVER: f32 = 0.25;
+/// Change in a knob's value when nudged with the Up or Down arrow keys.
+const KEY_NUDGE_STEP: f32 = 0.01;
+/// Change in a knob's value when nudged with the Page Up or Page Down keys.
+const KEY_PAGE_STEP: f32 = 0.1;
+/// Knob drags are this many times less sensitive while fine adjustment is enabled.
+const FINE_ADJUST_DIVISOR: f32 = 5.;
+/// Two clicks on the knob within this interval are treated as a double-click.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+/// Number of recent output samples shown by the scope.
+pub(super) const SCOPE_LEN: usize = 256;
+/// Maximum number of knob changes that can be undone.
+const UNDO_STACK_LIMIT: usize = 32;
+/// Time taken for a displayed knob position to cover about 63% of the distance to its value, in
+/// seconds.
+const KNOB_DISPLAY_SMOOTHING_SECONDS: f32 = 0.03;
+/// Circular knob drags ignore the cursor while it's this close to the center of the knob, as a
+/// proportion of the knob's radius, since tiny movements there would make the angle jump wildly.
+const CIRCULAR_DRAG_DEAD_ZONE: f64 = 0.1;
+/// Time taken for the highlight around a knob changed from outside of the editor to fade out, in
+/// seconds.
+const AUTOMATION_HIGHLIGHT_SECONDS: f32 = 0.2;
+/// Time for which the input clipping indicator stays on after the input stops clipping, in
+/// seconds.
+const INPUT_CLIP_HOLD_SECONDS: f32 = 1.;
+/// Time for which the peak level of each meter is held before it decays, in seconds.
+const PEAK_HOLD_SECONDS: f32 = 2.;
+/// Rate at which the meter falls back after a peak.
+const METER_DECAY_DB_PER_SECOND: f32 = 24.;
+/// Output level displayed at the bottom of the meter. Once a meter falls below this, it stops
+/// decaying, so that it doesn't keep the interface redrawing.
+pub(super) const METER_MIN_DB: f32 = -48.;
+/// Displayed knob positions closer than this to their values are snapped to them, so that the
+/// interface isn't redrawn indefinitely for imperceptible movements.
+const KNOB_DISPLAY_TOLERANCE: f32 = 1e-4;
 
 impl InterfaceState {
-    pub fn new(amplitude_value: f32) -> Self {
+    /// Creates a new `InterfaceState`, using `parameter_value` to get the current value of each
+    /// knob's parameter by index, and drawn with the theme at index `theme`. `active_snapshot` is
+    /// the index of the A/B snapshot most recently stored or recalled, `muted` is whether the
+    /// output is muted, and `output_trim` is the current output trim in decibels.
+    pub fn new(
+        parameter_value: impl Fn(usize) -> f32,
+        theme: usize,
+        active_snapshot: usize,
+        muted: bool,
+        output_trim: f32,
+    ) -> Self {
+        let mut knob_values = [0.; NUM_KNOBS];
+        for (value, knob) in knob_values.iter_mut().zip(KNOBS.iter()) {
+            *value = parameter_value(knob.parameter);
+        }
         Self {
-            amplitude_value,
+            knob_values,
+            displayed_knob_values: knob_values,
+            meter_levels: [0.; 2],
+            peak_holds: [0.; 2],
+            peak_hold_times: [0.; 2],
+            clip_holds: [false; 2],
+            scope_samples: [0.; SCOPE_LEN],
+            smoothing_active: false,
+            input_clip_indicator: false,
+            input_clipping: false,
+            input_clip_hold: 0.,
+            automation_highlights: [0.; NUM_KNOBS],
+            preset_crossfade_seconds: 0.,
+            knob_crossfade: None,
+            last_tick: Instant::now(),
             cursor_pos: Default::default(),
             drag_behavior: None,
+            fine_adjust: false,
+            snap_held: false,
+            snap_to_grid: false,
+            knob_drag_mode: KnobDragMode::default(),
+            drag_curve: DragCurve::default(),
+            jump_to_value: false,
+            last_knob_click: None,
+            focused_knob: AMPLITUDE_KNOB,
+            layout: Layout::new((SIZE_X as u32, SIZE_Y as u32)),
+            text_entry: None,
+            text_entry_knob: AMPLITUDE_KNOB,
+            context_menu: None,
+            about_open: false,
+            // The stored theme may be out of range if it was restored from a corrupted preset.
+            theme: if theme < THEMES.len() { theme } else { 0 },
+            active_snapshot,
+            muted,
+            output_trim,
+            undo_stack: Vec::with_capacity(UNDO_STACK_LIMIT),
+            clipboard: open_clipboard(),
+            dirty: true,
         }
     }
 
+    /// Updates the placement of the interface within the editor window.
+    pub(super) fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+        self.dirty = true;
+    }
+
+    /// Records that something visible has changed, so that the interface should be redrawn.
+    pub(super) fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Returns whether anything visible has changed since the last call, in which case the
+    /// interface should be redrawn.
+    pub(super) fn take_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.dirty, false)
+    }
+
     /// Update the editor state in response to an external message.
     pub fn react_to_control_event(&mut self, event: StateUpdate) {
         match event {
-            StateUpdate::SetKnob(value) => self.amplitude_value = value,
+            StateUpdate::SetKnob(value) => self.set_parameter_value(AMPLITUDE, value),
+            StateUpdate::SetMakeupGain(value) => self.set_parameter_value(MAKEUP_GAIN, value),
+            StateUpdate::MeterLevels(levels) => {
+                for (meter, &level) in levels.iter().enumerate() {
+                    if level > self.meter_levels[meter] {
+                        self.meter_levels[meter] = level;
+                        self.dirty = true;
+                    }
+                    if level >= self.peak_holds[meter] {
+                        self.peak_holds[meter] = level;
+                        self.peak_hold_times[meter] = PEAK_HOLD_SECONDS;
+                    }
+                    if level >= 1. && !self.clip_holds[meter] {
+                        self.clip_holds[meter] = true;
+                        self.dirty = true;
+                    }
+                }
+            }
+            StateUpdate::SmoothingActive(active) => {
+                self.dirty |= self.smoothing_active != active;
+                self.smoothing_active = active;
+            }
+            StateUpdate::InputClipping(clipping) => {
+                self.input_clipping = clipping;
+                if clipping {
+                    self.dirty |= !self.input_clip_indicator;
+                    self.input_clip_indicator = true;
+                } else {
+                    self.input_clip_hold = INPUT_CLIP_HOLD_SECONDS;
+                }
+            }
+            StateUpdate::SetOutputTrim(db) => {
+                self.dirty |= self.output_trim != db;
+                self.output_trim = db;
+            }
+            StateUpdate::ScopeSamples(samples) => {
+                // Silence keeps arriving while the host is playing, but only needs to be drawn
+                // once.
+                let previous = self.scope_samples;
+                self.scope_samples.rotate_left(samples.len());
+                self.scope_samples[SCOPE_LEN - samples.len()..].copy_from_slice(&samples);
+                self.dirty |= self.scope_samples != previous;
+            }
+            StateUpdate::Batch(updates) => {
+                for update in updates {
+                    self.react_to_control_event(update);
+                }
+            }
+            StateUpdate::Crossfade(updates) => {
+                // Like the audio processing thread, use the crossfade time from before the preset
+                // was loaded.
+                let duration = self.preset_crossfade_seconds;
+                let from = self.displayed_knob_values;
+                for update in updates {
+                    self.react_to_control_event(update);
+                }
+                if duration > 0. {
+                    self.knob_crossfade = Some(KnobCrossfade {
+                        from,
+                        elapsed: 0.,
+                        duration,
+                    });
+                }
+            }
+            StateUpdate::SetPresetCrossfade(ms) => self.preset_crossfade_seconds = ms / 1000.,
+            StateUpdate::SetWidth(_)
+            | StateUpdate::SetMix(_)
+            | StateUpdate::SetSaturation(_)
+            | StateUpdate::SetOversampling(_)
+            | StateUpdate::SetPan(_)
+            | StateUpdate::SetChannelGain(..)
+            | StateUpdate::SetPhaseInvert(_)
+            | StateUpdate::SetAutoGain(_)
+            | StateUpdate::SetAmplitudeController(_)
+            | StateUpdate::SetControllerPickup(_)
+            | StateUpdate::SetEnvelopeRelease(_)
+            | StateUpdate::SetAutoGainTarget(_)
+            | StateUpdate::SetGlideTime(_)
+            | StateUpdate::SetGate(_)
+            | StateUpdate::SetMute(_)
+            | StateUpdate::SetMonoSum(_)
+            | StateUpdate::SetDcBlock(_)
+            | StateUpdate::SetLoudnessMatch(_) => (),
+        }
+    }
+
+    /// Updates the value of any knob controlling the parameter at `index`.
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        for ((knob_value, highlight), knob) in self
+            .knob_values
+            .iter_mut()
+            .zip(self.automation_highlights.iter_mut())
+            .zip(KNOBS.iter())
+        {
+            if knob.parameter == index && *knob_value != value {
+                *knob_value = value;
+                *highlight = 1.;
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Sets the knob at `knob` to a new value, and updates the remote state to match. The knob is
+    /// drawn at the new value immediately, since it's being changed from within the editor.
+    fn set_knob<S: super::EditorRemoteState>(&mut self, knob: usize, value: f32, remote_state: &S) {
+        self.knob_crossfade = None;
+        self.knob_values[knob] = value;
+        self.displayed_knob_values[knob] = value;
+        remote_state.set_parameter_control(KNOBS[knob].parameter, value);
+    }
+
+    /// Sets the knob at `knob` to a new value as a single complete gesture, for changes that don't
+    /// involve dragging. The gesture can be undone.
+    fn set_knob_gesture<S: super::EditorRemoteState>(
+        &mut self,
+        knob: usize,
+        value: f32,
+        remote_state: &S,
+    ) {
+        self.push_undo(knob, self.knob_values[knob]);
+        self.set_knob_without_undo(knob, value, remote_state);
+    }
+
+    /// Like `set_knob_gesture`, but the gesture can't be undone.
+    fn set_knob_without_undo<S: super::EditorRemoteState>(
+        &mut self,
+        knob: usize,
+        value: f32,
+        remote_state: &S,
+    ) {
+        remote_state.begin_parameter_edit(KNOBS[knob].parameter);
+        self.set_knob(knob, value, remote_state);
+        remote_state.end_parameter_edit(KNOBS[knob].parameter);
+    }
+
+    /// Records that the knob at `knob` had `previous_value` before a gesture, so that the gesture
+    /// can be undone. The oldest record is forgotten once the stack is full.
+    fn push_undo(&mut self, knob: usize, previous_value: f32) {
+        self.push_undo_step(UndoStep::Knob(knob, previous_value));
+    }
+
+    /// Records a change that can be undone. The oldest record is forgotten once the stack is
+    /// full.
+    fn push_undo_step(&mut self, step: UndoStep) {
+        if self.undo_stack.len() == UNDO_STACK_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(step);
+    }
+
+    /// Reverts the most recent change that hasn't been undone yet, if any. Returns `true` if there
+    /// was one.
+    fn undo<S: super::EditorRemoteState>(&mut self, remote_state: &S) -> bool {
+        match self.undo_stack.pop() {
+            Some(UndoStep::Knob(knob, value)) => {
+                self.set_knob_without_undo(knob, value, remote_state)
+            }
+            // The knobs follow along through the usual `StateUpdate` messages.
+            Some(UndoStep::ResetAll(values)) => remote_state.set_all_parameters(&values),
+            None => return false,
+        }
+        true
+    }
+
+    /// Resets every parameter to its default value. This can be undone.
+    fn reset_all<S: super::EditorRemoteState>(&mut self, remote_state: &S) {
+        let previous_values = remote_state.reset_all_parameters();
+        self.push_undo_step(UndoStep::ResetAll(previous_values));
+    }
+
+    /// Advance any time-based animations up to `now`. The interface is marked dirty for as long
+    /// as any animation is still in progress.
+    pub fn advance_time(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_tick).as_secs_f32();
+        self.last_tick = now;
+        let meter_decay = 10f32.powf(-METER_DECAY_DB_PER_SECOND * elapsed / 20.);
+        let meter_floor = 10f32.powf(METER_MIN_DB / 20.);
+        for meter_level in self.meter_levels.iter_mut() {
+            if *meter_level > meter_floor {
+                *meter_level *= meter_decay;
+                self.dirty = true;
+            } else {
+                *meter_level = 0.;
+            }
+        }
+        for (peak_hold, hold_time) in self
+            .peak_holds
+            .iter_mut()
+            .zip(self.peak_hold_times.iter_mut())
+        {
+            // Only the time after the hold ends is spent decaying.
+            let decay_seconds = elapsed - *hold_time;
+            *hold_time = (*hold_time - elapsed).max(0.);
+            if decay_seconds <= 0. {
+                continue;
+            }
+            if *peak_hold > meter_floor {
+                *peak_hold *= 10f32.powf(-METER_DECAY_DB_PER_SECOND * decay_seconds / 20.);
+                self.dirty = true;
+            } else {
+                *peak_hold = 0.;
+            }
+        }
+
+        if let Some(crossfade) = &mut self.knob_crossfade {
+            crossfade.elapsed += elapsed;
+            let progress = (crossfade.elapsed / crossfade.duration).min(1.);
+            for ((displayed, value), from) in self
+                .displayed_knob_values
+                .iter_mut()
+                .zip(self.knob_values.iter())
+                .zip(crossfade.from.iter())
+            {
+                *displayed = from + (value - from) * progress;
+            }
+            if progress >= 1. {
+                self.knob_crossfade = None;
+            }
+            self.dirty = true;
+        } else {
+            let knob_smoothing = 1. - (-elapsed / KNOB_DISPLAY_SMOOTHING_SECONDS).exp();
+            for (displayed, value) in self
+                .displayed_knob_values
+                .iter_mut()
+                .zip(self.knob_values.iter())
+            {
+                if *displayed == *value {
+                    continue;
+                }
+                *displayed += (value - *displayed) * knob_smoothing;
+                if (value - *displayed).abs() < KNOB_DISPLAY_TOLERANCE {
+                    *displayed = *value;
+                }
+                self.dirty = true;
+            }
+        }
+
+        if self.input_clip_indicator && !self.input_clipping {
+            self.input_clip_hold -= elapsed;
+            if self.input_clip_hold <= 0. {
+                self.input_clip_indicator = false;
+                self.dirty = true;
+            }
+        }
+
+        for highlight in self.automation_highlights.iter_mut() {
+            if *highlight > 0. {
+                *highlight = (*highlight - elapsed / AUTOMATION_HIGHLIGHT_SECONDS).max(0.);
+                self.dirty = true;
+            }
         }
     }
 
@@ -55,43 +596,1194 @@ impl InterfaceState {
         event: WindowEvent,
         remote_state: &S,
     ) {
+        // Moving the cursor only changes what's drawn while dragging something, or while
+        // hovering over the context menu.
+        if !matches!(event, WindowEvent::CursorMovement(..))
+            || self.drag_behavior.is_some()
+            || self.context_menu.is_some()
+        {
+            self.dirty = true;
+        }
         match event {
             WindowEvent::CursorMovement(x, y) => {
-                self.cursor_pos = ((x * SIZE_X as f32) as isize, (y * SIZE_Y as f32) as isize);
-                if let Some(DragBehavior::TurnAmplitudeKnob {
+                let (window_x, window_y) = self.layout.window_size;
+                self.cursor_pos = (
+                    (x * window_x as f32) as isize,
+                    (y * window_y as f32) as isize,
+                );
+                let fine_adjust_divisor = if self.fine_adjust {
+                    FINE_ADJUST_DIVISOR
+                } else {
+                    1.
+                };
+                if let Some(DragBehavior::AdjustTrim {
                     click_y,
-                    original_value,
-                    ..
+                    original_trim,
                 }) = self.drag_behavior
                 {
                     let diff_y = click_y - self.cursor_pos.1;
-                    self.amplitude_value = (original_value
-                        + diff_y as f32 / SIZE_Y as f32 * KNOB_CHANGE_SPEED)
-                        .max(0.)
-                        .min(1.);
-                    remote_state.set_amplitude_control(self.amplitude_value);
+                    let interface_height = self.layout.interface_size().1 as f32;
+                    // A drag that would turn a knob through its whole range covers the whole
+                    // range of the trim.
+                    let change = diff_y as f32 / interface_height
+                        * KNOB_CHANGE_SPEED
+                        * 2.
+                        * MAX_OUTPUT_TRIM_DB;
+                    self.output_trim = (original_trim + change / fine_adjust_divisor)
+                        .clamp(-MAX_OUTPUT_TRIM_DB, MAX_OUTPUT_TRIM_DB);
+                    remote_state.set_output_trim(self.output_trim);
+                    return;
+                }
+                let value = match (self.knob_drag_mode, &mut self.drag_behavior) {
+                    (
+                        KnobDragMode::Vertical,
+                        Some(DragBehavior::TurnKnob {
+                            knob,
+                            click_y,
+                            original_value,
+                            ..
+                        }),
+                    ) => {
+                        let diff_y = *click_y - self.cursor_pos.1;
+                        let interface_height = self.layout.interface_size().1 as f32;
+                        let distance = self.drag_curve.apply(diff_y as f32 / interface_height);
+                        let change = distance * KNOB_CHANGE_SPEED;
+                        Some((*knob, *original_value + change / fine_adjust_divisor))
+                    }
+                    (
+                        KnobDragMode::Circular,
+                        Some(DragBehavior::TurnKnob {
+                            knob,
+                            last_angle,
+                            unsnapped_value,
+                            ..
+                        }),
+                    ) => cursor_angle(self.layout, self.cursor_pos, &KNOBS[*knob]).and_then(
+                        |angle| {
+                            // Turning is measured from wherever the cursor first leaves the dead
+                            // zone, if the drag started inside it.
+                            let previous_angle = last_angle.replace(angle)?;
+                            // The angle wraps around at the bottom of the knob, which should be
+                            // treated as a small movement rather than a full turn the other way.
+                            let diff = (angle - previous_angle + 180.).rem_euclid(360.) - 180.;
+                            let change = diff / KNOB_ANGLE_RANGE;
+                            *unsnapped_value =
+                                (*unsnapped_value + change / fine_adjust_divisor).clamp(0., 1.);
+                            Some((*knob, *unsnapped_value))
+                        },
+                    ),
+                    (_, Some(DragBehavior::AdjustTrim { .. })) | (_, None) => None,
+                };
+                if let Some((knob, value)) = value {
+                    // Snapping only rounds the value that's applied, so the drag itself stays
+                    // continuous and releasing Ctrl picks up from the cursor's position.
+                    let value = if self.snap_held || self.snap_to_grid {
+                        snap_to_grid(value, KNOBS[knob].snap_step)
+                    } else {
+                        value
+                    };
+                    self.set_knob(knob, value.clamp(0., 1.), remote_state);
                 }
             }
             WindowEvent::MouseClick(button) => {
                 let (x, y) = self.cursor_pos;
-                if (x - KNOB_CENTER_X as isize).pow(2) + (y - KNOB_CENTER_Y as isize).pow(2)
-                    < KNOB_RADIUS.pow(2) as isize
+                let (image_x, image_y) = self.layout.to_image_coords((x as f64, y as f64));
+                // Clicking anywhere abandons any text entry in progress.
+                self.text_entry = None;
+                // While the context menu is open, any click closes it, and does nothing else
+                // unless it chooses an entry.
+                if let Some(menu) = self.context_menu.take() {
+                    let index = menu.item_index_at((image_x, image_y));
+                    if let (vst_window::MouseButton::Left, Some(index)) = (button, index) {
+                        self.choose_menu_item(menu.knob, MENU_ITEMS[index], remote_state);
+                    }
+                    return;
+                }
+                // Likewise, any click closes the about overlay, and does nothing else.
+                if self.about_open {
+                    self.about_open = false;
+                    return;
+                }
+                let within = |(left, right): (usize, usize), top: usize, bottom: usize| {
+                    (left as f64..right as f64).contains(&image_x)
+                        && (top as f64..bottom as f64).contains(&image_y)
+                };
+                let on_button = |span, top, bottom| {
+                    button == vst_window::MouseButton::Left && within(span, top, bottom)
+                };
+                let on_snapshot_button =
+                    |span| on_button(span, ORIG_SNAPSHOT_BUTTON_TOP, ORIG_SNAPSHOT_BUTTON_BOTTOM);
+                if on_button(
+                    (ORIG_READOUT_LEFT, ORIG_READOUT_RIGHT),
+                    ORIG_READOUT_TOP,
+                    ORIG_READOUT_BOTTOM,
+                ) {
+                    self.start_text_entry(AMPLITUDE_KNOB);
+                } else if on_button(
+                    (ORIG_THEME_TOGGLE_LEFT, ORIG_THEME_TOGGLE_RIGHT),
+                    ORIG_THEME_TOGGLE_TOP,
+                    ORIG_THEME_TOGGLE_BOTTOM,
+                ) {
+                    self.theme = (self.theme + 1) % THEMES.len();
+                    remote_state.set_theme(self.theme);
+                } else if on_button(
+                    (ORIG_ABOUT_BUTTON_LEFT, ORIG_ABOUT_BUTTON_RIGHT),
+                    ORIG_ABOUT_BUTTON_TOP,
+                    ORIG_ABOUT_BUTTON_BOTTOM,
+                ) {
+                    self.about_open = true;
+                } else if on_button(
+                    (ORIG_MUTE_BUTTON_LEFT, ORIG_MUTE_BUTTON_RIGHT),
+                    ORIG_MUTE_BUTTON_TOP,
+                    ORIG_MUTE_BUTTON_BOTTOM,
+                ) {
+                    self.muted = !self.muted;
+                    remote_state.set_muted(self.muted);
+                } else if within(
+                    (ORIG_TRIM_CONTROL_LEFT, ORIG_TRIM_CONTROL_RIGHT),
+                    ORIG_TRIM_CONTROL_TOP,
+                    ORIG_TRIM_CONTROL_BOTTOM,
+                ) {
+                    // The trim is dragged like a knob, and reset by right-clicking.
+                    match button {
+                        vst_window::MouseButton::Left => {
+                            self.drag_behavior = Some(DragBehavior::AdjustTrim {
+                                click_y: y,
+                                original_trim: self.output_trim,
+                            })
+                        }
+                        vst_window::MouseButton::Right => {
+                            self.output_trim = 0.;
+                            remote_state.set_output_trim(0.);
+                        }
+                        _ => (),
+                    }
+                } else if on_button(
+                    (ORIG_METER_LEFT, ORIG_METER_RIGHT),
+                    ORIG_CLIP_INDICATOR_TOP,
+                    ORIG_METER_BOTTOM,
+                ) {
+                    // Clicking the meters resets their holds.
+                    self.peak_holds = self.meter_levels;
+                    self.peak_hold_times = [0.; 2];
+                    self.clip_holds = [false; 2];
+                    self.dirty = true;
+                } else if on_snapshot_button(ORIG_SNAPSHOT_TOGGLE_SPAN) {
+                    self.active_snapshot = (self.active_snapshot + 1) % NUM_SNAPSHOTS;
+                    remote_state.recall_snapshot(self.active_snapshot);
+                } else if let Some(slot) = ORIG_SNAPSHOT_STORE_SPANS
+                    .iter()
+                    .position(|&span| on_snapshot_button(span))
+                {
+                    self.active_snapshot = slot;
+                    remote_state.store_snapshot(slot);
+                } else if let Some(knob) = KNOBS
+                    .iter()
+                    .position(|knob| knob.contains((image_x, image_y)))
                 {
+                    self.focused_knob = knob;
                     if button == vst_window::MouseButton::Left {
-                        self.drag_behavior = Some(DragBehavior::TurnAmplitudeKnob {
-                            click_y: y,
-                            original_value: self.amplitude_value,
-                        });
+                        let now = Instant::now();
+                        let is_double_click =
+                            self.last_knob_click.is_some_and(|(last_knob, last)| {
+                                last_knob == knob && now - last < DOUBLE_CLICK_INTERVAL
+                            });
+                        if is_double_click {
+                            // Don't let a third click count as another double-click.
+                            self.last_knob_click = None;
+                            self.set_knob_gesture(knob, KNOBS[knob].default, remote_state);
+                        } else {
+                            self.last_knob_click = Some((knob, now));
+                            remote_state.begin_parameter_edit(KNOBS[knob].parameter);
+                            let drag_start_value = self.knob_values[knob];
+                            let angle = cursor_angle(self.layout, (x, y), &KNOBS[knob]);
+                            // Clicks in the gap at the bottom of the knob, or too close to its
+                            // center, leave the value alone.
+                            let jump_value = angle.and_then(knob_value_at_angle);
+                            if let (true, Some(value)) = (self.jump_to_value, jump_value) {
+                                self.set_knob(knob, value, remote_state);
+                            }
+                            self.drag_behavior = Some(DragBehavior::TurnKnob {
+                                knob,
+                                click_y: y,
+                                original_value: self.knob_values[knob],
+                                drag_start_value,
+                                last_angle: angle,
+                                unsnapped_value: self.knob_values[knob],
+                            });
+                        }
                     } else if button == vst_window::MouseButton::Right {
-                        self.amplitude_value = 0.5;
-                        remote_state.set_amplitude_control(self.amplitude_value);
+                        self.context_menu = Some(ContextMenu::new(knob, (image_x, image_y)));
                     }
                 }
             }
             WindowEvent::MouseRelease(vst_window::MouseButton::Left) => {
-                drop(self.drag_behavior.take());
+                if let Some(DragBehavior::TurnKnob {
+                    knob,
+                    drag_start_value,
+                    ..
+                }) = self.drag_behavior.take()
+                {
+                    remote_state.end_parameter_edit(KNOBS[knob].parameter);
+                    if self.knob_values[knob] != drag_start_value {
+                        self.push_undo(knob, drag_start_value);
+                    }
+                }
             }
             _ => (),
         }
     }
+
+    /// Returns the index into `MENU_ITEMS` of the context menu entry under the cursor, if the
+    /// menu is open.
+    pub fn hovered_menu_item(&self) -> Option<usize> {
+        let (x, y) = self.cursor_pos;
+        let position = self.layout.to_image_coords((x as f64, y as f64));
+        self.context_menu?.item_index_at(position)
+    }
+
+    /// Carries out the action of the context menu entry `item` on the knob at index `knob`.
+    fn choose_menu_item<S: super::EditorRemoteState>(
+        &mut self,
+        knob: usize,
+        item: MenuItem,
+        remote_state: &S,
+    ) {
+        match item {
+            MenuItem::ResetToDefault => {
+                self.set_knob_gesture(knob, KNOBS[knob].default, remote_state)
+            }
+            MenuItem::CopyValue => self.copy_value(knob),
+            MenuItem::PasteValue => self.paste_value(knob, remote_state),
+            MenuItem::EnterValue => self.start_text_entry(knob),
+            MenuItem::ResetAll => self.reset_all(remote_state),
+            MenuItem::ToggleDragAcceleration => self.drag_curve = self.drag_curve.toggled(),
+        }
+    }
+
+    /// Copies the displayed value of the knob at index `knob`.
+    fn copy_value(&mut self, knob: usize) {
+        let text = (KNOBS[knob].format)(self.knob_values[knob]);
+        self.clipboard.copy(text);
+    }
+
+    /// Sets the knob at index `knob` to the copied value. Text that isn't valid for the knob is
+    /// ignored.
+    fn paste_value<S: super::EditorRemoteState>(&mut self, knob: usize, remote_state: &S) {
+        if let Some(value) = self
+            .clipboard
+            .paste()
+            .as_deref()
+            .and_then(KNOBS[knob].parse)
+        {
+            self.set_knob_gesture(knob, value, remote_state);
+        }
+    }
+
+    /// Starts typing a new value for the knob at index `knob` into the readout.
+    fn start_text_entry(&mut self, knob: usize) {
+        self.text_entry = Some(String::new());
+        self.text_entry_knob = knob;
+    }
+
+    /// Update the editor state in response to a key being pressed. Returns `true` if the key was
+    /// used.
+    pub fn react_to_key_down<S: super::EditorRemoteState>(
+        &mut self,
+        keycode: KeyCode,
+        remote_state: &S,
+    ) -> bool {
+        if self.text_entry.is_some() {
+            return self.react_to_text_entry_key(keycode, remote_state);
+        }
+        if let (Some(_), Key::Escape) = (self.context_menu, keycode.key) {
+            self.context_menu = None;
+            return true;
+        }
+        // Every shortcut in one place, so that they can be checked against those of hosts. Other
+        // Ctrl combinations, like Ctrl+A and Ctrl+S, are left for the host.
+        if keycode.modifier & ModifierKey::CONTROL.bits() != 0 {
+            match keycode.character.to_ascii_lowercase() {
+                'z' => return self.undo(remote_state),
+                'c' => {
+                    self.copy_value(self.focused_knob);
+                    return true;
+                }
+                'v' => {
+                    self.paste_value(self.focused_knob, remote_state);
+                    return true;
+                }
+                'r' => {
+                    self.knob_drag_mode = self.knob_drag_mode.toggled();
+                    return true;
+                }
+                'j' => {
+                    self.jump_to_value = !self.jump_to_value;
+                    return true;
+                }
+                'g' => {
+                    self.snap_to_grid = !self.snap_to_grid;
+                    return true;
+                }
+                _ => (),
+            }
+        }
+        let step = match keycode.key {
+            Key::Shift => {
+                self.set_fine_adjust(true);
+                return true;
+            }
+            Key::Control => {
+                self.snap_held = true;
+                return true;
+            }
+            Key::Up => KEY_NUDGE_STEP,
+            Key::Down => -KEY_NUDGE_STEP,
+            Key::PageUp => KEY_PAGE_STEP,
+            Key::PageDown => -KEY_PAGE_STEP,
+            _ => return false,
+        };
+        // Nudges are finer while Shift is held, like knob drags.
+        let step = if self.fine_adjust {
+            step / FINE_ADJUST_DIVISOR
+        } else {
+            step
+        };
+        let knob = self.focused_knob;
+        let value = (self.knob_values[knob] + step).clamp(0., 1.);
+        if value != self.knob_values[knob] {
+            self.set_knob_gesture(knob, value, remote_state);
+        }
+        true
+    }
+
+    /// Edit the readout text in response to a key being pressed. Typed digits, signs, and decimal
+    /// points are appended, Enter applies the new value to the knob being entered, and Escape
+    /// cancels. Returns `true` if the key was used.
+    fn react_to_text_entry_key<S: super::EditorRemoteState>(
+        &mut self,
+        keycode: KeyCode,
+        remote_state: &S,
+    ) -> bool {
+        let text = match &mut self.text_entry {
+            Some(text) => text,
+            None => return false,
+        };
+        // Printable keys are reported through `character` alone. Signs allow entry of decibels.
+        if keycode.character.is_ascii_digit() || ['.', '-', '+'].contains(&keycode.character) {
+            text.push(keycode.character);
+            return true;
+        }
+        match keycode.key {
+            Key::Back => {
+                text.pop();
+                true
+            }
+            Key::Return | Key::Enter => {
+                // Invalid text is discarded, leaving the knob unchanged.
+                let knob = self.text_entry_knob;
+                if let Some(value) = (KNOBS[knob].parse)(text) {
+                    self.set_knob_gesture(knob, value, remote_state);
+                }
+                self.text_entry = None;
+                true
+            }
+            Key::Escape => {
+                self.text_entry = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Update the editor state in response to a key being released. Returns `true` if the key was
+    /// used.
+    pub fn react_to_key_up(&mut self, keycode: KeyCode) -> bool {
+        match keycode.key {
+            Key::Shift => {
+                self.set_fine_adjust(false);
+                true
+            }
+            Key::Control => {
+                self.snap_held = false;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Enables or disables fine adjustment of knob drags. If a drag is in progress, it continues
+    /// from the current cursor position and value, so that the knob doesn't jump when the
+    /// sensitivity changes.
+    fn set_fine_adjust(&mut self, enabled: bool) {
+        if self.fine_adjust == enabled {
+            return;
+        }
+        self.fine_adjust = enabled;
+        match &mut self.drag_behavior {
+            Some(DragBehavior::TurnKnob {
+                knob,
+                click_y,
+                original_value,
+                ..
+            }) => {
+                *click_y = self.cursor_pos.1;
+                *original_value = self.knob_values[*knob];
+            }
+            Some(DragBehavior::AdjustTrim {
+                click_y,
+                original_trim,
+            }) => {
+                *click_y = self.cursor_pos.1;
+                *original_trim = self.output_trim;
+            }
+            None => (),
+        }
+    }
+}
+
+/// Rounds a knob `value` to the nearest multiple of `step`.
+fn snap_to_grid(value: f32, step: f32) -> f32 {
+    (value / step).round() * step
+}
+
+/// Returns the angle of the window pixel coordinate `cursor_pos` around the center of `knob`, in
+/// degrees clockwise from the top, or `None` if it's too close to the center to tell.
+fn cursor_angle(layout: Layout, cursor_pos: (isize, isize), knob: &Knob) -> Option<f32> {
+    let (x, y) = layout.to_image_coords((cursor_pos.0 as f64, cursor_pos.1 as f64));
+    let (dx, dy) = (x - knob.center.0 as f64, y - knob.center.1 as f64);
+    if dx.hypot(dy) < knob.radius as f64 * CIRCULAR_DRAG_DEAD_ZONE {
+        return None;
+    }
+    // Image coordinates increase downwards.
+    Some(dx.atan2(-dy).to_degrees() as f32)
+}
+
+/// Returns the value at which a knob's pointer faces `angle`, in degrees clockwise from the top,
+/// or `None` if the angle is in the gap at the bottom of the knob that no value points towards.
+fn knob_value_at_angle(angle: f32) -> Option<f32> {
+    // Pointer angles are measured counterclockwise.
+    let value = (KNOB_START_ANGLE + angle) / KNOB_ANGLE_RANGE;
+    (0. ..=1.).contains(&value).then_some(value)
+}
+
+#[cfg(test)]
+pub(in crate::editor) mod tests {
+    use super::*;
+    use crate::dsp::SCOPE_BATCH_LEN;
+    use crate::editor::interface::image_consts::ORIG_BG_SIZE_Y;
+    use crate::editor::EditorRemoteState;
+    use std::cell::RefCell;
+
+    /// Records the parameter values set by the editor, in place of the plugin state.
+    #[derive(Default)]
+    pub(in crate::editor) struct RecordingRemote {
+        pub parameters: RefCell<Vec<(usize, f32)>>,
+        /// Values passed to each `set_all_parameters` call.
+        pub restored: RefCell<Vec<Vec<f32>>>,
+        /// Number of `reset_all_parameters` calls.
+        pub resets: RefCell<usize>,
+    }
+
+    impl EditorRemoteState for RecordingRemote {
+        fn set_event_subscription(&self, _enabled: bool) {}
+        fn set_parameter_control(&self, index: usize, value: f32) {
+            self.parameters.borrow_mut().push((index, value));
+        }
+        fn begin_parameter_edit(&self, _index: usize) {}
+        fn end_parameter_edit(&self, _index: usize) {}
+        fn resize_editor_window(&self, _size: (u32, u32)) {}
+        fn window_size(&self) -> (u32, u32) {
+            (SIZE_X as u32, SIZE_Y as u32)
+        }
+        fn scale_factor(&self) -> f32 {
+            1.
+        }
+        fn set_theme(&self, _index: usize) {}
+        fn theme(&self) -> usize {
+            0
+        }
+        fn reset_all_parameters(&self) -> Vec<f32> {
+            *self.resets.borrow_mut() += 1;
+            vec![0.25; 3]
+        }
+        fn set_all_parameters(&self, values: &[f32]) {
+            self.restored.borrow_mut().push(values.to_vec());
+        }
+        fn store_snapshot(&self, _slot: usize) {}
+        fn recall_snapshot(&self, _slot: usize) {}
+        fn active_snapshot(&self) -> usize {
+            0
+        }
+        fn set_muted(&self, _muted: bool) {}
+        fn muted(&self) -> bool {
+            false
+        }
+        fn set_output_trim(&self, _db: f32) {}
+        fn output_trim(&self) -> f32 {
+            0.
+        }
+    }
+
+    fn ctrl_z() -> KeyCode {
+        KeyCode {
+            character: 'z',
+            // `Key` has no variant for printable keys, so use one that is otherwise ignored.
+            key: Key::Pause,
+            modifier: ModifierKey::CONTROL.bits(),
+        }
+    }
+
+    fn key(key: Key) -> KeyCode {
+        KeyCode {
+            character: '\0',
+            key,
+            modifier: 0,
+        }
+    }
+
+    #[test]
+    fn arrow_keys_nudge_focused_knob() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.5, 0, 0, false, 0.);
+        assert!(state.react_to_key_down(key(Key::Up), &remote));
+        assert!((state.knob_values[AMPLITUDE_KNOB] - (0.5 + KEY_NUDGE_STEP)).abs() < 1e-6);
+        assert!(state.react_to_key_down(key(Key::PageDown), &remote));
+        assert!((state.knob_values[AMPLITUDE_KNOB] - (0.4 + KEY_NUDGE_STEP)).abs() < 1e-6);
+        for _ in 0..10 {
+            state.react_to_key_down(key(Key::PageDown), &remote);
+        }
+        assert_eq!(state.knob_values[AMPLITUDE_KNOB], 0.);
+        assert_eq!(remote.parameters.borrow().last(), Some(&(AMPLITUDE, 0.)));
+
+        // Clicking another knob moves the focus to it.
+        let gain_knob = KNOBS
+            .iter()
+            .position(|knob| knob.parameter == MAKEUP_GAIN)
+            .unwrap();
+        let center = KNOBS[gain_knob].center;
+        move_cursor(&mut state, &remote, (center.0 as f64, center.1 as f64));
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Left),
+            &remote,
+        );
+        state.react_to_window_event(
+            WindowEvent::MouseRelease(vst_window::MouseButton::Left),
+            &remote,
+        );
+        for _ in 0..10 {
+            state.react_to_key_down(key(Key::PageUp), &remote);
+        }
+        assert_eq!(state.knob_values[gain_knob], 1.);
+        assert_eq!(state.knob_values[AMPLITUDE_KNOB], 0.);
+    }
+
+    #[test]
+    fn undo_reverts_gestures_in_order() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.5, 0, 0, false, 0.);
+        state.set_knob_gesture(AMPLITUDE_KNOB, 0.8, &remote);
+        state.set_knob_gesture(AMPLITUDE_KNOB, 0.2, &remote);
+
+        assert!(state.react_to_key_down(ctrl_z(), &remote));
+        assert_eq!(state.knob_values[AMPLITUDE_KNOB], 0.8);
+        assert!(state.react_to_key_down(ctrl_z(), &remote));
+        assert_eq!(state.knob_values[AMPLITUDE_KNOB], 0.5);
+        assert_eq!(remote.parameters.borrow().last(), Some(&(AMPLITUDE, 0.5)));
+
+        // Nothing is left to undo, so the key is left for the host.
+        assert!(!state.react_to_key_down(ctrl_z(), &remote));
+        assert_eq!(state.knob_values[AMPLITUDE_KNOB], 0.5);
+    }
+
+    #[test]
+    fn displayed_knobs_follow_external_changes() {
+        let mut state = InterfaceState::new(|_| 0., 0, 0, false, 0.);
+        let start = state.last_tick;
+        state.react_to_control_event(StateUpdate::SetKnob(1.));
+        assert_eq!(state.displayed_knob_values[AMPLITUDE_KNOB], 0.);
+
+        state.advance_time(start + Duration::from_millis(10));
+        let partway = state.displayed_knob_values[AMPLITUDE_KNOB];
+        assert!(partway > 0. && partway < 1.);
+
+        state.advance_time(start + Duration::from_secs(1));
+        assert!((state.displayed_knob_values[AMPLITUDE_KNOB] - 1.).abs() < 1e-3);
+        assert_eq!(state.knob_values[AMPLITUDE_KNOB], 1.);
+    }
+
+    #[test]
+    fn redraws_only_while_something_changes() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0., 0, 0, false, 0.);
+        let start = state.last_tick;
+        // The first frame is always drawn.
+        assert!(state.take_dirty());
+        assert!(!state.take_dirty());
+
+        move_cursor(&mut state, &remote, (10., 10.));
+        state.react_to_control_event(StateUpdate::MeterLevels([0.; 2]));
+        state.react_to_control_event(StateUpdate::ScopeSamples([0.; SCOPE_BATCH_LEN]));
+        state.advance_time(start + Duration::from_millis(10));
+        assert!(!state.take_dirty());
+
+        // Animations keep the interface dirty until they finish.
+        state.react_to_control_event(StateUpdate::SetKnob(1.));
+        state.react_to_control_event(StateUpdate::MeterLevels([1.; 2]));
+        assert!(state.take_dirty());
+        state.advance_time(start + Duration::from_millis(20));
+        assert!(state.take_dirty());
+        state.advance_time(start + Duration::from_secs(10));
+        state.advance_time(start + Duration::from_secs(11));
+        assert!(state.take_dirty());
+        state.advance_time(start + Duration::from_secs(12));
+        assert!(!state.take_dirty());
+        assert_eq!(state.displayed_knob_values[AMPLITUDE_KNOB], 1.);
+    }
+
+    #[test]
+    fn preset_crossfade_moves_knobs_linearly() {
+        let mut state = InterfaceState::new(|_| 0., 0, 0, false, 0.);
+        let start = state.last_tick;
+        state.react_to_control_event(StateUpdate::Crossfade(vec![
+            StateUpdate::SetPresetCrossfade(1000.),
+            StateUpdate::SetKnob(1.),
+        ]));
+        // Without a crossfade time beforehand, the knob follows its usual smoothing.
+        state.advance_time(start + Duration::from_secs(1));
+        assert_eq!(state.displayed_knob_values[AMPLITUDE_KNOB], 1.);
+
+        state.react_to_control_event(StateUpdate::Crossfade(vec![StateUpdate::SetKnob(0.)]));
+        state.advance_time(start + Duration::from_millis(1250));
+        assert!((state.displayed_knob_values[AMPLITUDE_KNOB] - 0.75).abs() < 1e-4);
+        state.advance_time(start + Duration::from_millis(1500));
+        assert!((state.displayed_knob_values[AMPLITUDE_KNOB] - 0.5).abs() < 1e-4);
+        state.advance_time(start + Duration::from_secs(3));
+        assert_eq!(state.displayed_knob_values[AMPLITUDE_KNOB], 0.);
+        assert!(state.knob_crossfade.is_none());
+    }
+
+    #[test]
+    fn input_clip_indicator_latches() {
+        let mut state = InterfaceState::new(|_| 0., 0, 0, false, 0.);
+        let start = state.last_tick;
+        state.react_to_control_event(StateUpdate::InputClipping(true));
+        assert!(state.input_clip_indicator);
+
+        // The indicator stays on for as long as the input keeps clipping.
+        state.advance_time(start + Duration::from_secs(5));
+        assert!(state.input_clip_indicator);
+
+        state.react_to_control_event(StateUpdate::InputClipping(false));
+        state.advance_time(start + Duration::from_millis(5900));
+        assert!(state.input_clip_indicator);
+        state.take_dirty();
+        state.advance_time(start + Duration::from_millis(6100));
+        assert!(!state.input_clip_indicator);
+        assert!(state.take_dirty());
+    }
+
+    #[test]
+    fn meter_holds_peaks_and_clips() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0., 0, 0, false, 0.);
+        let start = state.last_tick;
+        state.react_to_control_event(StateUpdate::MeterLevels([0.5, 1.]));
+        state.react_to_control_event(StateUpdate::MeterLevels([0.25, 0.25]));
+        assert_eq!(state.peak_holds, [0.5, 1.]);
+        assert_eq!(state.clip_holds, [false, true]);
+
+        // Peaks are held while the meters fall, and then decay too.
+        state.advance_time(start + Duration::from_millis(1900));
+        assert_eq!(state.peak_holds, [0.5, 1.]);
+        assert!(state.meter_levels[0] < 0.5);
+        state.advance_time(start + Duration::from_millis(2100));
+        state.advance_time(start + Duration::from_millis(2600));
+        assert!(state.peak_holds[0] < 0.5);
+
+        // Clips stay latched until the meters are clicked.
+        state.advance_time(start + Duration::from_secs(60));
+        assert_eq!(state.peak_holds, [0.; 2]);
+        assert_eq!(state.clip_holds, [false, true]);
+        move_cursor(
+            &mut state,
+            &remote,
+            (
+                ORIG_METER_LEFT as f64 + 5.,
+                ORIG_CLIP_INDICATOR_TOP as f64 + 5.,
+            ),
+        );
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Left),
+            &remote,
+        );
+        assert_eq!(state.clip_holds, [false; 2]);
+    }
+
+    #[test]
+    fn external_changes_are_highlighted() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0., 0, 0, false, 0.);
+        let start = state.last_tick;
+        state.react_to_control_event(StateUpdate::SetKnob(1.));
+        assert_eq!(state.automation_highlights[AMPLITUDE_KNOB], 1.);
+
+        state.advance_time(start + Duration::from_millis(100));
+        let partway = state.automation_highlights[AMPLITUDE_KNOB];
+        assert!(partway > 0. && partway < 1.);
+        state.advance_time(start + Duration::from_millis(300));
+        assert_eq!(state.automation_highlights[AMPLITUDE_KNOB], 0.);
+
+        // Neither changes from within the editor nor repeats of the same value are highlighted.
+        state.set_knob_gesture(AMPLITUDE_KNOB, 0.5, &remote);
+        state.react_to_control_event(StateUpdate::SetKnob(0.5));
+        assert_eq!(state.automation_highlights[AMPLITUDE_KNOB], 0.);
+    }
+
+    /// Moves the cursor to a pixel coordinate of the original image assets.
+    fn move_cursor<S: EditorRemoteState>(
+        state: &mut InterfaceState,
+        remote: &S,
+        image: (f64, f64),
+    ) {
+        let (x, y) = state.layout.to_window_coords(image);
+        let (window_x, window_y) = state.layout.window_size;
+        state.react_to_window_event(
+            WindowEvent::CursorMovement(x as f32 / window_x as f32, y as f32 / window_y as f32),
+            remote,
+        );
+    }
+
+    #[test]
+    fn vertical_drag_only_starts_on_knob() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.5, 0, 0, false, 0.);
+        let knob = KNOBS[AMPLITUDE_KNOB];
+        let (center_x, center_y) = (knob.center.0 as f64, knob.center.1 as f64);
+        // Far enough that a drag would move the knob through its whole range.
+        let drag = ORIG_BG_SIZE_Y as f64 / KNOB_CHANGE_SPEED as f64;
+
+        // Just outside the knob, up and to the left of its center.
+        let outside = center_x - knob.radius as f64 - 1.;
+        move_cursor(&mut state, &remote, (outside, center_y - 1.));
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Left),
+            &remote,
+        );
+        assert!(state.drag_behavior.is_none());
+        move_cursor(&mut state, &remote, (outside, center_y - drag));
+        assert_eq!(state.knob_values[AMPLITUDE_KNOB], 0.5);
+        state.react_to_window_event(
+            WindowEvent::MouseRelease(vst_window::MouseButton::Left),
+            &remote,
+        );
+
+        // Dragging up from inside the knob increases its value in proportion to the distance.
+        move_cursor(&mut state, &remote, (center_x - 1., center_y - 1.));
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Left),
+            &remote,
+        );
+        assert!(state.drag_behavior.is_some());
+        move_cursor(
+            &mut state,
+            &remote,
+            (center_x - 1., center_y - 1. - drag / 4.),
+        );
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.75).abs() < 0.01);
+        move_cursor(
+            &mut state,
+            &remote,
+            (center_x - 1., center_y - 1. + drag / 4.),
+        );
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.25).abs() < 0.01);
+        state.react_to_window_event(
+            WindowEvent::MouseRelease(vst_window::MouseButton::Left),
+            &remote,
+        );
+    }
+
+    #[test]
+    fn held_ctrl_snaps_drags_to_grid() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.5, 0, 0, false, 0.);
+        let knob = KNOBS[AMPLITUDE_KNOB];
+        let (center_x, center_y) = (knob.center.0 as f64, knob.center.1 as f64);
+        // Far enough to turn the knob by 0.12, between grid steps.
+        let drag = ORIG_BG_SIZE_Y as f64 / KNOB_CHANGE_SPEED as f64 * 0.12;
+
+        move_cursor(&mut state, &remote, (center_x, center_y));
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Left),
+            &remote,
+        );
+        assert!(state.react_to_key_down(key(Key::Control), &remote));
+        move_cursor(&mut state, &remote, (center_x, center_y - drag));
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.6).abs() < 1e-6);
+
+        // Releasing Ctrl returns to continuous control, from the cursor's position.
+        assert!(state.react_to_key_up(key(Key::Control)));
+        move_cursor(&mut state, &remote, (center_x, center_y - drag - 1.));
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.62).abs() < 0.01);
+        state.react_to_window_event(
+            WindowEvent::MouseRelease(vst_window::MouseButton::Left),
+            &remote,
+        );
+
+        // While snapping is toggled on, small circular movements still add up. The next click
+        // shouldn't count as a double-click.
+        state.knob_drag_mode = KnobDragMode::Circular;
+        state.last_knob_click = None;
+        let ctrl_g = KeyCode {
+            character: 'g',
+            key: Key::Pause,
+            modifier: ModifierKey::CONTROL.bits(),
+        };
+        assert!(state.react_to_key_down(ctrl_g, &remote));
+        assert!(state.snap_to_grid);
+        let radius = knob.radius as f64 / 2.;
+        let at_angle = |degrees: f64| {
+            let radians = degrees.to_radians();
+            (
+                center_x + radius * radians.sin(),
+                center_y - radius * radians.cos(),
+            )
+        };
+        move_cursor(&mut state, &remote, at_angle(0.));
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Left),
+            &remote,
+        );
+        for degrees in 1..=30 {
+            move_cursor(&mut state, &remote, at_angle(degrees as f64));
+        }
+        // 30 degrees is a tenth of the knob's range, turning it from 0.62 to the step nearest
+        // 0.72.
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn accelerated_drags_are_finer_then_coarser() {
+        let crossover = DragCurve::Accelerated.apply(ACCELERATION_CROSSOVER);
+        assert!((crossover - ACCELERATION_CROSSOVER).abs() < 1e-6);
+        for &distance in &[0.01, 0.1, 0.2] {
+            assert!(DragCurve::Accelerated.apply(distance).abs() < distance);
+            assert!(DragCurve::Accelerated.apply(-distance) < 0.);
+        }
+        assert!(DragCurve::Accelerated.apply(0.5) > 0.5);
+        assert_eq!(DragCurve::Linear.apply(0.1), 0.1);
+
+        // Toggled from the context menu, and applied to vertical drags. Ctrl+A is left for the
+        // host, where it usually selects everything.
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.5, 0, 0, false, 0.);
+        let ctrl_a = KeyCode {
+            character: 'a',
+            key: Key::Pause,
+            modifier: ModifierKey::CONTROL.bits(),
+        };
+        assert!(!state.react_to_key_down(ctrl_a, &remote));
+        assert_eq!(state.drag_curve, DragCurve::Linear);
+        let (center_x, center_y) = KNOBS[AMPLITUDE_KNOB].center;
+        let (center_x, center_y) = (center_x as f64, center_y as f64);
+        move_cursor(&mut state, &remote, (center_x, center_y));
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Right),
+            &remote,
+        );
+        let index = MENU_ITEMS
+            .iter()
+            .position(|&item| item == MenuItem::ToggleDragAcceleration)
+            .unwrap();
+        let (left, top, right, bottom) = state.context_menu.unwrap().item_bounds(index);
+        move_cursor(
+            &mut state,
+            &remote,
+            ((left + right) / 2., (top + bottom) / 2.),
+        );
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Left),
+            &remote,
+        );
+        assert_eq!(state.drag_curve, DragCurve::Accelerated);
+        move_cursor(&mut state, &remote, (center_x, center_y));
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Left),
+            &remote,
+        );
+        // A tenth of the interface's height.
+        move_cursor(&mut state, &remote, (center_x, center_y - 80.));
+        let expected = 0.5 + DragCurve::Accelerated.apply(0.1) * KNOB_CHANGE_SPEED;
+        // Allow for rounding of the cursor position to whole pixels.
+        assert!((state.knob_values[AMPLITUDE_KNOB] - expected).abs() < 0.005);
+    }
+
+    #[test]
+    fn cursor_outside_window_is_ignored() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.5, 0, 0, false, 0.);
+        // Hosts can report positions beyond the top left of the window while the cursor is
+        // captured.
+        state.react_to_window_event(WindowEvent::CursorMovement(-0.5, -2.), &remote);
+        for button in [
+            vst_window::MouseButton::Left,
+            vst_window::MouseButton::Right,
+        ] {
+            state.react_to_window_event(WindowEvent::MouseClick(button.clone()), &remote);
+            state.react_to_window_event(WindowEvent::MouseRelease(button), &remote);
+        }
+        assert!(state.drag_behavior.is_none());
+        assert_eq!(state.knob_values, [0.5; NUM_KNOBS]);
+    }
+
+    #[test]
+    fn circular_drag_follows_cursor_angle() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.2, 0, 0, false, 0.);
+        state.knob_drag_mode = KnobDragMode::Circular;
+        let knob = KNOBS[AMPLITUDE_KNOB];
+        let (center_x, center_y) = (knob.center.0 as f64, knob.center.1 as f64);
+        let offset = knob.radius as f64 / 2.;
+
+        // Start on the right of the knob, and turn clockwise a quarter turn to the bottom, then
+        // across the bottom to the left.
+        move_cursor(&mut state, &remote, (center_x + offset, center_y));
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Left),
+            &remote,
+        );
+        move_cursor(&mut state, &remote, (center_x, center_y + offset));
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.5).abs() < 0.01);
+        move_cursor(&mut state, &remote, (center_x - offset, center_y));
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.8).abs() < 0.01);
+
+        // Movement near the center is ignored.
+        move_cursor(&mut state, &remote, (center_x, center_y));
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.8).abs() < 0.01);
+    }
+
+    #[test]
+    fn circular_drag_from_center_starts_where_cursor_leaves_it() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.2, 0, 0, false, 0.);
+        state.knob_drag_mode = KnobDragMode::Circular;
+        let knob = KNOBS[AMPLITUDE_KNOB];
+        let (center_x, center_y) = (knob.center.0 as f64, knob.center.1 as f64);
+        let offset = knob.radius as f64 / 2.;
+
+        move_cursor(&mut state, &remote, (center_x, center_y));
+        state.react_to_window_event(
+            WindowEvent::MouseClick(vst_window::MouseButton::Left),
+            &remote,
+        );
+        // Leaving the center on the left doesn't count as a turn from the top.
+        move_cursor(&mut state, &remote, (center_x - offset, center_y));
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.2).abs() < 0.01);
+        move_cursor(&mut state, &remote, (center_x, center_y - offset));
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn click_jumps_to_value() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.2, 0, 0, false, 0.);
+        state.jump_to_value = true;
+        let knob = KNOBS[AMPLITUDE_KNOB];
+        let (center_x, center_y) = (knob.center.0 as f64, knob.center.1 as f64);
+        let offset = knob.radius as f64 / 2.;
+        let click = |state: &mut InterfaceState| {
+            state.react_to_window_event(
+                WindowEvent::MouseClick(vst_window::MouseButton::Left),
+                &remote,
+            );
+            state.react_to_window_event(
+                WindowEvent::MouseRelease(vst_window::MouseButton::Left),
+                &remote,
+            );
+        };
+
+        // The top of the knob is halfway through its range, and the right is 90 degrees further.
+        move_cursor(&mut state, &remote, (center_x, center_y - offset));
+        click(&mut state);
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.5).abs() < 0.01);
+        move_cursor(&mut state, &remote, (center_x + offset, center_y));
+        state.last_knob_click = None;
+        click(&mut state);
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.8).abs() < 0.01);
+
+        // Clicking the bottom of the knob leaves it alone.
+        move_cursor(&mut state, &remote, (center_x, center_y + offset));
+        state.last_knob_click = None;
+        click(&mut state);
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.8).abs() < 0.01);
+
+        // Each jump can be undone.
+        assert!(state.react_to_key_down(ctrl_z(), &remote));
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn context_menu_acts_on_knob() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.25, 0, 0, false, 0.);
+        // The shared clipboard could be changed by other tests running at the same time.
+        state.clipboard = Box::new(super::super::clipboard::LocalClipboard::default());
+        let gain_knob = KNOBS
+            .iter()
+            .position(|knob| knob.parameter == MAKEUP_GAIN)
+            .unwrap();
+        let click_at = |state: &mut InterfaceState, position, button| {
+            move_cursor(state, &remote, position);
+            state.react_to_window_event(WindowEvent::MouseClick(button), &remote);
+        };
+        let choose = |state: &mut InterfaceState, knob: usize, item| {
+            let center = KNOBS[knob].center;
+            let center = (center.0 as f64, center.1 as f64);
+            click_at(state, center, vst_window::MouseButton::Right);
+            let index = MENU_ITEMS.iter().position(|&entry| entry == item).unwrap();
+            let (left, top, right, bottom) = state.context_menu.unwrap().item_bounds(index);
+            click_at(
+                state,
+                ((left + right) / 2., (top + bottom) / 2.),
+                vst_window::MouseButton::Left,
+            );
+            assert_eq!(state.context_menu, None);
+        };
+
+        choose(&mut state, AMPLITUDE_KNOB, MenuItem::CopyValue);
+        choose(&mut state, AMPLITUDE_KNOB, MenuItem::ResetToDefault);
+        assert_eq!(
+            state.knob_values[AMPLITUDE_KNOB],
+            KNOBS[AMPLITUDE_KNOB].default
+        );
+        choose(&mut state, AMPLITUDE_KNOB, MenuItem::PasteValue);
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.25).abs() < 1e-3);
+
+        choose(&mut state, gain_knob, MenuItem::EnterValue);
+        for key in "12".chars() {
+            let keycode = KeyCode {
+                character: key,
+                key: Key::Pause,
+                modifier: 0,
+            };
+            assert!(state.react_to_key_down(keycode, &remote));
+        }
+        assert!(state.react_to_key_down(key(Key::Return), &remote));
+        assert!((state.knob_values[gain_knob] - 0.5).abs() < 1e-6);
+
+        // Clicking outside of the menu closes it without doing anything else.
+        let center = KNOBS[AMPLITUDE_KNOB].center;
+        click_at(
+            &mut state,
+            (center.0 as f64, center.1 as f64),
+            vst_window::MouseButton::Right,
+        );
+        click_at(&mut state, (1., 1.), vst_window::MouseButton::Right);
+        assert_eq!(state.context_menu, None);
+        assert!((state.knob_values[AMPLITUDE_KNOB] - 0.25).abs() < 1e-3);
+
+        // The focused knob's value can also be copied and pasted with Ctrl+C and Ctrl+V.
+        let ctrl = |character| KeyCode {
+            character,
+            key: Key::Pause,
+            modifier: ModifierKey::CONTROL.bits(),
+        };
+        let center = KNOBS[gain_knob].center;
+        click_at(
+            &mut state,
+            (center.0 as f64, center.1 as f64),
+            vst_window::MouseButton::Left,
+        );
+        state.react_to_window_event(
+            WindowEvent::MouseRelease(vst_window::MouseButton::Left),
+            &remote,
+        );
+        let copied = state.knob_values[gain_knob];
+        assert!(state.react_to_key_down(ctrl('c'), &remote));
+        assert!(state.react_to_key_down(key(Key::PageUp), &remote));
+        assert!(state.react_to_key_down(ctrl('v'), &remote));
+        assert!((state.knob_values[gain_knob] - copied).abs() < 1e-3);
+        state.clipboard.copy("garbage".to_string());
+        assert!(state.react_to_key_down(ctrl('v'), &remote));
+        assert!((state.knob_values[gain_knob] - copied).abs() < 1e-3);
+    }
+
+    #[test]
+    fn about_overlay_closes_on_any_click() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.5, 0, 0, false, 0.);
+        let click_at = |state: &mut InterfaceState, position| {
+            move_cursor(state, &remote, position);
+            state.react_to_window_event(
+                WindowEvent::MouseClick(vst_window::MouseButton::Left),
+                &remote,
+            );
+        };
+        let about_button = (
+            (ORIG_ABOUT_BUTTON_LEFT + ORIG_ABOUT_BUTTON_RIGHT) as f64 / 2.,
+            (ORIG_ABOUT_BUTTON_TOP + ORIG_ABOUT_BUTTON_BOTTOM) as f64 / 2.,
+        );
+        click_at(&mut state, about_button);
+        assert!(state.about_open);
+
+        // Clicking a knob only closes the overlay, without starting a drag.
+        let center = KNOBS[AMPLITUDE_KNOB].center;
+        click_at(&mut state, (center.0 as f64, center.1 as f64));
+        assert!(!state.about_open);
+        assert!(state.drag_behavior.is_none());
+
+        click_at(&mut state, about_button);
+        click_at(&mut state, about_button);
+        assert!(!state.about_open);
+    }
+
+    #[test]
+    fn reset_all_is_chosen_from_menu_and_undoable() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0.5, 0, 0, false, 0.);
+        let click_at = |state: &mut InterfaceState, position, button| {
+            move_cursor(state, &remote, position);
+            state.react_to_window_event(WindowEvent::MouseClick(button), &remote);
+        };
+
+        // Right-clicking away from the knobs does nothing.
+        click_at(&mut state, (1., 1.), vst_window::MouseButton::Right);
+        assert_eq!(*remote.resets.borrow(), 0);
+        assert_eq!(state.context_menu, None);
+
+        let center = KNOBS[AMPLITUDE_KNOB].center;
+        click_at(
+            &mut state,
+            (center.0 as f64, center.1 as f64),
+            vst_window::MouseButton::Right,
+        );
+        let index = MENU_ITEMS
+            .iter()
+            .position(|&item| item == MenuItem::ResetAll)
+            .unwrap();
+        let (left, top, right, bottom) = state.context_menu.unwrap().item_bounds(index);
+        click_at(
+            &mut state,
+            ((left + right) / 2., (top + bottom) / 2.),
+            vst_window::MouseButton::Left,
+        );
+        assert_eq!(*remote.resets.borrow(), 1);
+
+        assert!(state.react_to_key_down(ctrl_z(), &remote));
+        assert_eq!(*remote.restored.borrow(), vec![vec![0.25; 3]]);
+    }
+
+    #[test]
+    fn undo_stack_is_limited() {
+        let remote = RecordingRemote::default();
+        let mut state = InterfaceState::new(|_| 0., 0, 0, false, 0.);
+        for step in 1..=UNDO_STACK_LIMIT + 8 {
+            state.set_knob_gesture(AMPLITUDE_KNOB, step as f32 / 100., &remote);
+        }
+        for _ in 0..UNDO_STACK_LIMIT + 8 {
+            state.react_to_key_down(ctrl_z(), &remote);
+        }
+        // The oldest changes were forgotten.
+        assert_eq!(state.knob_values[AMPLITUDE_KNOB], 0.08);
+    }
 }
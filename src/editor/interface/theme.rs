@@ -0,0 +1,194 @@
+//! Images, fonts, and colors used to draw the editor interface.
+//!
+//! Default assets are embedded into the plugin at compile time, but any of them can be replaced by
+//! a file loaded at runtime. If a replacement file is missing or can't be decoded, the embedded
+//! default is used instead, so a broken theme can never prevent the editor from opening.
+
+use std::path::PathBuf;
+
+use wgpu_glyph::ab_glyph::FontArc;
+
+const BACKGROUND_IMAGE: &[u8] = include_bytes!("../../../assets/images/bg.png");
+const POINTER_IMAGE: &[u8] = include_bytes!("../../../assets/images/pointer.png");
+const KNOB_IMAGE: &[u8] = include_bytes!("../../../assets/images/knob.png");
+const FONT: &[u8] = include_bytes!("../../../assets/fonts/iosevka-Iosevka-medium.ttf");
+
+/// Setting this environment variable to the path of a TrueType or OpenType font file replaces the
+/// embedded font.
+const FONT_VAR: &str = "AMPLIFE_FONT";
+
+/// Colors used to draw the editor interface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Theme {
+    /// Color of the value readout text and the theme toggle.
+    pub font_color: [f32; 4],
+    /// Color of any part of the window not covered by the interface.
+    pub clear_color: wgpu::Color,
+    /// Color multiplied with the background image, which is left unchanged by white.
+    pub background_tint: [f32; 4],
+    /// Color multiplied with the image of each knob's pointer, which is left unchanged by white.
+    pub pointer_tint: [f32; 4],
+}
+
+/// Tint that leaves an image unchanged.
+pub(super) const WHITE_TINT: [f32; 4] = [1.; 4];
+
+/// Every selectable theme. Clicking the theme toggle cycles through these in order.
+pub(super) const THEMES: [Theme; 2] = [
+    // Dark
+    Theme {
+        font_color: [1.0, 0.51, 0.0, 1.0],
+        clear_color: wgpu::Color::BLACK,
+        background_tint: WHITE_TINT,
+        pointer_tint: WHITE_TINT,
+    },
+    // Light
+    Theme {
+        font_color: [0.55, 0.85, 1.0, 1.0],
+        clear_color: wgpu::Color {
+            r: 0.85,
+            g: 0.85,
+            b: 0.85,
+            a: 1.,
+        },
+        background_tint: WHITE_TINT,
+        pointer_tint: WHITE_TINT,
+    },
+];
+
+/// Optional paths to files that replace the embedded assets. PNG images may have any dimensions;
+/// they are scaled to fit the area of the interface they are drawn in.
+#[derive(Clone, Debug, Default)]
+pub(super) struct ThemeAssets {
+    /// Image covering the entire interface.
+    pub background: Option<PathBuf>,
+    /// Image of a knob's pointer, rotated over the knob's body.
+    pub pointer: Option<PathBuf>,
+    /// Image of a knob's body, for knobs that aren't part of the background.
+    pub knob: Option<PathBuf>,
+    /// TrueType or OpenType font used for the value readout and button labels.
+    pub font: Option<FontSource>,
+}
+
+/// Where to load a replacement font from.
+#[derive(Clone, Debug)]
+pub(super) enum FontSource {
+    /// A font file, read when the editor is opened.
+    Path(PathBuf),
+    /// The contents of a font file, for forks that get their font some other way, like from their
+    /// own embedded assets.
+    // Not currently constructed by the plugin itself, which only loads fonts from files.
+    #[allow(dead_code)]
+    Data(Vec<u8>),
+}
+
+/// Decoded RGBA image data.
+pub(super) struct Image {
+    /// Pixel data, in rows of 4 bytes per pixel.
+    pub data: Vec<u8>,
+    /// Pixel dimensions of the image.
+    pub size: (u32, u32),
+}
+
+impl ThemeAssets {
+    /// Replacement assets specified by environment variables, which currently only include the
+    /// font set by `AMPLIFE_FONT`.
+    pub fn from_environment() -> Self {
+        Self {
+            font: std::env::var_os(FONT_VAR).map(|path| FontSource::Path(path.into())),
+            ..Default::default()
+        }
+    }
+
+    pub fn background(&self) -> Image {
+        load_image(&self.background, BACKGROUND_IMAGE)
+    }
+
+    pub fn pointer(&self) -> Image {
+        load_image(&self.pointer, POINTER_IMAGE)
+    }
+
+    pub fn knob(&self) -> Image {
+        load_image(&self.knob, KNOB_IMAGE)
+    }
+
+    pub fn font(&self) -> FontArc {
+        let font = self.font.as_ref().and_then(|source| {
+            let data = match source {
+                FontSource::Path(path) => std::fs::read(path).ok()?,
+                FontSource::Data(data) => data.clone(),
+            };
+            FontArc::try_from_vec(data).ok()
+        });
+        if let (Some(source), None) = (&self.font, &font) {
+            log::warn!("Could not load font from {:?}, using the default", source);
+        }
+        font.unwrap_or_else(|| FontArc::try_from_slice(FONT).unwrap())
+    }
+}
+
+/// Loads the PNG image at `path`, falling back to the `embedded` PNG image if there is no path or
+/// the file can't be loaded.
+fn load_image(path: &Option<PathBuf>, embedded: &[u8]) -> Image {
+    path.as_ref()
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|data| decode_png(&data))
+        .unwrap_or_else(|| decode_png(embedded).unwrap())
+}
+
+/// Decodes a PNG image of any 8 or 16 bit color type into RGBA data.
+pub(super) fn decode_png(png_image: &[u8]) -> Option<Image> {
+    let mut decoder = png::Decoder::new(png_image);
+    // Expand palettes and low bit depths to 8 bits per sample.
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info().ok()?;
+    let mut image_data = vec![0; info.buffer_size()];
+    reader.next_frame(&mut image_data).ok()?;
+
+    let data = match info.color_type {
+        png::ColorType::RGBA => image_data,
+        png::ColorType::RGB => image_data
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => image_data
+            .chunks_exact(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        png::ColorType::Grayscale => image_data.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        png::ColorType::Indexed => return None,
+    };
+    Some(Image {
+        data,
+        size: (info.width, info.height),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu_glyph::ab_glyph::Font;
+
+    #[test]
+    fn invalid_font_falls_back_to_embedded() {
+        let embedded = ThemeAssets::default().font();
+        for source in [
+            FontSource::Data(b"not a font".to_vec()),
+            FontSource::Path("/nonexistent/font.ttf".into()),
+        ] {
+            let theme = ThemeAssets {
+                font: Some(source),
+                ..Default::default()
+            };
+            let font = theme.font();
+            assert_eq!(font.glyph_count(), embedded.glyph_count());
+            assert_ne!(font.glyph_id('A').0, 0);
+        }
+
+        let replaced = ThemeAssets {
+            font: Some(FontSource::Data(FONT.to_vec())),
+            ..Default::default()
+        };
+        assert_eq!(replaced.font().glyph_count(), embedded.glyph_count());
+    }
+}
@@ -6,59 +6,159 @@
 //! graphically, instructs the overall plugin state to update in response to window input events,
 //! and handles notifications of state updates that occur on the processing thread.
 
-use std::sync::{mpsc::Receiver, Arc};
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{mpsc::Receiver, Arc, Mutex, PoisonError};
 
-use vst::editor::Editor;
+use vst::api::ModifierKey;
+use vst::editor::{Editor, KeyCode};
 use vst::plugin::PluginParameters;
 use vst_window::setup;
 
 use crate::plugin_state::{PluginState, StateUpdate};
 
 mod interface;
-use interface::{EditorInterface, InterfaceState, SIZE_X, SIZE_Y};
+use interface::{
+    is_within_zoom_range, zoomed_size, EditorInterface, InterfaceState, SIZE_X, SIZE_Y,
+};
+
+/// Pixel dimensions of the editor window, until resized.
+pub(super) const DEFAULT_WINDOW_SIZE: (u32, u32) = (SIZE_X as u32, SIZE_Y as u32);
+
+/// Returns `size` if the editor window can be zoomed to it, or `DEFAULT_WINDOW_SIZE` otherwise,
+/// like for a size restored from a corrupted preset.
+pub(super) fn restorable_window_size(size: (u32, u32)) -> (u32, u32) {
+    if is_within_zoom_range(size) {
+        size
+    } else {
+        DEFAULT_WINDOW_SIZE
+    }
+}
+
+/// Converts a `size` in logical pixels into physical display pixels, for a display with the given
+/// `scale_factor`.
+pub(super) fn physical_size(size: (u32, u32), scale_factor: f32) -> (u32, u32) {
+    (
+        (size.0 as f32 * scale_factor).round() as u32,
+        (size.1 as f32 * scale_factor).round() as u32,
+    )
+}
+
+/// Returns the message passed to `panic!`, if it was given one.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
 
 /// Persistent VST-compatible wrapper that opens and closes an `EditorInterface`.
 pub(super) struct PluginEditor {
     opened_interface: Option<EditorInterface>,
+    /// Host window that the interface was last opened within.
+    parent: Option<*mut core::ffi::c_void>,
     remote_state: Arc<PluginState>,
-    incoming: Receiver<StateUpdate>,
+    /// Shared with any other `PluginEditor` created for the same plugin instance.
+    incoming: Arc<Mutex<Receiver<StateUpdate>>>,
+    /// Set once the editor has panicked, after which it can no longer be opened.
+    disabled: bool,
 }
 
 impl PluginEditor {
-    pub fn new(remote_state: Arc<PluginState>, incoming: Receiver<StateUpdate>) -> Self {
+    pub fn new(
+        remote_state: Arc<PluginState>,
+        incoming: Arc<Mutex<Receiver<StateUpdate>>>,
+    ) -> Self {
         Self {
             opened_interface: None,
+            parent: None,
             remote_state,
             incoming,
+            disabled: false,
+        }
+    }
+
+    /// Runs `f`, catching any panic rather than letting it unwind into the host, which would
+    /// usually crash the entire DAW. A panic closes and disables the editor, since its state may
+    /// no longer be consistent, and `None` is returned. The host then falls back to its own generic
+    /// interface the next time the editor is opened. `doing` describes `f` for the logged message.
+    ///
+    /// The audio processing thread doesn't share any state with the editor that a panic could
+    /// leave inconsistent, so it's unaffected.
+    fn catch_panic<T>(&mut self, doing: &str, f: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        match catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(result) => Some(result),
+            Err(payload) => {
+                log::error!(
+                    "the editor panicked while {}, and has been disabled: {}",
+                    doing,
+                    panic_message(&*payload)
+                );
+                self.disabled = true;
+                self.remote_state.set_event_subscription(false);
+                // Dropping a broken interface could panic again.
+                let opened_interface = self.opened_interface.take();
+                let _ = catch_unwind(AssertUnwindSafe(|| drop(opened_interface)));
+                None
+            }
+        }
+    }
+
+    /// Changes the size of the editor window, asking the host to resize its own window to match.
+    ///
+    /// `vst_window` doesn't report resizes performed by the host, so resizing is driven from the
+    /// editor, using the Ctrl+= and Ctrl+- keyboard shortcuts. It also can't resize the window it
+    /// created within the host's window, so an open interface is moved to a new window of the new
+    /// size, keeping its state.
+    fn resize(&mut self, size: (u32, u32)) {
+        self.remote_state.resize_editor_window(size);
+        if let (Some(opened_interface), Some(parent)) = (self.opened_interface.take(), self.parent)
+        {
+            // The old window is destroyed along with the rest of the old interface.
+            let state = opened_interface.into_state();
+            if !self.attach_interface(parent, state) {
+                self.remote_state.set_event_subscription(false);
+            }
         }
     }
 }
 
 /// `PluginEditor` responds directly to VST API calls specific to the UI thread.
 impl Editor for PluginEditor {
+    /// Hosts expect the editor size in physical display pixels.
     fn size(&self) -> (i32, i32) {
-        (SIZE_X as i32, SIZE_Y as i32)
+        let (width, height) = physical_size(
+            self.remote_state.window_size(),
+            self.remote_state.scale_factor(),
+        );
+        (width as i32, height as i32)
     }
 
     fn position(&self) -> (i32, i32) {
         (0, 0)
     }
 
+    /// Opens the editor interface within `parent`. If the interface can't be shown, `false` is
+    /// returned so that the host can fall back to its own generic parameter interface.
     fn open(&mut self, parent: *mut core::ffi::c_void) -> bool {
-        if self.opened_interface.is_none() {
-            let (window, event_source) = setup(parent, (SIZE_X as i32, SIZE_Y as i32));
-            (*self.remote_state).set_event_subscription(true);
-            let initial_state = InterfaceState::new(self.remote_state.get_parameter(0));
-            self.opened_interface = Some(EditorInterface::new(window, event_source, initial_state));
-            true
-        } else {
-            false
+        if self.disabled {
+            return false;
         }
+        self.catch_panic("opening", |editor| editor.open_interface(parent))
+            .unwrap_or(false)
     }
 
     fn close(&mut self) {
-        self.remote_state.set_event_subscription(false);
-        drop(self.opened_interface.take());
+        self.catch_panic("closing", |editor| {
+            editor.remote_state.set_event_subscription(false);
+            editor.parent = None;
+            if editor.opened_interface.take().is_some() {
+                log::info!("closed the editor");
+            }
+        });
     }
 
     fn is_open(&mut self) -> bool {
@@ -66,8 +166,116 @@ impl Editor for PluginEditor {
     }
 
     fn idle(&mut self) {
+        self.catch_panic("drawing", Self::run_interface);
+    }
+
+    fn key_down(&mut self, keycode: KeyCode) -> bool {
+        self.catch_panic("handling a key press", |editor| {
+            editor.react_to_key_down(keycode)
+        })
+        .unwrap_or(false)
+    }
+
+    fn key_up(&mut self, keycode: KeyCode) -> bool {
+        self.catch_panic("handling a key release", |editor| {
+            match &mut editor.opened_interface {
+                Some(opened_interface) => opened_interface.key_up(keycode),
+                None => false,
+            }
+        })
+        .unwrap_or(false)
+    }
+}
+
+/// The bodies of the `Editor` methods, which are run through `catch_panic`.
+impl PluginEditor {
+    fn open_interface(&mut self, parent: *mut core::ffi::c_void) -> bool {
+        if self.opened_interface.is_none() {
+            (*self.remote_state).set_event_subscription(true);
+            let initial_state = InterfaceState::new(
+                |index| self.remote_state.get_parameter(index as i32),
+                self.remote_state.theme(),
+                self.remote_state.active_snapshot(),
+                self.remote_state.muted(),
+                self.remote_state.output_trim(),
+            );
+            if self.attach_interface(parent, initial_state) {
+                log::info!("opened the editor");
+                true
+            } else {
+                self.remote_state.set_event_subscription(false);
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    /// Creates a window of the current size within `parent`, and sets up an interface in it
+    /// starting from `initial_state`. Returns `false` if the interface can't be shown.
+    fn attach_interface(
+        &mut self,
+        parent: *mut core::ffi::c_void,
+        initial_state: InterfaceState,
+    ) -> bool {
+        let (window, event_source) = setup(parent, self.size());
+        self.parent = Some(parent);
+        match EditorInterface::new(
+            window,
+            event_source,
+            self.remote_state.window_size(),
+            self.remote_state.scale_factor(),
+            initial_state,
+            self.remote_state.dsp_report_reader(),
+        ) {
+            Ok(interface) => {
+                self.opened_interface = Some(interface);
+                true
+            }
+            Err(e) => {
+                log::error!("unable to open the editor: {}", e);
+                false
+            }
+        }
+    }
+
+    fn run_interface(&mut self) {
+        // Latency changes are recorded by the audio processing thread, but hosts expect to be told
+        // about them from this thread.
+        self.remote_state.report_latency_change();
+        // The host may report a new scale factor while the editor is open, like when the window is
+        // moved to a different display.
+        let scale_factor = self.remote_state.scale_factor();
+        if let Some(opened_interface) = &self.opened_interface {
+            if opened_interface.scale_factor() != scale_factor {
+                self.resize(self.remote_state.window_size());
+            }
+        }
         if let Some(opened_interface) = &mut self.opened_interface {
-            opened_interface.run_tasks(&*self.remote_state, &mut self.incoming);
+            // A panic while the receiver was locked leaves it poisoned, but the receiver itself is
+            // still usable by a new editor.
+            let mut incoming = self.incoming.lock().unwrap_or_else(PoisonError::into_inner);
+            opened_interface.run_tasks(&*self.remote_state, &mut incoming);
+        }
+    }
+
+    fn react_to_key_down(&mut self, keycode: KeyCode) -> bool {
+        if keycode.modifier & ModifierKey::CONTROL.bits() != 0 {
+            let zoom_in = match keycode.character {
+                '=' | '+' => Some(true),
+                '-' => Some(false),
+                _ => None,
+            };
+            if let Some(zoom_in) = zoom_in {
+                if let Some(size) = zoomed_size(self.remote_state.window_size(), zoom_in) {
+                    self.resize(size);
+                }
+                return true;
+            }
+        }
+        match &mut self.opened_interface {
+            Some(opened_interface) => opened_interface.key_down(keycode, &*self.remote_state),
+            None => false,
         }
     }
 }
@@ -85,7 +293,86 @@ pub(super) trait EditorRemoteState {
     /// While the event subscription is enabled, state update events will be sent over the
     /// `control_send` channel.
     fn set_event_subscription(&self, enabled: bool);
-    /// Sets the position of the amplitude control to a new fraction of its full range between 0
-    /// and 1.
-    fn set_amplitude_control(&self, value: f32);
+    /// Sets the position of the control for the parameter at `index` to a new fraction of its
+    /// full range between 0 and 1.
+    fn set_parameter_control(&self, index: usize, value: f32);
+    /// Notifies the host that the user has started a gesture that changes the parameter at
+    /// `index`, like a knob drag. Every change until the matching `end_parameter_edit` call can
+    /// then be grouped into a single automation write or undo step.
+    fn begin_parameter_edit(&self, index: usize);
+    /// Notifies the host that a gesture started by `begin_parameter_edit` has finished.
+    fn end_parameter_edit(&self, index: usize);
+    /// Records a new pixel size for the editor window, and asks the host to resize its window to
+    /// fit.
+    fn resize_editor_window(&self, size: (u32, u32));
+    /// Pixel size of the editor window, as last recorded or restored from a preset. This is
+    /// measured in logical pixels, which may be scaled by `scale_factor` on high-resolution
+    /// displays.
+    fn window_size(&self) -> (u32, u32);
+    /// Number of physical display pixels per logical pixel of the editor window.
+    fn scale_factor(&self) -> f32;
+    /// Records the index of the editor's selected color theme.
+    fn set_theme(&self, index: usize);
+    /// Index of the editor's selected color theme, as last recorded or restored from a preset.
+    fn theme(&self) -> usize;
+    /// Resets every parameter to its default value, returning the previous value of each so that
+    /// the reset can be undone. The editor is notified of the new values through the usual
+    /// `StateUpdate` messages.
+    fn reset_all_parameters(&self) -> Vec<f32>;
+    /// Sets every parameter to the corresponding entry of `values`, as returned by
+    /// `reset_all_parameters`. The editor is notified of the new values through the usual
+    /// `StateUpdate` messages.
+    fn set_all_parameters(&self, values: &[f32]);
+    /// Stores the current value of every parameter as the snapshot at `slot`, for A/B
+    /// comparison.
+    fn store_snapshot(&self, slot: usize);
+    /// Restores every parameter from the snapshot at `slot`. The editor is notified of the new
+    /// values through the usual `StateUpdate` messages.
+    fn recall_snapshot(&self, slot: usize);
+    /// Index of the snapshot most recently stored or recalled.
+    fn active_snapshot(&self) -> usize;
+    /// Silences or restores the output, without changing any parameter.
+    fn set_muted(&self, muted: bool);
+    /// Whether the output is currently muted.
+    fn muted(&self) -> bool;
+    /// Sets the gain applied to the output after all other processing, in decibels.
+    fn set_output_trim(&self, db: f32);
+    /// Current output trim, in decibels.
+    fn output_trim(&self) -> f32;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use vst::plugin::HostCallback;
+
+    #[test]
+    fn panic_disables_editor() {
+        let (to_dsp, _dsp_recv) = channel();
+        let (to_editor, editor_recv) = channel();
+        let state = Arc::new(PluginState::new(
+            HostCallback::default(),
+            crate::parameters::plugin_parameters(),
+            to_dsp.clone(),
+            to_dsp,
+            to_editor,
+        ));
+        let mut editor = PluginEditor::new(state, Arc::new(Mutex::new(editor_recv)));
+
+        assert_eq!(editor.catch_panic("testing", |_| 1), Some(1));
+        assert_eq!(
+            editor.catch_panic("testing", |_| -> usize { panic!("test panic") }),
+            None
+        );
+        assert!(!editor.open(std::ptr::null_mut()));
+        assert!(!editor.is_open());
+    }
+
+    #[test]
+    fn panic_messages() {
+        assert_eq!(panic_message(&"static"), "static");
+        assert_eq!(panic_message(&String::from("formatted")), "formatted");
+        assert_eq!(panic_message(&0), "unknown panic");
+    }
 }
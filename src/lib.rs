@@ -7,11 +7,15 @@
 //! ampli-Fe's code is well-documented - feel free to use it as a starting point for your next VST2
 //! plugin in Rust.
 
-use std::sync::{mpsc::channel, Arc};
+use std::sync::{
+    mpsc::{channel, Receiver},
+    Arc, Mutex,
+};
 
 use vst::{
-    api::Supported,
+    api::{Events, Supported},
     buffer::AudioBuffer,
+    channels::ChannelInfo,
     editor::Editor,
     plugin::{CanDo, HostCallback, Info, Plugin, PluginParameters},
 };
@@ -22,8 +26,22 @@ use dsp::PluginDsp;
 mod editor;
 use editor::PluginEditor;
 
+mod logging;
+
+mod metadata;
+use metadata::PluginMetadata;
+
+mod parameters;
+
 mod plugin_state;
-use plugin_state::PluginState;
+use plugin_state::{PluginState, StateUpdate};
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+/// Number of input and output channels processed by the plugin, selected at build time. Stereo
+/// width and panning only apply to the default stereo configuration.
+const NUM_CHANNELS: usize = if cfg!(feature = "mono") { 1 } else { 2 };
 
 /// Top level wrapper that exposes a full `vst::Plugin` implementation.
 struct AmpliFeVst {
@@ -36,88 +54,237 @@ struct AmpliFeVst {
     /// processing thread and the UI thread, and updated using thread-safe interior mutability.
     state_handle: Arc<PluginState>,
 
-    /// The `PluginEditor` implements the plugin's custom editor interface. It's temporarily stored
-    /// here until being moved to the UI thread by the first `get_editor` method call.
-    editor_placeholder: Option<PluginEditor>,
+    /// Receives updates for the plugin's custom editor interface, implemented by `PluginEditor`.
+    /// A new `PluginEditor` is moved to the UI thread by each `get_editor` method call, and they
+    /// all share this receiver.
+    editor_updates: Arc<Mutex<Receiver<StateUpdate>>>,
+
+    /// Identifying information reported to the host.
+    metadata: PluginMetadata,
 }
 
 impl AmpliFeVst {
-    /// Initializes the VST plugin, along with an optional `HostCallback` handle.
-    fn new_maybe_host(maybe_host: Option<HostCallback>) -> Self {
+    /// Initializes the VST plugin described by `metadata`, along with an optional `HostCallback`
+    /// handle.
+    fn new_maybe_host(maybe_host: Option<HostCallback>, metadata: PluginMetadata) -> Self {
         let host = maybe_host.unwrap_or_default();
 
         let (to_editor, editor_recv) = channel();
         let (to_dsp, dsp_recv) = channel();
+        let (priority_to_dsp, priority_dsp_recv) = channel();
 
-        let state_handle = Arc::new(PluginState::new(host, to_dsp, to_editor));
+        let state_handle = Arc::new(PluginState::new(
+            host,
+            parameters::plugin_parameters(),
+            to_dsp,
+            priority_to_dsp,
+            to_editor,
+        ));
 
-        let editor_placeholder = Some(PluginEditor::new(Arc::clone(&state_handle), editor_recv));
+        let editor_updates = Arc::new(Mutex::new(editor_recv));
 
-        let dsp = PluginDsp::new(dsp_recv);
+        let dsp = PluginDsp::new(
+            dsp_recv,
+            priority_dsp_recv,
+            state_handle.dsp_knob_slot(),
+            state_handle.editor_notifier(),
+            state_handle.retired_batch_sender(),
+        );
 
         Self {
             dsp,
             state_handle,
-            editor_placeholder,
+            editor_updates,
+            metadata,
         }
     }
+
+    /// Records the current processing latency after a block has been processed, which can change
+    /// with the oversampling factor. It's reported to the host later, from the UI thread.
+    fn record_latency(&self) {
+        self.state_handle.set_latency(self.dsp.latency_samples());
+    }
 }
 
 /// `vst::plugin_main` requires a `Default` implementation.
 impl Default for AmpliFeVst {
     fn default() -> Self {
-        Self::new_maybe_host(None)
+        Self::new_maybe_host(None, metadata::plugin_metadata())
     }
 }
 
 /// Main `vst` plugin implementation.
 impl Plugin for AmpliFeVst {
     fn new(host: HostCallback) -> Self {
-        Self::new_maybe_host(Some(host))
+        logging::init();
+        let metadata = metadata::plugin_metadata();
+        log::info!(
+            "creating {} {} instance",
+            metadata.name,
+            env!("CARGO_PKG_VERSION")
+        );
+        Self::new_maybe_host(Some(host), metadata)
     }
 
     fn get_info(&self) -> Info {
-        /// Use a hash of a string describing this plugin to avoid unique ID conflicts.
-        const UNIQUE_ID_SEED: &str = "ampli-Fe Amplitude Effect VST2 Plugin";
-        static UNIQUE_ID: once_cell::sync::Lazy<i32> = once_cell::sync::Lazy::new(|| {
-            use std::collections::hash_map::DefaultHasher;
-            use std::hash::{Hash, Hasher};
-
-            let mut s = DefaultHasher::new();
-            UNIQUE_ID_SEED.hash(&mut s);
-            s.finish() as i32
-        });
-
         Info {
-            name: "ampli-Fe".to_string(),
-            vendor: "antonok".to_string(),
-            unique_id: *UNIQUE_ID,
-            inputs: 2,
-            outputs: 2,
-            parameters: 1,
-            initial_delay: 0,
+            name: self.metadata.name.clone(),
+            vendor: self.metadata.vendor.clone(),
+            unique_id: self.metadata.unique_id,
+            inputs: self.metadata.num_channels as i32,
+            outputs: (self.metadata.num_channels + dsp::ENVELOPE_OUTPUTS) as i32,
+            category: self.metadata.category,
+            midi_inputs: 1,
+            parameters: self.state_handle.num_parameters() as i32,
+            initial_delay: self.dsp.latency_samples() as i32,
             preset_chunks: true,
+            f64_precision: true,
             ..Info::default()
         }
     }
 
+    /// Outputs after those for audio carry the envelope of the input, which hosts generally list
+    /// by the name given here.
+    fn get_output_info(&self, output: i32) -> ChannelInfo {
+        if output >= self.metadata.num_channels as i32 {
+            ChannelInfo::new("Envelope".to_string(), Some("Env".to_string()), true, None)
+        } else {
+            ChannelInfo::new(
+                format!("Output channel {}", output),
+                Some(format!("Out {}", output)),
+                true,
+                None,
+            )
+        }
+    }
+
+    fn set_sample_rate(&mut self, rate: f32) {
+        self.dsp.set_sample_rate(rate);
+    }
+
+    /// Hosts report the largest block they will process before processing starts, which gives the
+    /// DSP a chance to allocate any memory it needs up front.
+    fn set_block_size(&mut self, size: i64) {
+        self.dsp.set_block_size(size.max(0) as usize);
+    }
+
+    /// The output fades in whenever processing resumes, to avoid a click. This is called from the
+    /// UI thread, so any latency change from the last time processing ran is reported here.
+    fn resume(&mut self) {
+        self.dsp.resume();
+        self.state_handle.report_latency_change();
+    }
+
     fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
         self.dsp.process(buffer);
+        self.record_latency();
     }
 
-    fn can_do(&self, _can_do: CanDo) -> Supported {
-        Supported::Maybe
+    fn process_f64(&mut self, buffer: &mut AudioBuffer<f64>) {
+        self.dsp.process_f64(buffer);
+        self.record_latency();
+    }
+
+    fn get_tail_size(&self) -> isize {
+        // A tail size of 0 asks the host to use its default, so 1 is reported when there is no
+        // tail.
+        match self.dsp.tail_samples() {
+            0 => 1,
+            samples => samples as isize,
+        }
+    }
+
+    fn process_events(&mut self, events: &Events) {
+        if let Some(value) = self.dsp.process_events(events) {
+            self.state_handle
+                .set_parameter_from_controller(parameters::AMPLITUDE, value);
+        }
+    }
+
+    /// Some hosts report the display scale factor of the editor through a vendor-specific call,
+    /// with an `index` of "PreS" and a `value` of "AeCs", passing the scale factor as `opt`.
+    fn vendor_specific(
+        &mut self,
+        index: i32,
+        value: isize,
+        _ptr: *mut core::ffi::c_void,
+        opt: f32,
+    ) -> isize {
+        const PRES: i32 = i32::from_be_bytes(*b"PreS");
+        const AECS: isize = i32::from_be_bytes(*b"AeCs") as isize;
+        if index == PRES && value == AECS {
+            self.state_handle.set_scale_factor(opt);
+            1
+        } else {
+            0
+        }
+    }
+
+    fn can_do(&self, can_do: CanDo) -> Supported {
+        match can_do {
+            // MIDI notes are used to gate the audio, and a MIDI controller sets the amplitude.
+            CanDo::ReceiveEvents | CanDo::ReceiveMidiEvent => Supported::Yes,
+            // No events are sent to the host, only note and control change messages are understood,
+            // and there is no custom bypass processing, offline processing, or use of the host's
+            // transport.
+            CanDo::SendEvents
+            | CanDo::SendMidiEvent
+            | CanDo::ReceiveTimeInfo
+            | CanDo::Offline
+            | CanDo::MidiProgramNames
+            | CanDo::Bypass
+            | CanDo::ReceiveSysExEvent
+            | CanDo::MidiSingleNoteTuningChange
+            | CanDo::MidiKeyBasedInstrumentControl => Supported::No,
+            // Unrecognized capabilities are left for the host to decide.
+            CanDo::Other(_) => Supported::Maybe,
+        }
     }
 
     fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
         Arc::clone(&self.state_handle) as Arc<dyn PluginParameters>
     }
 
+    /// The `vst` crate only calls this once per plugin instance, but the editor can be opened and
+    /// closed any number of times through the returned `Editor`. A new editor is returned on every
+    /// call anyway, in case the plugin is wrapped by anything that calls it again.
     fn get_editor(&mut self) -> Option<Box<dyn Editor>> {
-        self.editor_placeholder
-            .take()
-            .map(|editor| Box::new(editor) as Box<dyn Editor>)
+        Some(Box::new(PluginEditor::new(
+            Arc::clone(&self.state_handle),
+            Arc::clone(&self.editor_updates),
+        )))
     }
 }
 
 vst::plugin_main!(AmpliFeVst);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editor_is_available_after_first_call() {
+        let mut plugin = AmpliFeVst::default();
+        assert!(plugin.get_editor().is_some());
+        assert!(plugin.get_editor().is_some());
+    }
+
+    #[test]
+    fn oversampling_latency_is_reported_when_changed() {
+        let mut plugin = AmpliFeVst::default();
+        plugin
+            .state_handle
+            .set_parameter(parameters::OVERSAMPLING as i32, 1.);
+        // The host is told before any audio is processed with the new factor.
+        let reported = plugin.state_handle.reported_latency();
+        assert!(reported > 0);
+
+        plugin
+            .dsp
+            .process_slices(&[&[0.; 64]], &mut [&mut [0.; 64][..]]);
+        plugin.record_latency();
+        assert_eq!(plugin.dsp.latency_samples(), reported);
+        plugin.resume();
+        assert_eq!(plugin.state_handle.reported_latency(), reported);
+    }
+}
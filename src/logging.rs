@@ -0,0 +1,114 @@
+//! DAWs don't show a plugin's standard output or error streams, so messages are logged to a file
+//! instead, through the `log` crate's macros.
+//!
+//! The log is written to `ampli-Fe.log` in the system's temporary directory. Only messages at the
+//! `info` level or above are logged by default, since more detailed messages, like those for each
+//! parameter change, can be logged from the audio processing thread where writing to a file could
+//! cause dropouts. Setting the `AMPLIFE_LOG` environment variable to a level like `debug` or
+//! `trace` logs more, and `off` disables logging entirely.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, Once, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Environment variable that sets the most detailed level of message that is logged.
+const LOG_LEVEL_VAR: &str = "AMPLIFE_LOG";
+/// Name of the log file, within the system's temporary directory.
+const LOG_FILE_NAME: &str = "ampli-Fe.log";
+
+/// Appends each logged message to a file, as a single line.
+struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        // There's nowhere left to report a failure to log.
+        let _ = writeln!(
+            file,
+            "[{}.{:03}] {} {}: {}",
+            timestamp.as_secs(),
+            timestamp.subsec_millis(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {
+        let _ = self
+            .file
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .flush();
+    }
+}
+
+/// Path of the log file.
+pub fn log_path() -> PathBuf {
+    std::env::temp_dir().join(LOG_FILE_NAME)
+}
+
+/// Starts logging to the file at `log_path`. This only has an effect the first time it's called,
+/// since every instance of the plugin loaded by a host shares the same logger.
+pub fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let level = level_filter(std::env::var(LOG_LEVEL_VAR).ok().as_deref());
+        if level == LevelFilter::Off {
+            return;
+        }
+        let file = match OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path())
+        {
+            Ok(file) => file,
+            Err(_) => return,
+        };
+        // The logger must live for the rest of the program, so it's leaked deliberately.
+        let logger = Box::leak(Box::new(FileLogger {
+            file: Mutex::new(file),
+        }));
+        // Another logger may already have been set, like by a test harness.
+        if log::set_logger(logger).is_ok() {
+            log::set_max_level(level);
+        }
+    });
+}
+
+/// Parses the value of the `AMPLIFE_LOG` environment variable, falling back to `Info` if it's
+/// unset or invalid.
+fn level_filter(value: Option<&str>) -> LevelFilter {
+    value
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(LevelFilter::Info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_defaults_to_info() {
+        assert_eq!(level_filter(None), LevelFilter::Info);
+        assert_eq!(level_filter(Some("nonsense")), LevelFilter::Info);
+        assert_eq!(level_filter(Some("debug")), LevelFilter::Debug);
+        assert_eq!(level_filter(Some(" OFF ")), LevelFilter::Off);
+    }
+}
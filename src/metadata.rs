@@ -0,0 +1,98 @@
+//! Describes the plugin to the host DAW.
+//!
+//! Everything that identifies the plugin, rather than affecting what it does, is collected in the
+//! `PluginMetadata` returned by `plugin_metadata`. Forks of ampli-Fe can be renamed by changing
+//! that function alone.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use vst::plugin::Category;
+
+/// Returns the metadata that ampli-Fe reports to the host.
+pub fn plugin_metadata() -> PluginMetadata {
+    PluginMetadata::new("ampli-Fe")
+        .vendor("antonok")
+        .unique_id_seed("ampli-Fe Amplitude Effect VST2 Plugin")
+        .channels(crate::NUM_CHANNELS)
+        .category(Category::Effect)
+}
+
+/// Identifying information about a plugin, built up from a name with chained method calls.
+#[derive(Clone, Debug)]
+pub struct PluginMetadata {
+    /// Name of the plugin, as displayed by the host.
+    pub name: String,
+    /// Name of the plugin's developer, as displayed by the host.
+    pub vendor: String,
+    /// Identifies the plugin to hosts, which use it to tell plugins apart when reloading projects.
+    pub unique_id: i32,
+    /// Number of input channels, and of output channels.
+    pub num_channels: usize,
+    /// Kind of plugin, which hosts use to organize their plugin browsers.
+    pub category: Category,
+}
+
+impl PluginMetadata {
+    /// Creates metadata for a stereo effect plugin called `name`, whose unique ID is derived from
+    /// its name.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            vendor: String::new(),
+            unique_id: hash_unique_id(name),
+            num_channels: 2,
+            category: Category::Effect,
+        }
+    }
+
+    pub fn vendor(mut self, vendor: &str) -> Self {
+        self.vendor = vendor.to_string();
+        self
+    }
+
+    /// Derives the unique ID from a hash of `seed`, which should be a string describing the
+    /// plugin. Changing the seed of a released plugin will prevent hosts from recognizing it in
+    /// existing projects.
+    pub fn unique_id_seed(mut self, seed: &str) -> Self {
+        self.unique_id = hash_unique_id(seed);
+        self
+    }
+
+    pub fn channels(mut self, num_channels: usize) -> Self {
+        self.num_channels = num_channels;
+        self
+    }
+
+    /// Sets the kind of plugin. Forks that generate their own sound should use
+    /// `Category::Synth`.
+    pub fn category(mut self, category: Category) -> Self {
+        self.category = category;
+        self
+    }
+}
+
+/// Uses a hash of a string describing a plugin to avoid unique ID conflicts.
+fn hash_unique_id(seed: &str) -> i32 {
+    let mut s = DefaultHasher::new();
+    seed.hash(&mut s);
+    s.finish() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_id_only_depends_on_seed() {
+        let metadata = PluginMetadata::new("Fork").vendor("someone else");
+        assert_eq!(metadata.unique_id, hash_unique_id("Fork"));
+
+        let renamed = PluginMetadata::new("Renamed Fork").unique_id_seed("Fork");
+        assert_eq!(renamed.unique_id, metadata.unique_id);
+        assert_ne!(
+            plugin_metadata().unique_id,
+            PluginMetadata::new("ampli-Fe").unique_id
+        );
+    }
+}
@@ -0,0 +1,638 @@
+//! Describes each of the plugin's host-automatable parameters.
+//!
+//! The VST API identifies parameters by index and represents every value as a float from 0 to 1.
+//! The table returned by `plugin_parameters` attaches everything else the plugin needs to know
+//! about each index - how to name, display, and parse it, and which `StateUpdate` notifies the
+//! rest of the plugin when it changes. Adding a new parameter should only require a new entry
+//! here, along with handling of its `StateUpdate` variant.
+
+use crate::dsp::{
+    SaturationMode, DEFAULT_CONTROLLER, DEFAULT_ENVELOPE_RELEASE_MS, DEFAULT_SMOOTHING_TIME_MS,
+    MAX_MAKEUP_GAIN_DB, MAX_OVERSAMPLING,
+};
+use crate::plugin_state::StateUpdate;
+
+/// Index of the amplitude parameter.
+pub const AMPLITUDE: usize = 0;
+/// Default value of the amplitude parameter, corresponding to unity gain.
+pub const AMPLITUDE_DEFAULT: f32 = 0.5;
+/// Whether the amplitude is displayed and entered in decibels, rather than as a multiplier.
+const AMPLITUDE_IN_DB: bool = cfg!(feature = "decibel-display");
+/// Index of the make-up gain parameter.
+pub const MAKEUP_GAIN: usize = 6;
+/// Default value of the make-up gain parameter, corresponding to no additional gain.
+pub const MAKEUP_GAIN_DEFAULT: f32 = 0.;
+/// Index of the oversampling parameter.
+pub const OVERSAMPLING: usize = 7;
+
+/// Lowest target loudness that can be chosen for auto-gain, in decibels. The highest is 0dB.
+const MIN_AUTO_GAIN_TARGET_DB: f32 = -40.;
+/// Default target loudness for auto-gain, in decibels.
+const DEFAULT_AUTO_GAIN_TARGET_DB: f32 = -18.;
+
+/// Static description of a single parameter.
+pub struct ParamDescriptor {
+    /// Full name of the parameter, as displayed by the host.
+    pub name: &'static str,
+    /// Units of the parameter's displayed value.
+    pub label: &'static str,
+    /// Value of the parameter in a freshly instantiated plugin, from 0 to 1.
+    pub default: f32,
+    /// Converts a value from 0 to 1 into text for display.
+    pub format: fn(f32) -> String,
+    /// Converts user-entered text into a value from 0 to 1, if valid.
+    pub parse: fn(&str) -> Option<f32>,
+    /// Label of each value that the parameter can take, in order, if it's stepped rather than
+    /// continuous. The range from 0 to 1 is divided evenly between the steps.
+    pub steps: Option<&'static [&'static str]>,
+    /// Creates the message used to notify other parts of the plugin of a new value.
+    pub to_update: fn(f32) -> StateUpdate,
+}
+
+impl ParamDescriptor {
+    /// Rounds `value` to the nearest value that the parameter can take. Continuous parameters are
+    /// left unchanged.
+    pub fn quantize(&self, value: f32) -> f32 {
+        match self.steps {
+            Some(steps) => step_value(step_index(value, steps.len()), steps.len()),
+            None => value,
+        }
+    }
+}
+
+/// Returns descriptors for all of the plugin's parameters, in index order.
+pub fn plugin_parameters() -> Vec<ParamDescriptor> {
+    vec![
+        ParamDescriptor {
+            name: "Amplitude",
+            label: if AMPLITUDE_IN_DB { "dB" } else { "x" },
+            default: AMPLITUDE_DEFAULT,
+            format: format_amplitude,
+            parse: parse_amplitude,
+            steps: None,
+            to_update: StateUpdate::SetKnob,
+        },
+        ParamDescriptor {
+            name: "Width",
+            label: "x",
+            default: 0.5,
+            format: format_multiplier,
+            parse: parse_multiplier,
+            steps: None,
+            to_update: StateUpdate::SetWidth,
+        },
+        ParamDescriptor {
+            name: "Mix",
+            label: "%",
+            default: 1.,
+            format: format_percentage,
+            parse: parse_percentage,
+            steps: None,
+            to_update: StateUpdate::SetMix,
+        },
+        ParamDescriptor {
+            name: "Saturation",
+            label: "",
+            default: 0.,
+            format: format_saturation,
+            parse: parse_saturation,
+            steps: Some(&SATURATION_STEPS),
+            to_update: |value| StateUpdate::SetSaturation(saturation_mode(value)),
+        },
+        ParamDescriptor {
+            name: "Pan",
+            label: "",
+            default: 0.5,
+            format: format_pan,
+            parse: parse_pan,
+            steps: None,
+            to_update: StateUpdate::SetPan,
+        },
+        ParamDescriptor {
+            name: "Glide",
+            label: "ms",
+            default: DEFAULT_SMOOTHING_TIME_MS / MAX_GLIDE_TIME_MS,
+            format: format_glide_time,
+            parse: parse_glide_time,
+            steps: None,
+            to_update: |value| StateUpdate::SetGlideTime(value * MAX_GLIDE_TIME_MS),
+        },
+        ParamDescriptor {
+            name: "Make-up Gain",
+            label: "dB",
+            default: MAKEUP_GAIN_DEFAULT,
+            format: format_makeup_gain,
+            parse: parse_makeup_gain,
+            steps: None,
+            to_update: StateUpdate::SetMakeupGain,
+        },
+        ParamDescriptor {
+            name: "Oversampling",
+            label: "",
+            default: 0.,
+            format: format_oversampling,
+            parse: parse_oversampling,
+            steps: Some(&OVERSAMPLING_STEPS),
+            to_update: |value| StateUpdate::SetOversampling(oversampling_factor(value)),
+        },
+        ParamDescriptor {
+            name: "Left Gain",
+            label: "x",
+            default: 0.5,
+            format: format_multiplier,
+            parse: parse_multiplier,
+            steps: None,
+            to_update: |value| StateUpdate::SetChannelGain(0, value),
+        },
+        // Has no effect in mono builds, but is kept so that presets stay compatible.
+        ParamDescriptor {
+            name: "Right Gain",
+            label: "x",
+            default: 0.5,
+            format: format_multiplier,
+            parse: parse_multiplier,
+            steps: None,
+            to_update: |value| StateUpdate::SetChannelGain(1, value),
+        },
+        ParamDescriptor {
+            name: "Auto-Gain",
+            label: "",
+            default: 0.,
+            format: format_switch,
+            parse: parse_switch,
+            steps: Some(&SWITCH_STEPS),
+            to_update: |value| StateUpdate::SetAutoGain(value >= 0.5),
+        },
+        ParamDescriptor {
+            name: "Auto-Gain Target",
+            label: "dB",
+            default: 1. - DEFAULT_AUTO_GAIN_TARGET_DB / MIN_AUTO_GAIN_TARGET_DB,
+            format: format_auto_gain_target,
+            parse: parse_auto_gain_target,
+            steps: None,
+            to_update: |value| StateUpdate::SetAutoGainTarget(auto_gain_target_db(value)),
+        },
+        ParamDescriptor {
+            name: "Amplitude CC",
+            label: "",
+            default: DEFAULT_CONTROLLER as f32 / 127.,
+            format: |value| controller_number(value).to_string(),
+            parse: parse_controller_number,
+            steps: None,
+            to_update: |value| StateUpdate::SetAmplitudeController(controller_number(value)),
+        },
+        ParamDescriptor {
+            name: "Mono Sum",
+            label: "",
+            default: 0.,
+            format: format_switch,
+            parse: parse_switch,
+            steps: Some(&SWITCH_STEPS),
+            to_update: |value| StateUpdate::SetMonoSum(value >= 0.5),
+        },
+        ParamDescriptor {
+            name: "DC Block",
+            label: "",
+            default: 0.,
+            format: format_switch,
+            parse: parse_switch,
+            steps: Some(&SWITCH_STEPS),
+            to_update: |value| StateUpdate::SetDcBlock(value >= 0.5),
+        },
+        ParamDescriptor {
+            name: "CC Pickup",
+            label: "",
+            default: 0.,
+            format: format_switch,
+            parse: parse_switch,
+            steps: Some(&SWITCH_STEPS),
+            to_update: |value| StateUpdate::SetControllerPickup(value >= 0.5),
+        },
+        ParamDescriptor {
+            name: "Envelope Release",
+            label: "ms",
+            default: DEFAULT_ENVELOPE_RELEASE_MS / MAX_ENVELOPE_RELEASE_MS,
+            format: format_envelope_release,
+            parse: parse_envelope_release,
+            steps: None,
+            to_update: |value| StateUpdate::SetEnvelopeRelease(value * MAX_ENVELOPE_RELEASE_MS),
+        },
+        ParamDescriptor {
+            name: "Match Dry Level",
+            label: "",
+            default: 0.,
+            format: format_switch,
+            parse: parse_switch,
+            steps: Some(&SWITCH_STEPS),
+            to_update: |value| StateUpdate::SetLoudnessMatch(value >= 0.5),
+        },
+        ParamDescriptor {
+            name: "Preset Crossfade",
+            label: "ms",
+            default: 0.,
+            format: format_preset_crossfade,
+            parse: parse_preset_crossfade,
+            steps: None,
+            to_update: |value| StateUpdate::SetPresetCrossfade(value * MAX_PRESET_CROSSFADE_MS),
+        },
+        ParamDescriptor {
+            name: "Phase Invert",
+            label: "",
+            default: 0.,
+            format: format_phase_invert,
+            parse: parse_phase_invert,
+            steps: Some(&PHASE_INVERT_STEPS),
+            to_update: |value| {
+                StateUpdate::SetPhaseInvert(step_index(value, PHASE_INVERT_STEPS.len()) as u8)
+            },
+        },
+    ]
+}
+
+/// Displays a value of the amplitude parameter, in decibels or as a multiplier depending on how
+/// the plugin was built.
+pub fn format_amplitude(value: f32) -> String {
+    if AMPLITUDE_IN_DB {
+        format_decibels(value)
+    } else {
+        format_multiplier(value)
+    }
+}
+
+/// Parses text entered for the amplitude parameter, in decibels or as a multiplier depending on
+/// how the plugin was built.
+pub fn parse_amplitude(text: &str) -> Option<f32> {
+    if AMPLITUDE_IN_DB {
+        parse_decibels(text)
+    } else {
+        parse_multiplier(text)
+    }
+}
+
+/// Displays a value from 0 to 1 as a multiplier from 0 to 2.
+fn format_multiplier(value: f32) -> String {
+    format!("{:.2}", value * 2.)
+}
+
+/// Parses a multiplier from 0 to 2 into a value from 0 to 1.
+fn parse_multiplier(text: &str) -> Option<f32> {
+    match text.trim().parse::<f32>() {
+        Ok(value) if (0. ..=2.).contains(&value) => Some(value / 2.),
+        _ => None,
+    }
+}
+
+/// Displays a value from 0 to 1 as the gain in decibels of a multiplier from 0 to 2, like "-inf",
+/// "0.0", or "+6.0".
+fn format_decibels(value: f32) -> String {
+    if value <= 0. {
+        return "-inf".to_string();
+    }
+    let db = (20. * (value * 2.).log10() * 10.).round() / 10.;
+    if db > 0. {
+        format!("+{:.1}", db)
+    } else if db < 0. {
+        format!("{:.1}", db)
+    } else {
+        "0.0".to_string()
+    }
+}
+
+/// Parses a gain in decibels, up to that of a multiplier of 2, into a value from 0 to 1.
+fn parse_decibels(text: &str) -> Option<f32> {
+    let text = text.trim().trim_end_matches("dB").trim();
+    if text.eq_ignore_ascii_case("-inf") {
+        return Some(0.);
+    }
+    let multiplier = 10f32.powf(text.parse::<f32>().ok()? / 20.);
+    // Allow for rounding of displayed values at the top of the range.
+    if multiplier <= 2.001 {
+        Some((multiplier / 2.).min(1.))
+    } else {
+        None
+    }
+}
+
+/// Displays a value from 0 to 1 as a percentage.
+fn format_percentage(value: f32) -> String {
+    format!("{:.0}", value * 100.)
+}
+
+/// Parses a percentage into a value from 0 to 1.
+fn parse_percentage(text: &str) -> Option<f32> {
+    match text.trim().trim_end_matches('%').parse::<f32>() {
+        Ok(value) if (0. ..=100.).contains(&value) => Some(value / 100.),
+        _ => None,
+    }
+}
+
+/// Labels of each `SaturationMode`, in order.
+const SATURATION_STEPS: [&str; 3] = ["Off", "Hard", "Soft"];
+
+/// Divides the range from 0 to 1 evenly between each `SaturationMode`.
+fn saturation_mode(value: f32) -> SaturationMode {
+    match step_index(value, SATURATION_STEPS.len()) {
+        0 => SaturationMode::None,
+        1 => SaturationMode::HardClip,
+        _ => SaturationMode::SoftClip,
+    }
+}
+
+/// Displays a value from 0 to 1 as the name of a `SaturationMode`.
+fn format_saturation(value: f32) -> String {
+    format_step(value, &SATURATION_STEPS)
+}
+
+/// Parses the name of a `SaturationMode` into a value from 0 to 1.
+fn parse_saturation(text: &str) -> Option<f32> {
+    parse_step(text, &SATURATION_STEPS)
+}
+
+/// Labels of the channels whose polarity can be inverted. The index of each step is a bit mask of
+/// the inverted channels, with the lowest bit for the left channel.
+const PHASE_INVERT_STEPS: [&str; 4] = ["Off", "Left", "Right", "Both"];
+
+/// Displays a value from 0 to 1 as the channels whose polarity is inverted.
+fn format_phase_invert(value: f32) -> String {
+    format_step(value, &PHASE_INVERT_STEPS)
+}
+
+/// Parses the channels whose polarity is inverted into a value from 0 to 1.
+fn parse_phase_invert(text: &str) -> Option<f32> {
+    parse_step(text, &PHASE_INVERT_STEPS)
+}
+
+/// Labels of the states of a switch.
+const SWITCH_STEPS: [&str; 2] = ["Off", "On"];
+
+/// Displays a value from 0 to 1 as a switch, which is on for the upper half of the range.
+fn format_switch(value: f32) -> String {
+    format_step(value, &SWITCH_STEPS)
+}
+
+/// Parses the state of a switch into a value from 0 to 1.
+fn parse_switch(text: &str) -> Option<f32> {
+    parse_step(text, &SWITCH_STEPS)
+}
+
+/// Returns the index of the step that `value`, from 0 to 1, falls in when the range is divided
+/// evenly into `num_steps` steps.
+fn step_index(value: f32, num_steps: usize) -> usize {
+    ((value.max(0.) * num_steps as f32) as usize).min(num_steps - 1)
+}
+
+/// Returns the value from 0 to 1 that represents the step at `index` out of `num_steps`. The
+/// first and last steps are represented by 0 and 1, so that they're at the ends of the range.
+fn step_value(index: usize, num_steps: usize) -> f32 {
+    if num_steps > 1 {
+        index as f32 / (num_steps - 1) as f32
+    } else {
+        0.
+    }
+}
+
+/// Displays a value from 0 to 1 as the label of the step it falls in.
+fn format_step(value: f32, steps: &[&str]) -> String {
+    steps[step_index(value, steps.len())].to_string()
+}
+
+/// Parses the label of a step, ignoring case, into the value from 0 to 1 that represents it.
+fn parse_step(text: &str, steps: &[&str]) -> Option<f32> {
+    let text = text.trim();
+    steps
+        .iter()
+        .position(|step| step.eq_ignore_ascii_case(text))
+        .map(|index| step_value(index, steps.len()))
+}
+
+/// Maps a value from 0 to 1 onto a target loudness from `MIN_AUTO_GAIN_TARGET_DB` to 0dB.
+fn auto_gain_target_db(value: f32) -> f32 {
+    (1. - value) * MIN_AUTO_GAIN_TARGET_DB
+}
+
+/// Displays a value from 0 to 1 as an auto-gain target loudness in decibels.
+fn format_auto_gain_target(value: f32) -> String {
+    format!("{:.1}", auto_gain_target_db(value))
+}
+
+/// Parses an auto-gain target loudness in decibels into a value from 0 to 1.
+fn parse_auto_gain_target(text: &str) -> Option<f32> {
+    match text.trim().trim_end_matches("dB").trim().parse::<f32>() {
+        Ok(db) if (MIN_AUTO_GAIN_TARGET_DB..=0.).contains(&db) => {
+            Some(1. - db / MIN_AUTO_GAIN_TARGET_DB)
+        }
+        _ => None,
+    }
+}
+
+/// Maps a value from 0 to 1 onto a MIDI controller number from 0 to 127.
+fn controller_number(value: f32) -> u8 {
+    (value.clamp(0., 1.) * 127.).round() as u8
+}
+
+/// Parses a MIDI controller number into a value from 0 to 1.
+fn parse_controller_number(text: &str) -> Option<f32> {
+    match text.trim().parse::<u8>() {
+        Ok(number) if number <= 127 => Some(number as f32 / 127.),
+        _ => None,
+    }
+}
+
+/// Labels of each power-of-two oversampling factor up to `MAX_OVERSAMPLING`, in order.
+const OVERSAMPLING_STEPS: [&str; 3] = ["Off", "2x", "4x"];
+const _: () = assert!(1 << (OVERSAMPLING_STEPS.len() - 1) == MAX_OVERSAMPLING);
+
+/// Divides the range from 0 to 1 evenly between each power-of-two oversampling factor up to
+/// `MAX_OVERSAMPLING`.
+pub fn oversampling_factor(value: f32) -> usize {
+    1 << step_index(value, OVERSAMPLING_STEPS.len())
+}
+
+/// Displays a value from 0 to 1 as an oversampling factor, like "2x".
+fn format_oversampling(value: f32) -> String {
+    format_step(value, &OVERSAMPLING_STEPS)
+}
+
+/// Parses an oversampling factor into a value from 0 to 1. Besides the labels of each step, bare
+/// factors like "2" are accepted, with "1" meaning "Off".
+fn parse_oversampling(text: &str) -> Option<f32> {
+    parse_step(text, &OVERSAMPLING_STEPS).or_else(|| {
+        let factor = text
+            .trim()
+            .trim_end_matches(['x', 'X'])
+            .parse::<usize>()
+            .ok()?;
+        if factor.is_power_of_two() && factor <= MAX_OVERSAMPLING {
+            Some(step_value(
+                factor.trailing_zeros() as usize,
+                OVERSAMPLING_STEPS.len(),
+            ))
+        } else {
+            None
+        }
+    })
+}
+
+/// Displays a value from 0 to 1 as a stereo position, from "L100" through "C" to "R100".
+fn format_pan(value: f32) -> String {
+    let position = ((value - 0.5) * 200.).round();
+    if position < 0. {
+        format!("L{:.0}", -position)
+    } else if position > 0. {
+        format!("R{:.0}", position)
+    } else {
+        "C".to_string()
+    }
+}
+
+/// Parses a stereo position like "L30", "C", or "R100" into a value from 0 to 1. Positions may
+/// also be given as plain numbers from -100 (left) to 100 (right).
+fn parse_pan(text: &str) -> Option<f32> {
+    let text = text.trim().to_uppercase();
+    let position = if text == "C" {
+        0.
+    } else if let Some(amount) = text.strip_prefix('L') {
+        -amount.trim().parse::<f32>().ok()?
+    } else if let Some(amount) = text.strip_prefix('R') {
+        amount.trim().parse::<f32>().ok()?
+    } else {
+        text.parse::<f32>().ok()?
+    };
+    if (-100. ..=100.).contains(&position) {
+        Some(position / 200. + 0.5)
+    } else {
+        None
+    }
+}
+
+/// Longest parameter smoothing time that can be selected, in milliseconds.
+const MAX_GLIDE_TIME_MS: f32 = 500.;
+
+/// Displays a value from 0 to 1 as a smoothing time in milliseconds.
+fn format_glide_time(value: f32) -> String {
+    format!("{:.0}", value * MAX_GLIDE_TIME_MS)
+}
+
+/// Parses a smoothing time in milliseconds into a value from 0 to 1.
+fn parse_glide_time(text: &str) -> Option<f32> {
+    match text.trim().trim_end_matches("ms").trim().parse::<f32>() {
+        Ok(value) if (0. ..=MAX_GLIDE_TIME_MS).contains(&value) => Some(value / MAX_GLIDE_TIME_MS),
+        _ => None,
+    }
+}
+
+/// Longest release time that can be chosen for the envelope output, in milliseconds.
+const MAX_ENVELOPE_RELEASE_MS: f32 = 1000.;
+
+/// Displays a value from 0 to 1 as an envelope release time in milliseconds.
+fn format_envelope_release(value: f32) -> String {
+    format!("{:.0}", value * MAX_ENVELOPE_RELEASE_MS)
+}
+
+/// Parses an envelope release time in milliseconds into a value from 0 to 1.
+fn parse_envelope_release(text: &str) -> Option<f32> {
+    match text.trim().trim_end_matches("ms").trim().parse::<f32>() {
+        Ok(value) if (0. ..=MAX_ENVELOPE_RELEASE_MS).contains(&value) => {
+            Some(value / MAX_ENVELOPE_RELEASE_MS)
+        }
+        _ => None,
+    }
+}
+
+/// Longest time that can be chosen for crossfading to a newly loaded preset, in milliseconds.
+const MAX_PRESET_CROSSFADE_MS: f32 = 2000.;
+
+/// Displays a value from 0 to 1 as a preset crossfade time in milliseconds.
+fn format_preset_crossfade(value: f32) -> String {
+    format!("{:.0}", value * MAX_PRESET_CROSSFADE_MS)
+}
+
+/// Parses a preset crossfade time in milliseconds into a value from 0 to 1.
+fn parse_preset_crossfade(text: &str) -> Option<f32> {
+    match text.trim().trim_end_matches("ms").trim().parse::<f32>() {
+        Ok(value) if (0. ..=MAX_PRESET_CROSSFADE_MS).contains(&value) => {
+            Some(value / MAX_PRESET_CROSSFADE_MS)
+        }
+        _ => None,
+    }
+}
+
+/// Displays a value from 0 to 1 as a make-up gain in decibels.
+pub fn format_makeup_gain(value: f32) -> String {
+    format!("{:.1}", value * MAX_MAKEUP_GAIN_DB)
+}
+
+/// Parses a make-up gain in decibels into a value from 0 to 1.
+pub fn parse_makeup_gain(text: &str) -> Option<f32> {
+    match text.trim().trim_end_matches("dB").trim().parse::<f32>() {
+        Ok(value) if (0. ..=MAX_MAKEUP_GAIN_DB).contains(&value) => {
+            Some(value / MAX_MAKEUP_GAIN_DB)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decibel_display() {
+        assert_eq!(format_decibels(0.), "-inf");
+        assert_eq!(format_decibels(0.5), "0.0");
+        assert_eq!(format_decibels(1.), "+6.0");
+        assert_eq!(format_decibels(0.25), "-6.0");
+    }
+
+    #[test]
+    fn oversampling_round_trip() {
+        assert_eq!(plugin_parameters()[OVERSAMPLING].name, "Oversampling");
+        assert_eq!(oversampling_factor(0.), 1);
+        assert_eq!(oversampling_factor(0.5), 2);
+        assert_eq!(oversampling_factor(1.), 4);
+        for factor in ["Off", "2x", "4X"] {
+            let value = parse_oversampling(factor).unwrap();
+            assert!(format_oversampling(value).eq_ignore_ascii_case(factor));
+        }
+        for (factor, label) in [("1", "Off"), ("1x", "Off"), ("2", "2x"), (" 4 ", "4x")] {
+            let value = parse_oversampling(factor).unwrap();
+            assert_eq!(format_oversampling(value), label);
+        }
+        for factor in ["3", "3x", "8", "8x", "0", "x"] {
+            assert_eq!(parse_oversampling(factor), None);
+        }
+    }
+
+    #[test]
+    fn stepped_parameters_are_quantized() {
+        for param in plugin_parameters() {
+            let steps = match param.steps {
+                Some(steps) => steps,
+                None => {
+                    assert_eq!(param.quantize(0.3), 0.3);
+                    continue;
+                }
+            };
+            for (index, step) in steps.iter().enumerate() {
+                let value = (param.parse)(step).unwrap();
+                assert_eq!(&(param.format)(value), step);
+                assert_eq!(param.quantize(value), value);
+                // Anywhere within the step's part of the range is rounded to the same value.
+                let within = (index as f32 + 0.9) / steps.len() as f32;
+                assert_eq!(param.quantize(within), value);
+            }
+            assert_eq!(param.quantize(1.), 1.);
+        }
+    }
+
+    #[test]
+    fn decibel_round_trip() {
+        for &value in &[0., 0.1, 0.25, 0.5, 0.8, 1.] {
+            let parsed = parse_decibels(&format_decibels(value)).unwrap();
+            assert!((parsed - value).abs() < 0.01);
+        }
+        assert_eq!(parse_decibels("0 dB"), Some(0.5));
+        assert_eq!(parse_decibels("+12"), None);
+        assert_eq!(parse_decibels("loud"), None);
+    }
+}
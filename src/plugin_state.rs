@@ -4,34 +4,405 @@
 //! synchronization overhead, and to reduce recalculation of derived parameters, the audio
 //! processing and UI threads subscribe to parameter updates through cross-thread message passing.
 //!
-//! This plugin's long-term state only consists of a single floating-point value (the value of the
-//! amplitude knob), but it should be simple to extend this scheme to work with multiple knobs,
-//! toggles, node locations, waveforms, user-defined labels, and so on.
+//! This plugin's long-term state only consists of a few floating-point values (one per parameter
+//! described in the `parameters` module), but it should be simple to extend this scheme to work
+//! with multiple knobs, toggles, node locations, waveforms, user-defined labels, and so on.
 
+use std::convert::TryFrom;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    mpsc::Sender,
-    Mutex,
+    atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+    mpsc::{sync_channel, Receiver, Sender, SyncSender},
+    Arc, Mutex,
 };
 
 use vst::{
-    host::Host,
+    host::{self, Host},
     plugin::{HostCallback, PluginParameters},
 };
 
+use crate::dsp::{oversampling_latency, SaturationMode, MAX_OUTPUT_TRIM_DB, SCOPE_BATCH_LEN};
+use crate::editor::{physical_size, restorable_window_size, DEFAULT_WINDOW_SIZE};
+use crate::parameters::{oversampling_factor, ParamDescriptor, OVERSAMPLING};
+
 /// Describes a discrete operation that can update this plugin's long-term state.
+///
+/// Most updates are passed between threads as messages, so that none of them are lost. The
+/// exception is `SetKnob`, which the audio processing thread receives through a `LatestValue`
+/// instead: knob drags produce a rapid stream of them, of which only the most recent matters by
+/// the time the next block is processed. Other continuous values could be coalesced the same way,
+/// but updates like `SetSaturation` and `SetGate` must always be delivered.
+///
+/// Time-critical updates, like `SetMute`, are sent through a separate priority channel, which the
+/// audio processing thread drains before any others.
 #[derive(Clone)]
 pub enum StateUpdate {
     SetKnob(f32),
+    SetWidth(f32),
+    SetMix(f32),
+    SetSaturation(SaturationMode),
+    /// Factor by which the sample rate is multiplied while saturating: 1, 2, or 4.
+    SetOversampling(usize),
+    SetPan(f32),
+    SetMakeupGain(f32),
+    /// MIDI controller number, from 0 to 127, that sets the amplitude.
+    SetAmplitudeController(u8),
+    /// Whether the MIDI controller must reach the amplitude's value before taking over from it.
+    SetControllerPickup(bool),
+    /// Enables or disables automatic choice of the amplitude, overriding the knob.
+    SetAutoGain(bool),
+    /// Loudness that auto-gain aims for, in decibels relative to full scale.
+    SetAutoGainTarget(f32),
+    /// Replaces both output channels with their sum, for checking mono compatibility.
+    SetMonoSum(bool),
+    /// Enables or disables removal of any DC offset from the output.
+    SetDcBlock(bool),
+    /// Enables or disables matching the loudness of the dry signal to the processed signal.
+    SetLoudnessMatch(bool),
+    /// Gain of a single channel, given by its index, applied independently of the others.
+    SetChannelGain(usize, f32),
+    /// Bit mask of the channels whose polarity is inverted, with the lowest bit for the first
+    /// channel.
+    SetPhaseInvert(u8),
+    /// Parameter smoothing time, in milliseconds.
+    SetGlideTime(f32),
+    /// Opens or closes the MIDI note gate. Sent only within the audio processing thread.
+    SetGate(bool),
+    /// Silences or restores the output, regardless of the amplitude and other parameters.
+    SetMute(bool),
+    /// Gain applied to the output after all other processing, in decibels.
+    SetOutputTrim(f32),
+    /// Release time of the envelope output's peak follower, in milliseconds.
+    SetEnvelopeRelease(f32),
+    /// Time over which loading a preset crossfades to its parameters, in milliseconds.
+    SetPresetCrossfade(f32),
+    /// Peak output levels of the left and right channels in the blocks processed since the editor
+    /// last read them, reported by the audio processing thread through an `EditorNotifier`. Mono
+    /// output is reported in both.
+    MeterLevels([f32; 2]),
+    /// Whether any parameter is still being smoothed towards a new value, reported by the audio
+    /// processing thread whenever it changes. Short pauses in smoothing are not reported, to
+    /// avoid flickering.
+    SmoothingActive(bool),
+    /// Whether any input sample reached full scale, before any processing, reported by the audio
+    /// processing thread whenever it changes.
+    InputClipping(bool),
+    /// Downsampled output samples, oldest first, reported by the audio processing thread for the
+    /// editor's scope.
+    ScopeSamples([f32; SCOPE_BATCH_LEN]),
+    /// Several updates that must be applied together, like the parameters of a snapshot, so that
+    /// no audio is processed with only some of them applied.
+    Batch(Vec<StateUpdate>),
+    /// Like `Batch`, but for the parameters of a newly loaded preset. Over the preset crossfade
+    /// time in effect beforehand, every smoothed parameter moves from its old value to its new one
+    /// in a straight line, all arriving together. With no crossfade time, they're smoothed as
+    /// usual.
+    Crossfade(Vec<StateUpdate>),
+}
+
+impl StateUpdate {
+    /// Whether the audio processing thread should apply this update ahead of any other pending
+    /// updates, because the user expects it to take effect immediately.
+    pub fn is_time_critical(&self) -> bool {
+        matches!(self, StateUpdate::SetMute(_))
+    }
+}
+
+/// Number of batches of scope samples kept for the editor. Batches that the editor doesn't read
+/// before this many more are reported are lost, which takes about a third of a second at 48kHz.
+const SCOPE_RING_BATCHES: usize = 16;
+
+/// Measurements taken by the audio processing thread on every block, for display by the editor.
+/// Sending these as `StateUpdate` messages could allocate on the audio processing thread, so they
+/// are written to preallocated atomics instead, which the editor reads through a
+/// `DspReportReader` whenever it runs.
+struct DspReports {
+    /// Highest peak output level of each channel since the editor last read them, stored as the
+    /// level's bit pattern plus one, or 0 if none has been reported since. Bit patterns of
+    /// non-negative `f32`s are ordered the same way as their values, so the highest level can be
+    /// kept with `fetch_max`.
+    meter_levels: [AtomicU32; 2],
+    /// Ring buffer of the most recent batches of scope samples, stored as bit patterns.
+    scope_batches: Vec<[AtomicU32; SCOPE_BATCH_LEN]>,
+    /// Total number of scope batches written to `scope_batches`.
+    scope_batches_written: AtomicUsize,
+    /// Whether any parameter is currently being smoothed.
+    smoothing_active: AtomicBool,
+    /// Whether the input clipped in the most recently processed block.
+    input_clipping: AtomicBool,
+    /// Whether the input clipped in any block since the editor last read it, so that clipping in
+    /// a single block between two reads isn't missed.
+    input_clipped: AtomicBool,
+}
+
+impl DspReports {
+    fn new() -> Self {
+        Self {
+            meter_levels: Default::default(),
+            scope_batches: (0..SCOPE_RING_BATCHES)
+                .map(|_| Default::default())
+                .collect(),
+            scope_batches_written: AtomicUsize::new(0),
+            smoothing_active: AtomicBool::new(false),
+            input_clipping: AtomicBool::new(false),
+            input_clipped: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Reports measurements from the audio processing thread to the editor interface, but only while
+/// it is open. This allows the audio processing thread to notify the editor directly, without
+/// accumulating reports while there is no editor to read them. Reporting never blocks or
+/// allocates.
+///
+/// Only one thread should report through an `EditorNotifier` at a time.
+pub struct EditorNotifier {
+    reports: Arc<DspReports>,
+    editor_is_open: Arc<AtomicBool>,
+}
+
+impl EditorNotifier {
+    /// Creates a notifier that isn't connected to any editor, for processing audio without one.
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        Self {
+            reports: Arc::new(DspReports::new()),
+            editor_is_open: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Creates a notifier for an open editor, whose reports are read by the returned
+    /// `DspReportReader`.
+    #[cfg(test)]
+    pub fn open() -> (Self, DspReportReader) {
+        let reports = Arc::new(DspReports::new());
+        let notifier = Self {
+            reports: Arc::clone(&reports),
+            editor_is_open: Arc::new(AtomicBool::new(true)),
+        };
+        (notifier, DspReportReader::new(reports))
+    }
+
+    /// Whether the editor is currently open to receive reports.
+    pub fn is_open(&self) -> bool {
+        self.editor_is_open.load(Ordering::Relaxed)
+    }
+
+    /// Reports the peak output `levels` of the left and right channels in a processed block.
+    pub fn report_meter_levels(&self, levels: [f32; 2]) {
+        for (meter, level) in self.reports.meter_levels.iter().zip(levels.iter()) {
+            meter.fetch_max(level.abs().to_bits() + 1, Ordering::Relaxed);
+        }
+    }
+
+    /// Reports the next batch of downsampled output samples for the scope.
+    pub fn report_scope_samples(&self, batch: [f32; SCOPE_BATCH_LEN]) {
+        let written = self.reports.scope_batches_written.load(Ordering::Relaxed);
+        let slot = &self.reports.scope_batches[written % SCOPE_RING_BATCHES];
+        for (stored, sample) in slot.iter().zip(batch.iter()) {
+            stored.store(sample.to_bits(), Ordering::Relaxed);
+        }
+        self.reports
+            .scope_batches_written
+            .store(written + 1, Ordering::Release);
+    }
+
+    /// Reports whether any parameter is currently being smoothed.
+    pub fn report_smoothing(&self, active: bool) {
+        self.reports
+            .smoothing_active
+            .store(active, Ordering::Relaxed);
+    }
+
+    /// Reports whether the input clipped in the most recently processed block.
+    pub fn report_input_clipping(&self, clipping: bool) {
+        self.reports
+            .input_clipping
+            .store(clipping, Ordering::Relaxed);
+        if clipping {
+            self.reports.input_clipped.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Reads the measurements reported through an `EditorNotifier` on behalf of an editor, passing
+/// them on as `StateUpdate`s.
+pub struct DspReportReader {
+    reports: Arc<DspReports>,
+    /// Number of scope batches that had been written when they were last read.
+    scope_batches_read: usize,
+    /// Whether smoothing was active when last passed on.
+    smoothing_active: bool,
+    /// Whether the input was clipping when last passed on.
+    input_clipping: bool,
+}
+
+impl DspReportReader {
+    /// Creates a reader for a newly opened editor, which assumes that nothing is being smoothed
+    /// or clipping, and has nothing to show in its meters or scope yet. Anything reported before
+    /// this is discarded.
+    fn new(reports: Arc<DspReports>) -> Self {
+        for meter in reports.meter_levels.iter() {
+            meter.store(0, Ordering::Relaxed);
+        }
+        reports.input_clipped.store(false, Ordering::Relaxed);
+        let scope_batches_read = reports.scope_batches_written.load(Ordering::Acquire);
+        Self {
+            reports,
+            scope_batches_read,
+            smoothing_active: false,
+            input_clipping: false,
+        }
+    }
+
+    /// Passes everything reported since the last call to `react`. Smoothing activity and input
+    /// clipping are only passed on when they change.
+    pub fn poll(&mut self, mut react: impl FnMut(StateUpdate)) {
+        let levels =
+            [0, 1].map(|meter| self.reports.meter_levels[meter].swap(0, Ordering::Relaxed));
+        if levels.iter().any(|&level| level != 0) {
+            react(StateUpdate::MeterLevels(
+                levels.map(|level| f32::from_bits(level.saturating_sub(1))),
+            ));
+        }
+
+        // The slot after the most recently written one may already be being overwritten.
+        let written = self.reports.scope_batches_written.load(Ordering::Acquire);
+        let oldest = written.saturating_sub(SCOPE_RING_BATCHES - 1);
+        for index in self.scope_batches_read.max(oldest)..written {
+            let slot = &self.reports.scope_batches[index % SCOPE_RING_BATCHES];
+            let mut batch = [0.; SCOPE_BATCH_LEN];
+            for (sample, stored) in batch.iter_mut().zip(slot.iter()) {
+                *sample = f32::from_bits(stored.load(Ordering::Relaxed));
+            }
+            react(StateUpdate::ScopeSamples(batch));
+        }
+        self.scope_batches_read = written;
+
+        let smoothing_active = self.reports.smoothing_active.load(Ordering::Relaxed);
+        if smoothing_active != self.smoothing_active {
+            self.smoothing_active = smoothing_active;
+            react(StateUpdate::SmoothingActive(smoothing_active));
+        }
+
+        let clipped = self.reports.input_clipped.swap(false, Ordering::Relaxed);
+        let clipping = self.reports.input_clipping.load(Ordering::Relaxed);
+        if clipped && !self.input_clipping {
+            self.input_clipping = true;
+            react(StateUpdate::InputClipping(true));
+        }
+        if !clipping && self.input_clipping {
+            self.input_clipping = false;
+            react(StateUpdate::InputClipping(false));
+        }
+    }
+}
+
+/// Single-slot mailbox holding the most recent value of a continuous parameter, which can be
+/// written from any thread and taken by another without locking. Values written before the
+/// previous one was taken are overwritten.
+#[derive(Clone)]
+pub struct LatestValue(Arc<AtomicU32>);
+
+impl LatestValue {
+    /// Marks an empty slot. This is a NaN bit pattern that `f32` operations never produce.
+    const EMPTY: u32 = u32::MAX;
+
+    pub fn new() -> Self {
+        Self(Arc::new(AtomicU32::new(Self::EMPTY)))
+    }
+
+    /// Replaces any value in the slot with `value`.
+    pub fn set(&self, value: f32) {
+        let bits = match value.to_bits() {
+            Self::EMPTY => f32::NAN.to_bits(),
+            bits => bits,
+        };
+        self.0.store(bits, Ordering::Release);
+    }
+
+    /// Empties the slot, returning the most recent value if one was set since the last call.
+    pub fn take(&self) -> Option<f32> {
+        match self.0.swap(Self::EMPTY, Ordering::Acquire) {
+            Self::EMPTY => None,
+            bits => Some(f32::from_bits(bits)),
+        }
+    }
 }
 
+/// An `f32` that can be read and written from any thread without locking, stored as its bit
+/// pattern. Each value is independent of any other, so relaxed ordering is enough.
+struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    fn new(value: f32) -> Self {
+        Self(AtomicU32::new(value.to_bits()))
+    }
+
+    fn load(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn store(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed)
+    }
+}
+
+/// Number of emptied batch vectors that the audio processing thread can hand back before the next
+/// batch is sent. Batches are only sent in response to the user, like when a preset is loaded, so
+/// few are ever waiting.
+const RETIRED_BATCH_CAPACITY: usize = 16;
+
+/// Number of parameter snapshots that can be stored for A/B comparison.
+pub const NUM_SNAPSHOTS: usize = 2;
+
 pub struct PluginState {
     host: HostCallback,
     to_dsp: Mutex<Sender<StateUpdate>>,
+    /// Carries time-critical updates to the audio processing thread, in place of `to_dsp`.
+    priority_to_dsp: Mutex<Sender<StateUpdate>>,
+    /// Receives `SetKnob` values on behalf of the audio processing thread, in place of `to_dsp`.
+    knob_to_dsp: LatestValue,
     to_editor: Mutex<Sender<StateUpdate>>,
-    editor_is_open: AtomicBool,
+    editor_is_open: Arc<AtomicBool>,
+    /// Measurements reported by the audio processing thread for the editor.
+    dsp_reports: Arc<DspReports>,
+    /// Passed to the audio processing thread, to hand back the emptied vectors of `Batch` and
+    /// `Crossfade` updates.
+    retired_batch_sender: SyncSender<Vec<StateUpdate>>,
+    /// Receives the vectors handed back through `retired_batch_sender`, which are freed whenever
+    /// another batch is sent.
+    retired_batches: Mutex<Receiver<Vec<StateUpdate>>>,
 
-    state_record: Mutex<Vec<f32>>,
+    parameters: Vec<ParamDescriptor>,
+    /// Current value of each parameter. Hosts may read and write these from the audio processing
+    /// thread, where waiting on a lock held by another thread could cause dropouts, so each value
+    /// is stored atomically instead. The number of parameters never changes, so the `Vec` itself
+    /// needs no lock.
+    ///
+    /// Sending the corresponding `StateUpdate`s still briefly locks the channel senders, but those
+    /// locks are only held for the duration of a non-blocking send.
+    state_record: Vec<AtomicF32>,
+    /// Pixel size of the editor window, kept so that it can be restored along with the project.
+    window_size: Mutex<(u32, u32)>,
+    /// Index of the editor's selected color theme.
+    theme: Mutex<u8>,
+    /// Number of physical display pixels per logical pixel of the editor window, as reported by
+    /// the host.
+    scale_factor: Mutex<f32>,
+    /// Copies of `state_record` stored for A/B comparison, or `None` for empty slots.
+    snapshots: Mutex<[Option<Vec<f32>>; NUM_SNAPSHOTS]>,
+    /// Index of the snapshot most recently stored or recalled.
+    active_snapshot: Mutex<u8>,
+    /// Whether the output is muted from the editor. This is a live performance control rather
+    /// than a parameter, so it isn't automated or saved with presets.
+    muted: AtomicBool,
+    /// Gain applied to the output after all other processing, in decibels. This is saved with
+    /// presets, but isn't a parameter, so that hosts don't offer to automate it.
+    output_trim: AtomicF32,
+    /// Processing latency in samples, as last recorded by the audio processing thread.
+    latency: AtomicUsize,
+    /// Processing latency most recently reported to the host, in samples.
+    reported_latency: AtomicUsize,
 }
 
 /// VST-accessible long-term plugin state storage. This is accessed through the audio processing
@@ -40,23 +411,175 @@ pub struct PluginState {
 impl PluginState {
     pub fn new(
         host: HostCallback,
+        parameters: Vec<ParamDescriptor>,
         to_dsp: Sender<StateUpdate>,
+        priority_to_dsp: Sender<StateUpdate>,
         to_editor: Sender<StateUpdate>,
     ) -> Self {
+        let state_record = parameters
+            .iter()
+            .map(|param| AtomicF32::new(param.default))
+            .collect();
+        let (retired_batch_sender, retired_batches) = sync_channel(RETIRED_BATCH_CAPACITY);
         Self {
             host,
             to_dsp: Mutex::new(to_dsp),
+            priority_to_dsp: Mutex::new(priority_to_dsp),
+            knob_to_dsp: LatestValue::new(),
             to_editor: Mutex::new(to_editor),
-            editor_is_open: AtomicBool::new(false),
-            state_record: Mutex::new(vec![0.5, 0., 0., 0.]),
+            editor_is_open: Arc::new(AtomicBool::new(false)),
+            dsp_reports: Arc::new(DspReports::new()),
+            retired_batch_sender,
+            retired_batches: Mutex::new(retired_batches),
+            parameters,
+            state_record,
+            window_size: Mutex::new(DEFAULT_WINDOW_SIZE),
+            theme: Mutex::new(0),
+            scale_factor: Mutex::new(1.),
+            snapshots: Mutex::new(Default::default()),
+            active_snapshot: Mutex::new(0),
+            muted: AtomicBool::new(false),
+            output_trim: AtomicF32::new(0.),
+            latency: AtomicUsize::new(0),
+            reported_latency: AtomicUsize::new(0),
         }
     }
-}
 
-/// The DAW directly accesses the plugin state through the VST API to get reports on knob states.
-impl PluginParameters for PluginState {
-    fn set_parameter(&self, index: i32, value: f32) {
-        let state_update = StateUpdate::SetKnob(value);
+    /// Records the display scale factor of the editor window reported by the host. Invalid scale
+    /// factors are ignored.
+    pub fn set_scale_factor(&self, scale_factor: f32) {
+        if scale_factor.is_finite() && scale_factor > 0. {
+            *self.scale_factor.lock().unwrap() = scale_factor;
+        }
+    }
+
+    /// Sets the output trim to `db` decibels, limited to `MAX_OUTPUT_TRIM_DB` in either direction,
+    /// and notifies the audio processing thread of the change. Invalid values are ignored.
+    pub fn set_output_trim(&self, db: f32) {
+        if db.is_finite() {
+            let db = db.clamp(-MAX_OUTPUT_TRIM_DB, MAX_OUTPUT_TRIM_DB);
+            self.output_trim.store(db);
+            self.send_to_dsp(StateUpdate::SetOutputTrim(db));
+        }
+    }
+
+    /// Returns a handle that the audio processing thread can use to report measurements to the
+    /// editor. There should only be one.
+    pub fn editor_notifier(&self) -> EditorNotifier {
+        EditorNotifier {
+            reports: Arc::clone(&self.dsp_reports),
+            editor_is_open: Arc::clone(&self.editor_is_open),
+        }
+    }
+
+    /// Returns a reader of the measurements reported through the `EditorNotifier`, for a newly
+    /// opened editor.
+    pub fn dsp_report_reader(&self) -> DspReportReader {
+        DspReportReader::new(Arc::clone(&self.dsp_reports))
+    }
+
+    /// Returns the sender through which the audio processing thread should hand back the emptied
+    /// vectors of `Batch` and `Crossfade` updates, so that they aren't freed on that thread.
+    pub fn retired_batch_sender(&self) -> SyncSender<Vec<StateUpdate>> {
+        self.retired_batch_sender.clone()
+    }
+
+    /// Returns the slot that the audio processing thread should take new `SetKnob` values from.
+    pub fn dsp_knob_slot(&self) -> LatestValue {
+        self.knob_to_dsp.clone()
+    }
+
+    /// Number of parameters exposed to the host.
+    pub fn num_parameters(&self) -> usize {
+        self.parameters.len()
+    }
+
+    /// Returns the descriptor for the parameter at `index`, or `None` if it is out of range.
+    fn descriptor(&self, index: i32) -> Option<&ParamDescriptor> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.parameters.get(index))
+    }
+
+    /// Sends an opcode to the host, if the plugin has been connected to one. Returns the host's
+    /// response, or 0 otherwise.
+    fn host_opcode(&self, opcode: host::OpCode, index: i32, value: isize) -> isize {
+        match self.host.raw_callback() {
+            Some(callback) => callback(
+                self.host.raw_effect(),
+                opcode.into(),
+                index,
+                value,
+                std::ptr::null_mut(),
+                0.,
+            ),
+            None => 0,
+        }
+    }
+
+    /// Records the processing latency of the audio processing thread, in samples. Hosts expect to
+    /// be told about latency changes on the UI thread, so this only stores the new latency until
+    /// the next `report_latency_change` call.
+    pub fn set_latency(&self, samples: usize) {
+        self.latency.store(samples, Ordering::Relaxed);
+    }
+
+    /// Informs the host if the processing latency recorded by `set_latency` has changed since it
+    /// was last reported. This should only be called from the UI thread.
+    pub fn report_latency_change(&self) {
+        let latency = self.latency.load(Ordering::Relaxed);
+        if self.reported_latency.swap(latency, Ordering::Relaxed) != latency {
+            self.report_latency(latency);
+        }
+    }
+
+    /// Processing latency most recently reported to the host, in samples.
+    #[cfg(test)]
+    pub fn reported_latency(&self) -> usize {
+        self.reported_latency.load(Ordering::Relaxed)
+    }
+
+    /// Informs the host that the plugin's processing latency has changed to `samples`, so that it
+    /// can adjust its delay compensation.
+    fn report_latency(&self, samples: usize) {
+        let effect = self.host.raw_effect();
+        if effect.is_null() {
+            return;
+        }
+        // Hosts read the latency directly from the `AEffect` struct after being notified of the
+        // change.
+        unsafe {
+            (*effect).initialDelay = samples as i32;
+        }
+        self.host_opcode(host::OpCode::IOChanged, 0, 0);
+    }
+
+    /// Passes `state_update` to the audio processing thread, coalescing it with any previous
+    /// update that hasn't been received yet if possible, or ahead of other updates if it's time
+    /// critical.
+    fn send_to_dsp(&self, state_update: StateUpdate) {
+        match state_update {
+            StateUpdate::SetKnob(value) => self.knob_to_dsp.set(value),
+            state_update if state_update.is_time_critical() => self
+                .priority_to_dsp
+                .lock()
+                .unwrap()
+                .send(state_update)
+                .unwrap(),
+            StateUpdate::Batch(_) | StateUpdate::Crossfade(_) => {
+                // The audio processing thread takes the knob slot after its messages, so a pending
+                // knob value would otherwise override any `SetKnob` in the batch.
+                self.knob_to_dsp.take();
+                self.to_dsp.lock().unwrap().send(state_update).unwrap()
+            }
+            state_update => self.to_dsp.lock().unwrap().send(state_update).unwrap(),
+        }
+    }
+
+    /// Records a new value for the parameter at `index`, and notifies the audio processing thread
+    /// and editor interface of the change.
+    fn update_parameter(&self, index: usize, value: f32) {
+        let state_update = (self.parameters[index].to_update)(value);
         if self.editor_is_open.load(Ordering::Relaxed) {
             self.to_editor
                 .lock()
@@ -64,69 +587,980 @@ impl PluginParameters for PluginState {
                 .send(state_update.clone())
                 .unwrap();
         }
-        self.to_dsp.lock().unwrap().send(state_update).unwrap();
-        self.state_record.lock().unwrap()[index as usize] = value;
+        self.send_to_dsp(state_update);
+        self.state_record[index].store(value);
+    }
+
+    /// Returns a copy of the current value of every parameter. Parameters changed while copying
+    /// may have either their old or new value.
+    fn parameter_values(&self) -> Vec<f32> {
+        self.state_record.iter().map(AtomicF32::load).collect()
+    }
+
+    /// Sets the parameter at `index` in response to a MIDI controller, notifying the audio
+    /// processing thread, the editor interface, and the host of the change. The host records it
+    /// like any other automation.
+    ///
+    /// Unless the "CC Pickup" parameter is enabled, there's no arbitration between MIDI
+    /// controllers and host automation of the same parameter: whichever changed it most recently
+    /// wins. With pickup, the audio processing thread holds back controller values until they
+    /// reach the current value, so this is only called once the controller has taken over.
+    pub fn set_parameter_from_controller(&self, index: usize, value: f32) {
+        self.update_parameter(index, value);
+        self.automate(index, value);
+    }
+
+    /// Records new values for the first `values.len()` parameters, and notifies the audio
+    /// processing thread and editor interface of every change in a single update created by
+    /// `combine`, like `StateUpdate::Batch`, along with any `other_updates` that must be applied at
+    /// the same time.
+    fn update_parameters(
+        &self,
+        values: &[f32],
+        other_updates: impl IntoIterator<Item = StateUpdate>,
+        combine: fn(Vec<StateUpdate>) -> StateUpdate,
+    ) {
+        // Free any batches that the audio processing thread has finished with.
+        self.retired_batches
+            .lock()
+            .unwrap()
+            .try_iter()
+            .for_each(drop);
+
+        let values = &values[..values.len().min(self.parameters.len())];
+        let updates: Vec<StateUpdate> = values
+            .iter()
+            .zip(self.parameters.iter())
+            .map(|(&value, param)| (param.to_update)(value))
+            .chain(other_updates)
+            .collect();
+        if self.editor_is_open.load(Ordering::Relaxed) {
+            self.to_editor
+                .lock()
+                .unwrap()
+                .send(combine(updates.clone()))
+                .unwrap();
+        }
+        self.send_to_dsp(combine(updates));
+        for (record, &value) in self.state_record.iter().zip(values) {
+            record.store(value);
+        }
+    }
+
+    /// Resets every parameter to its default value, notifying the audio processing thread, the
+    /// editor interface, and the host of each change.
+    ///
+    /// This is safe to call from any thread. No lock is held while notifying the host, so the host
+    /// may query parameter values in response.
+    pub fn reset_all_parameters(&self) {
+        let defaults: Vec<f32> = self.parameters.iter().map(|param| param.default).collect();
+        self.set_all_parameters(&defaults);
+    }
+
+    /// Stores a copy of the current value of every parameter in the snapshot at `slot`, which
+    /// becomes the active snapshot.
+    pub fn store_snapshot(&self, slot: usize) {
+        let values = self.parameter_values();
+        self.snapshots.lock().unwrap()[slot] = Some(values);
+        *self.active_snapshot.lock().unwrap() = slot as u8;
+    }
+
+    /// Restores every parameter from the snapshot at `slot`, which becomes the active snapshot,
+    /// notifying the audio processing thread, the editor interface, and the host of each change.
+    /// If nothing has been stored in `slot` yet, the current parameters are stored in it instead.
+    pub fn recall_snapshot(&self, slot: usize) {
+        let values = self.snapshots.lock().unwrap()[slot].clone();
+        match values {
+            Some(values) => {
+                self.set_all_parameters(&values);
+                *self.active_snapshot.lock().unwrap() = slot as u8;
+            }
+            None => self.store_snapshot(slot),
+        }
+    }
+
+    /// Sets each parameter to the corresponding entry of `values`, notifying the audio processing
+    /// thread, the editor interface, and the host of each change.
+    ///
+    /// No lock is held while notifying the host, so the host may query parameter values in
+    /// response.
+    fn set_all_parameters(&self, values: &[f32]) {
+        self.update_parameters(values, None, StateUpdate::Batch);
+        for (index, &value) in values.iter().enumerate().take(self.parameters.len()) {
+            self.host_opcode(host::OpCode::BeginEdit, index as i32, 0);
+            self.automate(index, value);
+            self.host_opcode(host::OpCode::EndEdit, index as i32, 0);
+        }
+    }
+
+    /// Notifies the host of a new value for the parameter at `index`, if the plugin has been
+    /// connected to one.
+    fn automate(&self, index: usize, value: f32) {
+        if self.host.raw_callback().is_some() {
+            self.host.automate(index as i32, value);
+        }
+    }
+
+    /// Serializes the current value of every parameter, followed by the editor window size and
+    /// theme, the A/B snapshots, and the output trim, into a preset chunk.
+    fn serialize(&self) -> Vec<u8> {
+        let state_record = self.parameter_values();
+        let (width, height) = *self.window_size.lock().unwrap();
+
+        let mut data = Vec::with_capacity(PRESET_HEADER_LEN + state_record.len() * 4 + 14);
+        data.extend_from_slice(PRESET_MAGIC);
+        data.push(PRESET_VERSION);
+        data.extend_from_slice(&(state_record.len() as u16).to_le_bytes());
+        for value in state_record.iter() {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.push(*self.theme.lock().unwrap());
+        data.push(*self.active_snapshot.lock().unwrap());
+        for snapshot in self.snapshots.lock().unwrap().iter() {
+            let values = snapshot.as_deref().unwrap_or_default();
+            data.extend_from_slice(&(values.len() as u16).to_le_bytes());
+            for value in values {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        data.extend_from_slice(&self.output_trim.load().to_le_bytes());
+        data
+    }
+
+    /// Restores every parameter from a preset chunk created by `serialize`. Chunks from older
+    /// versions of the plugin may contain fewer parameters, in which case the remaining parameters
+    /// are reset to their defaults, and may not include a window size, theme, or snapshots, in
+    /// which case the current ones are kept, or an output trim, in which case it is reset to 0dB.
+    /// A window size that the editor can't be zoomed to is replaced by the default size, and
+    /// parameter values, including those of snapshots, are restored as by `restored_value`.
+    /// Unrecognized data is ignored.
+    fn deserialize(&self, data: &[u8]) {
+        if data.len() < PRESET_HEADER_LEN
+            || &data[..4] != PRESET_MAGIC
+            || data[4] == 0
+            || data[4] > PRESET_VERSION
+        {
+            return;
+        }
+        let count = u16::from_le_bytes([data[5], data[6]]) as usize;
+
+        let mut stored_values = data[PRESET_HEADER_LEN..]
+            .chunks_exact(4)
+            .take(count)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+
+        let values: Vec<f32> = self
+            .parameters
+            .iter()
+            .map(|param| restored_value(param, stored_values.next()))
+            .collect();
+
+        let window_size_start = PRESET_HEADER_LEN + count * 4;
+        if data[4] >= 2 && data.len() >= window_size_start + 8 {
+            let size = &data[window_size_start..window_size_start + 8];
+            let width = u32::from_le_bytes([size[0], size[1], size[2], size[3]]);
+            let height = u32::from_le_bytes([size[4], size[5], size[6], size[7]]);
+            *self.window_size.lock().unwrap() = restorable_window_size((width, height));
+        }
+
+        let theme_start = window_size_start + 8;
+        if data[4] >= 3 && data.len() > theme_start {
+            *self.theme.lock().unwrap() = data[theme_start];
+        }
+
+        let snapshots_start = theme_start + 1;
+        let mut output_trim = 0.;
+        if data[4] >= 4 && data.len() > snapshots_start {
+            if let Some((snapshots, snapshots_len)) = read_snapshots(&data[snapshots_start + 1..]) {
+                *self.active_snapshot.lock().unwrap() =
+                    data[snapshots_start].min(NUM_SNAPSHOTS as u8 - 1);
+                *self.snapshots.lock().unwrap() = snapshots.map(|snapshot| {
+                    snapshot.map(|values| {
+                        self.parameters
+                            .iter()
+                            .zip(values)
+                            .map(|(param, value)| restored_value(param, Some(value)))
+                            .collect()
+                    })
+                });
+
+                let trim_start = snapshots_start + 1 + snapshots_len;
+                if let (true, Some(bytes)) = (data[4] >= 5, data.get(trim_start..trim_start + 4)) {
+                    let stored = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    if stored.is_finite() {
+                        output_trim = stored.clamp(-MAX_OUTPUT_TRIM_DB, MAX_OUTPUT_TRIM_DB);
+                    }
+                }
+            }
+        }
+        self.output_trim.store(output_trim);
+
+        // The trim is applied along with the parameters, so that no audio is processed with only
+        // one of them changed.
+        self.update_parameters(
+            &values,
+            Some(StateUpdate::SetOutputTrim(output_trim)),
+            StateUpdate::Crossfade,
+        );
+    }
+}
+
+/// Returns the value of `param` stored in a preset chunk, clamped to the range from 0 to 1 and
+/// rounded to one of its steps, or its default if the value is missing or not finite.
+fn restored_value(param: &ParamDescriptor, stored: Option<f32>) -> f32 {
+    match stored {
+        Some(value) if value.is_finite() => param.quantize(value.clamp(0., 1.)),
+        _ => param.default,
+    }
+}
+
+/// Reads every snapshot, each stored as a little-endian `u16` count followed by that many `f32`
+/// parameter values. A count of 0 represents an empty slot. Returns the snapshots along with the
+/// number of bytes they took up, or `None` if `data` is too short.
+fn read_snapshots(mut data: &[u8]) -> Option<([Option<Vec<f32>>; NUM_SNAPSHOTS], usize)> {
+    let total_len = data.len();
+    let mut snapshots: [Option<Vec<f32>>; NUM_SNAPSHOTS] = Default::default();
+    for snapshot in snapshots.iter_mut() {
+        let count = u16::from_le_bytes([*data.first()?, *data.get(1)?]) as usize;
+        let values = data.get(2..2 + count * 4)?;
+        if count > 0 {
+            *snapshot = Some(
+                values
+                    .chunks_exact(4)
+                    .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect(),
+            );
+        }
+        data = &data[2 + count * 4..];
+    }
+    Some((snapshots, total_len - data.len()))
+}
+
+/// Identifies preset chunks created by this plugin.
+const PRESET_MAGIC: &[u8; 4] = b"AmFe";
+/// Incremented whenever the layout of preset chunks changes. Version 2 added the editor window
+/// size, version 3 added the editor theme, version 4 added the A/B snapshots, and version 5 added
+/// the output trim.
+const PRESET_VERSION: u8 = 5;
+/// Preset chunks start with the magic bytes, a version byte, and a little-endian `u16` count of
+/// the `f32` parameter values that follow. The values are followed by the `u32` width and height
+/// of the editor window, a byte holding the index of the editor theme, a byte holding the index of
+/// the active snapshot, each snapshot as read by `read_snapshots`, and finally the `f32` output
+/// trim in decibels.
+const PRESET_HEADER_LEN: usize = 7;
+
+/// The DAW directly accesses the plugin state through the VST API to get reports on knob states.
+///
+/// Hosts may query indices outside of the parameter range. These are ignored, or answered with
+/// empty values.
+impl PluginParameters for PluginState {
+    /// Values of stepped parameters are rounded to their nearest step, so that the host shows
+    /// the value that's actually used.
+    fn set_parameter(&self, index: i32, value: f32) {
+        log::debug!("setting parameter {} to {}", index, value);
+        if let Some(param) = self.descriptor(index) {
+            let value = param.quantize(value);
+            self.update_parameter(index as usize, value);
+            // The latency only depends on the oversampling factor, so the host can be told about
+            // it straight away, even if the editor isn't open and processing doesn't restart.
+            if index as usize == OVERSAMPLING {
+                self.set_latency(oversampling_latency(oversampling_factor(value)));
+                self.report_latency_change();
+            }
+        }
     }
 
     fn get_parameter(&self, index: i32) -> f32 {
-        self.state_record.lock().unwrap()[index as usize]
+        match self.descriptor(index) {
+            Some(_) => self.state_record[index as usize].load(),
+            None => 0.,
+        }
     }
 
     fn get_parameter_label(&self, index: i32) -> String {
-        match index {
-            0 => "x".to_string(),
-            _ => unreachable!(),
-        }
+        self.descriptor(index)
+            .map(|param| param.label)
+            .unwrap_or_default()
+            .to_string()
     }
 
     fn get_parameter_text(&self, index: i32) -> String {
-        match index {
-            0 => format!(
-                "{:.2}",
-                self.state_record.lock().unwrap()[index as usize] * 2.
-            ),
-            _ => unreachable!(),
+        match self.descriptor(index) {
+            Some(param) => (param.format)(self.state_record[index as usize].load()),
+            None => String::new(),
         }
     }
 
     fn get_parameter_name(&self, index: i32) -> String {
-        match index {
-            0 => "Amplitude",
-            _ => unreachable!(),
-        }
-        .to_string()
+        self.descriptor(index)
+            .map(|param| param.name)
+            .unwrap_or_default()
+            .to_string()
     }
 
     fn string_to_parameter(&self, index: i32, text: String) -> bool {
-        dbg!("Set string to parameter for {}, {}", index, &text);
-        match index {
-            0 => match text.parse::<f32>() {
-                Ok(value) if value <= 2. && value >= 0. => {
-                    self.set_parameter(index, value / 2.);
-                    true
-                }
-                _ => false,
-            },
-            _ => unreachable!(),
+        log::debug!("setting parameter {} from text {:?}", index, text);
+        match self
+            .descriptor(index)
+            .and_then(|param| (param.parse)(&text))
+        {
+            Some(value) => {
+                self.set_parameter(index, value);
+                true
+            }
+            None => false,
         }
     }
+
+    fn get_preset_data(&self) -> Vec<u8> {
+        self.serialize()
+    }
+
+    fn get_bank_data(&self) -> Vec<u8> {
+        self.serialize()
+    }
+
+    fn load_preset_data(&self, data: &[u8]) {
+        self.deserialize(data)
+    }
+
+    fn load_bank_data(&self, data: &[u8]) {
+        self.deserialize(data)
+    }
 }
 
 /// The editor interface also directly accesses the plugin state through its own API.
 impl crate::editor::EditorRemoteState for PluginState {
-    fn set_amplitude_control(&self, value: f32) {
-        self.state_record.lock().unwrap()[0] = value;
+    fn set_parameter_control(&self, index: usize, value: f32) {
+        self.state_record[index].store(value);
 
-        self.to_dsp
-            .lock()
-            .unwrap()
-            .send(StateUpdate::SetKnob(value))
-            .unwrap();
+        self.send_to_dsp((self.parameters[index].to_update)(value));
 
-        self.host.automate(0, value);
+        self.automate(index, value);
     }
 
+    fn begin_parameter_edit(&self, index: usize) {
+        self.host_opcode(host::OpCode::BeginEdit, index as i32, 0);
+    }
+
+    fn end_parameter_edit(&self, index: usize) {
+        self.host_opcode(host::OpCode::EndEdit, index as i32, 0);
+    }
+
+    /// When subscribing, the current value of every parameter and the output trim are sent to the
+    /// editor straight away. The editor reads its initial state as it opens, but updates left in
+    /// the channel by a previously opened editor would otherwise be applied on top of it, so this
+    /// makes sure that the most recent state is applied last.
     fn set_event_subscription(&self, enabled: bool) {
         self.editor_is_open.store(enabled, Ordering::Relaxed);
+        if enabled {
+            let updates = self
+                .parameter_values()
+                .into_iter()
+                .zip(self.parameters.iter())
+                .map(|(value, param)| (param.to_update)(value))
+                .chain(Some(StateUpdate::SetOutputTrim(self.output_trim.load())))
+                .collect();
+            self.to_editor
+                .lock()
+                .unwrap()
+                .send(StateUpdate::Batch(updates))
+                .unwrap();
+        }
+    }
+
+    fn resize_editor_window(&self, size: (u32, u32)) {
+        *self.window_size.lock().unwrap() = size;
+        let (width, height) = physical_size(size, self.scale_factor());
+        self.host_opcode(host::OpCode::SizeWindow, width as i32, height as isize);
+    }
+
+    fn window_size(&self) -> (u32, u32) {
+        *self.window_size.lock().unwrap()
+    }
+
+    fn scale_factor(&self) -> f32 {
+        *self.scale_factor.lock().unwrap()
+    }
+
+    fn set_theme(&self, index: usize) {
+        *self.theme.lock().unwrap() = index as u8;
+    }
+
+    fn theme(&self) -> usize {
+        *self.theme.lock().unwrap() as usize
+    }
+
+    fn reset_all_parameters(&self) -> Vec<f32> {
+        let previous_values = self.parameter_values();
+        PluginState::reset_all_parameters(self);
+        previous_values
+    }
+
+    fn set_all_parameters(&self, values: &[f32]) {
+        PluginState::set_all_parameters(self, values)
+    }
+
+    fn store_snapshot(&self, slot: usize) {
+        PluginState::store_snapshot(self, slot)
+    }
+
+    fn recall_snapshot(&self, slot: usize) {
+        PluginState::recall_snapshot(self, slot)
+    }
+
+    fn active_snapshot(&self) -> usize {
+        *self.active_snapshot.lock().unwrap() as usize
+    }
+
+    fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::Relaxed);
+        self.send_to_dsp(StateUpdate::SetMute(muted));
+    }
+
+    fn muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
+    fn set_output_trim(&self, db: f32) {
+        PluginState::set_output_trim(self, db)
+    }
+
+    fn output_trim(&self) -> f32 {
+        self.output_trim.load()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::plugin_parameters;
+    use std::sync::mpsc::{channel, Receiver};
+
+    fn test_state() -> (PluginState, Receiver<StateUpdate>, Receiver<StateUpdate>) {
+        let (to_dsp, dsp_recv) = channel();
+        let (to_editor, editor_recv) = channel();
+        // Priority updates arrive in the same place as any others, since their order is only
+        // significant to the audio processing thread.
+        let state = PluginState::new(
+            HostCallback::default(),
+            plugin_parameters(),
+            to_dsp.clone(),
+            to_dsp,
+            to_editor,
+        );
+        (state, dsp_recv, editor_recv)
+    }
+
+    #[test]
+    fn dsp_reports_keep_peaks_and_brief_clipping() {
+        let (notifier, mut reader) = EditorNotifier::open();
+        let poll = |reader: &mut DspReportReader| {
+            let mut updates = Vec::new();
+            reader.poll(|update| updates.push(update));
+            updates
+        };
+        notifier.report_meter_levels([0.5, 0.25]);
+        notifier.report_meter_levels([0.25, 1.]);
+        notifier.report_input_clipping(true);
+        notifier.report_input_clipping(false);
+        match &poll(&mut reader)[..] {
+            [StateUpdate::MeterLevels(levels), StateUpdate::InputClipping(true), StateUpdate::InputClipping(false)] =>
+            {
+                assert_eq!(*levels, [0.5, 1.])
+            }
+            _ => panic!("unexpected reports"),
+        }
+        // Nothing new has been reported.
+        assert!(poll(&mut reader).is_empty());
+    }
+
+    #[test]
+    fn scope_batches_are_read_in_order() {
+        let (notifier, mut reader) = EditorNotifier::open();
+        let read_batches = |reader: &mut DspReportReader| {
+            let mut firsts = Vec::new();
+            reader.poll(|update| {
+                if let StateUpdate::ScopeSamples(batch) = update {
+                    firsts.push(batch[0]);
+                }
+            });
+            firsts
+        };
+        for index in 0..3 {
+            notifier.report_scope_samples([index as f32; SCOPE_BATCH_LEN]);
+        }
+        assert_eq!(read_batches(&mut reader), vec![0., 1., 2.]);
+
+        // Once the editor falls behind by a full ring, only the most recent batches are kept.
+        for index in 0..SCOPE_RING_BATCHES * 2 {
+            notifier.report_scope_samples([index as f32; SCOPE_BATCH_LEN]);
+        }
+        let firsts = read_batches(&mut reader);
+        assert_eq!(firsts.len(), SCOPE_RING_BATCHES - 1);
+        assert_eq!(firsts.last(), Some(&((SCOPE_RING_BATCHES * 2 - 1) as f32)));
+        assert!(firsts.windows(2).all(|pair| pair[1] == pair[0] + 1.));
+    }
+
+    #[test]
+    fn latency_is_reported_only_when_asked() {
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.set_latency(12);
+        assert_eq!(state.reported_latency.load(Ordering::Relaxed), 0);
+        state.report_latency_change();
+        assert_eq!(state.reported_latency.load(Ordering::Relaxed), 12);
+    }
+
+    #[test]
+    fn stepped_parameters_are_quantized() {
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        let saturation = state
+            .parameters
+            .iter()
+            .position(|param| param.name == "Saturation")
+            .unwrap() as i32;
+        state.set_parameter(saturation, 0.4);
+        assert_eq!(state.get_parameter(saturation), 0.5);
+        assert_eq!(state.get_parameter_text(saturation), "Hard");
+        assert!(state.string_to_parameter(saturation, "soft".to_string()));
+        assert_eq!(state.get_parameter(saturation), 1.);
+        assert!(!state.string_to_parameter(saturation, "medium".to_string()));
+
+        // Continuous parameters are left alone.
+        state.set_parameter(0, 0.4);
+        assert_eq!(state.get_parameter(0), 0.4);
+    }
+
+    #[test]
+    fn subscribing_sends_current_state_to_editor() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, editor_recv) = test_state();
+        state.set_parameter(0, 0.3);
+        assert!(editor_recv.try_recv().is_err());
+
+        state.set_event_subscription(true);
+        match editor_recv.try_recv() {
+            Ok(StateUpdate::Batch(updates)) => {
+                assert_eq!(updates.len(), state.num_parameters() + 1);
+                assert!(matches!(updates[0], StateUpdate::SetKnob(value) if value == 0.3));
+            }
+            _ => panic!("expected a batch of the current state"),
+        }
+
+        state.set_event_subscription(false);
+        assert!(editor_recv.try_recv().is_err());
+    }
+
+    #[test]
+    fn out_of_range_indices_are_ignored() {
+        let (state, dsp_recv, _editor_recv) = test_state();
+        let before = state.parameter_values();
+        for &index in &[state.num_parameters() as i32, 100, -1, i32::MIN] {
+            state.set_parameter(index, 0.5);
+            assert_eq!(state.get_parameter(index), 0.);
+            assert_eq!(state.get_parameter_text(index), "");
+            assert_eq!(state.get_parameter_name(index), "");
+            assert_eq!(state.get_parameter_label(index), "");
+            assert!(!state.string_to_parameter(index, "0.5".to_string()));
+        }
+        assert_eq!(state.parameter_values(), before);
+        assert!(dsp_recv.try_recv().is_err());
+    }
+
+    #[test]
+    fn preset_round_trip() {
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.set_parameter(0, 0.25);
+        state.set_parameter(1, 0.75);
+        let data = state.get_preset_data();
+
+        let (restored, dsp_recv, _editor_recv) = test_state();
+        restored.load_preset_data(&data);
+        assert_eq!(restored.get_parameter(0), 0.25);
+        assert_eq!(restored.get_parameter(1), 0.75);
+        // Every parameter is passed in a single crossfade, including the amplitude, along with the
+        // output trim.
+        let messages: Vec<StateUpdate> = dsp_recv.try_iter().collect();
+        assert!(matches!(
+            messages.as_slice(),
+            [StateUpdate::Crossfade(updates)] if updates.len() == restored.num_parameters() + 1
+        ));
+        assert_eq!(restored.dsp_knob_slot().take(), None);
+    }
+
+    #[test]
+    fn preset_with_fewer_parameters_uses_defaults() {
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.set_parameter(1, 0.1);
+
+        let mut data = PRESET_MAGIC.to_vec();
+        data.push(PRESET_VERSION);
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&0.8f32.to_le_bytes());
+        state.load_preset_data(&data);
+
+        assert_eq!(state.get_parameter(0), 0.8);
+        assert_defaults_from(&state, 1);
+    }
+
+    #[test]
+    fn editor_settings_round_trip() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        assert_eq!(state.window_size(), DEFAULT_WINDOW_SIZE);
+        state.resize_editor_window((900, 600));
+        state.set_theme(1);
+        let data = state.get_preset_data();
+
+        let (restored, _dsp_recv, _editor_recv) = test_state();
+        restored.load_preset_data(&data);
+        assert_eq!(restored.window_size(), (900, 600));
+        assert_eq!(restored.theme(), 1);
+    }
+
+    #[test]
+    fn out_of_range_window_size_is_not_restored() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.resize_editor_window((900, 600));
+        let data = state.get_preset_data();
+        let window_size_start = PRESET_HEADER_LEN + state.num_parameters() * 4;
+        for size in [(0, 0), (100_000, 600), (900, 10), (u32::MAX, u32::MAX)] {
+            let mut data = data.clone();
+            data[window_size_start..window_size_start + 4].copy_from_slice(&size.0.to_le_bytes());
+            data[window_size_start + 4..window_size_start + 8]
+                .copy_from_slice(&size.1.to_le_bytes());
+
+            let (restored, _dsp_recv, _editor_recv) = test_state();
+            restored.resize_editor_window((1200, 800));
+            restored.load_preset_data(&data);
+            assert_eq!(restored.window_size(), DEFAULT_WINDOW_SIZE);
+        }
+    }
+
+    #[test]
+    fn version_1_preset_keeps_window_size() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        let mut data = PRESET_MAGIC.to_vec();
+        data.push(1);
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&0.8f32.to_le_bytes());
+        state.load_preset_data(&data);
+
+        assert_eq!(state.get_parameter(0), 0.8);
+        assert_eq!(state.window_size(), DEFAULT_WINDOW_SIZE);
+    }
+
+    /// Asserts that every parameter of `state` from `first` onwards has its default value.
+    fn assert_defaults_from(state: &PluginState, first: usize) {
+        for index in first..state.num_parameters() {
+            assert_eq!(
+                state.get_parameter(index as i32),
+                state.parameters[index].default
+            );
+        }
+    }
+
+    // The following chunks are written out byte by byte, rather than with `serialize`, so that
+    // they keep testing the layouts that older versions of the plugin actually saved.
+
+    #[test]
+    fn version_1_chunk_is_migrated() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.resize_editor_window((900, 600));
+        let data = [
+            b'A', b'm', b'F', b'e', // magic
+            1,    // version
+            1, 0, // parameter count
+            0xcd, 0xcc, 0x4c, 0x3f, // amplitude: 0.8
+        ];
+        state.load_preset_data(&data);
+
+        assert_eq!(state.get_parameter(0), 0.8);
+        assert_defaults_from(&state, 1);
+        assert_eq!(state.window_size(), (900, 600));
+        assert_eq!(state.theme(), 0);
+        assert_eq!(state.active_snapshot(), 0);
+        assert_eq!(state.output_trim(), 0.);
+    }
+
+    #[test]
+    fn version_3_chunk_is_migrated() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        let data = [
+            b'A', b'm', b'F', b'e', // magic
+            3,    // version
+            3, 0, // parameter count
+            0x00, 0x00, 0x80, 0x3e, // amplitude: 0.25
+            0x00, 0x00, 0x40, 0x3f, // width: 0.75
+            0x00, 0x00, 0x80, 0x3f, // mix: 1
+            0x84, 0x03, 0x00, 0x00, // window width: 900
+            0x58, 0x02, 0x00, 0x00, // window height: 600
+            1,    // theme
+        ];
+        state.load_preset_data(&data);
+
+        assert_eq!(state.get_parameter(0), 0.25);
+        assert_eq!(state.get_parameter(1), 0.75);
+        assert_eq!(state.get_parameter(2), 1.);
+        assert_defaults_from(&state, 3);
+        assert_eq!(state.window_size(), (900, 600));
+        assert_eq!(state.theme(), 1);
+    }
+
+    #[test]
+    fn version_5_chunk_is_read() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        let data = [
+            b'A', b'm', b'F', b'e', // magic
+            5,    // version
+            1, 0, // parameter count
+            0x00, 0x00, 0x00, 0x3f, // amplitude: 0.5
+            0x84, 0x03, 0x00, 0x00, // window width: 900
+            0x58, 0x02, 0x00, 0x00, // window height: 600
+            0,    // theme
+            1,    // active snapshot
+            1, 0, 0x00, 0x00, 0x80, 0x3e, // snapshot A: amplitude 0.25
+            0, 0, // snapshot B: empty
+            0x00, 0x00, 0xc0, 0xc0, // output trim: -6dB
+        ];
+        state.load_preset_data(&data);
+
+        assert_eq!(state.get_parameter(0), 0.5);
+        assert_defaults_from(&state, 1);
+        assert_eq!(state.active_snapshot(), 1);
+        assert_eq!(state.output_trim(), -6.);
+        state.recall_snapshot(0);
+        assert_eq!(state.get_parameter(0), 0.25);
+
+        // A chunk cut off before the output trim still restores everything before it.
+        state.load_preset_data(&data[..data.len() - 4]);
+        assert_eq!(state.get_parameter(0), 0.5);
+        assert_eq!(state.output_trim(), 0.);
+    }
+
+    #[test]
+    fn unrecognized_chunks_are_ignored() {
+        let (state, dsp_recv, _editor_recv) = test_state();
+        state.set_parameter(0, 0.3);
+        let newer_version = [b'A', b'm', b'F', b'e', 6, 1, 0, 0x00, 0x00, 0x00, 0x3f];
+        let wrong_magic = [b'A', b'm', b'F', b'x', 1, 1, 0, 0x00, 0x00, 0x00, 0x3f];
+        let truncated_header = [b'A', b'm', b'F', b'e', 1, 1];
+        for data in [&newer_version[..], &wrong_magic, &truncated_header, &[]] {
+            state.load_preset_data(data);
+        }
+        assert_eq!(state.get_parameter(0), 0.3);
+        assert_eq!(dsp_recv.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn current_chunks_start_with_header() {
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        let data = state.get_preset_data();
+        assert_eq!(&data[..4], PRESET_MAGIC);
+        assert_eq!(data[4], PRESET_VERSION);
+        assert_eq!(
+            u16::from_le_bytes([data[5], data[6]]) as usize,
+            state.num_parameters()
+        );
+    }
+
+    #[test]
+    fn reset_restores_defaults() {
+        let (state, dsp_recv, _editor_recv) = test_state();
+        for index in 0..state.num_parameters() {
+            state.set_parameter(index as i32, 0.9);
+        }
+        state.reset_all_parameters();
+        for index in 0..state.num_parameters() {
+            assert_eq!(
+                state.get_parameter(index as i32),
+                state.parameters[index].default
+            );
+        }
+        // The amplitude set beforehand is replaced by the batch, rather than being left in the
+        // knob slot to override it.
+        assert_eq!(dsp_recv.try_iter().count(), state.num_parameters());
+        assert_eq!(state.dsp_knob_slot().take(), None);
+    }
+
+    #[test]
+    fn knob_updates_are_coalesced() {
+        let (state, dsp_recv, _editor_recv) = test_state();
+        let slot = state.dsp_knob_slot();
+        for step in 0..100 {
+            state.set_parameter(0, step as f32 / 100.);
+        }
+        assert_eq!(dsp_recv.try_iter().count(), 0);
+        assert_eq!(slot.take(), Some(0.99));
+        assert_eq!(slot.take(), None);
+    }
+
+    #[test]
+    fn snapshots_survive_presets() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.set_parameter(0, 0.2);
+        state.store_snapshot(0);
+        state.set_parameter(0, 0.7);
+        state.store_snapshot(1);
+        let data = state.get_preset_data();
+
+        let (restored, _dsp_recv, _editor_recv) = test_state();
+        restored.load_preset_data(&data);
+        assert_eq!(restored.active_snapshot(), 1);
+        restored.recall_snapshot(0);
+        assert_eq!(restored.get_parameter(0), 0.2);
+        assert_eq!(restored.active_snapshot(), 0);
+        restored.recall_snapshot(1);
+        assert_eq!(restored.get_parameter(0), 0.7);
+    }
+
+    #[test]
+    fn invalid_values_are_not_restored() {
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.set_parameter(1, 0.3);
+        let mut values: Vec<f32> = (0..state.num_parameters())
+            .map(|index| state.get_parameter(index as i32))
+            .collect();
+        values[..3].copy_from_slice(&[f32::NAN, 2., -1.]);
+        state.snapshots.lock().unwrap()[0] = Some(values);
+        let mut data = state.get_preset_data();
+        // Corrupt the first parameter's value, stored after the header, too.
+        data[PRESET_HEADER_LEN..PRESET_HEADER_LEN + 4]
+            .copy_from_slice(&f32::INFINITY.to_le_bytes());
+
+        let (restored, _dsp_recv, _editor_recv) = test_state();
+        restored.load_preset_data(&data);
+        assert_eq!(
+            restored.get_parameter(0),
+            crate::parameters::AMPLITUDE_DEFAULT
+        );
+        assert_eq!(restored.get_parameter(1), 0.3);
+        restored.recall_snapshot(0);
+        assert_eq!(
+            restored.get_parameter(0),
+            crate::parameters::AMPLITUDE_DEFAULT
+        );
+        assert_eq!(restored.get_parameter(1), 1.);
+        assert_eq!(restored.get_parameter(2), 0.);
+    }
+
+    #[test]
+    fn output_trim_round_trip() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.set_output_trim(-3.5);
+        state.store_snapshot(0);
+        let data = state.get_preset_data();
+
+        let (restored, dsp_recv, _editor_recv) = test_state();
+        restored.load_preset_data(&data);
+        assert_eq!(restored.output_trim(), -3.5);
+        assert!(dsp_recv.try_iter().any(|update| matches!(
+            update,
+            StateUpdate::Crossfade(updates) if updates.iter().any(
+                |update| matches!(update, StateUpdate::SetOutputTrim(db) if *db == -3.5)
+            )
+        )));
+        // The trim isn't one of the parameters exposed to the host.
+        assert_eq!(restored.num_parameters(), plugin_parameters().len());
+
+        // Presets from before the trim was added leave the output untrimmed.
+        let mut old_data = data.clone();
+        old_data[4] = 4;
+        old_data.truncate(data.len() - 4);
+        restored.load_preset_data(&old_data);
+        assert_eq!(restored.output_trim(), 0.);
+
+        restored.set_output_trim(100.);
+        assert_eq!(restored.output_trim(), MAX_OUTPUT_TRIM_DB);
+        restored.set_output_trim(f32::NAN);
+        assert_eq!(restored.output_trim(), MAX_OUTPUT_TRIM_DB);
+    }
+
+    #[test]
+    fn recalling_empty_snapshot_keeps_parameters() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.set_parameter(0, 0.2);
+        state.recall_snapshot(1);
+        assert_eq!(state.get_parameter(0), 0.2);
+        assert_eq!(state.active_snapshot(), 1);
+    }
+
+    #[test]
+    fn mute_is_sent_through_priority_channel() {
+        use crate::editor::EditorRemoteState;
+
+        let (to_dsp, dsp_recv) = channel();
+        let (priority_to_dsp, priority_dsp_recv) = channel();
+        let state = PluginState::new(
+            HostCallback::default(),
+            plugin_parameters(),
+            to_dsp,
+            priority_to_dsp,
+            channel().0,
+        );
+        state.set_parameter(0, 0.2);
+        state.set_muted(true);
+        assert!(matches!(
+            priority_dsp_recv.try_iter().collect::<Vec<_>>()[..],
+            [StateUpdate::SetMute(true)]
+        ));
+        assert!(dsp_recv.try_iter().all(|update| !update.is_time_critical()));
+    }
+
+    #[test]
+    fn invalid_scale_factors_are_ignored() {
+        use crate::editor::EditorRemoteState;
+
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.set_scale_factor(2.);
+        for &scale_factor in &[0., -1., f32::NAN, f32::INFINITY] {
+            state.set_scale_factor(scale_factor);
+        }
+        assert_eq!(state.scale_factor(), 2.);
+    }
+
+    #[test]
+    fn atomic_values_keep_their_bits() {
+        for &value in &[
+            0.,
+            -0.,
+            0.5,
+            f32::NAN,
+            f32::INFINITY,
+            f32::MIN_POSITIVE / 2.,
+        ] {
+            let atomic = AtomicF32::new(1.);
+            atomic.store(value);
+            assert_eq!(atomic.load().to_bits(), value.to_bits());
+        }
+    }
+
+    #[test]
+    fn invalid_preset_is_ignored() {
+        let (state, _dsp_recv, _editor_recv) = test_state();
+        state.set_parameter(0, 0.3);
+        state.load_preset_data(b"garbage");
+        state.load_preset_data(&[]);
+        assert_eq!(state.get_parameter(0), 0.3);
     }
 }
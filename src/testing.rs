@@ -0,0 +1,99 @@
+//! A minimal Rust API for driving the plugin without going through the VST C ABI, for end-to-end
+//! tests of its behavior. Only built with the `testing` feature.
+//!
+//! ```ignore
+//! let mut plugin = ampli_fe::testing::TestPlugin::new();
+//! plugin.set_parameter(0, 1.);
+//! let mut output = vec![0.; 64];
+//! plugin.process(&[&[0.5; 64]], &mut [&mut output]);
+//! ```
+
+use vst::plugin::{Plugin, PluginParameters};
+
+use crate::AmpliFeVst;
+
+/// A plugin instance that isn't connected to any host.
+pub struct TestPlugin {
+    plugin: AmpliFeVst,
+}
+
+impl TestPlugin {
+    /// Creates a plugin instance with the same configuration that a host would get, processing
+    /// audio at 44.1kHz until told otherwise.
+    pub fn new() -> Self {
+        Self {
+            plugin: AmpliFeVst::default(),
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, rate: f32) {
+        self.plugin.set_sample_rate(rate);
+    }
+
+    /// Number of parameters exposed to hosts.
+    pub fn num_parameters(&self) -> usize {
+        self.plugin.state_handle.num_parameters()
+    }
+
+    /// Sets the parameter at `index` to a value from 0 to 1, as host automation would.
+    pub fn set_parameter(&self, index: usize, value: f32) {
+        self.plugin.state_handle.set_parameter(index as i32, value);
+    }
+
+    /// Current value of the parameter at `index`, from 0 to 1.
+    pub fn get_parameter(&self, index: usize) -> f32 {
+        self.plugin.state_handle.get_parameter(index as i32)
+    }
+
+    /// Text displayed by hosts for the current value of the parameter at `index`.
+    pub fn get_parameter_text(&self, index: usize) -> String {
+        self.plugin.state_handle.get_parameter_text(index as i32)
+    }
+
+    /// Processes a block of audio, with one slice of samples for each input and output channel.
+    /// Only as many channels and samples as are present in both `inputs` and `outputs` are
+    /// processed.
+    pub fn process(&mut self, inputs: &[&[f32]], outputs: &mut [&mut [f32]]) {
+        self.plugin.dsp.process_slices(inputs, outputs);
+        self.plugin.record_latency();
+    }
+
+    /// Like `process`, but for samples in double precision, as processed by some hosts.
+    pub fn process_f64(&mut self, inputs: &[&[f64]], outputs: &mut [&mut [f64]]) {
+        self.plugin.dsp.process_slices_f64(inputs, outputs);
+        self.plugin.record_latency();
+    }
+
+    /// Number of samples by which the processed output lags behind its input.
+    pub fn latency_samples(&self) -> usize {
+        self.plugin.dsp.latency_samples()
+    }
+}
+
+impl Default for TestPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parameters_affect_processed_audio() {
+        let mut plugin = TestPlugin::new();
+        // Quarter gain, with no smoothing.
+        plugin.set_parameter(crate::parameters::AMPLITUDE, 0.125);
+        plugin.set_parameter(5, 0.);
+        assert_eq!(plugin.get_parameter(crate::parameters::AMPLITUDE), 0.125);
+
+        // Even without smoothing, the first block ramps to the new gain, while the output fades in
+        // from silence.
+        let mut output = vec![0.; 512];
+        plugin.process(&[&[0.; 512]], &mut [&mut output]);
+        let mut output = vec![0.; 64];
+        plugin.process(&[&[1.; 64]], &mut [&mut output]);
+        assert!(output.iter().all(|sample| (sample - 0.25).abs() < 1e-6));
+    }
+}